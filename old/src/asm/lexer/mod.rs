@@ -54,6 +54,12 @@ pub enum TokenKind {
     KeywordShiftLeft,
     KeywordReturn,
     KeywordSyscall,
+    KeywordJump,
+    KeywordNe,
+    KeywordLt,
+    KeywordLe,
+    KeywordPeek,
+    KeywordPoke,
     Number(u64),
     Identifier(String),
     Colon,
@@ -219,6 +225,12 @@ impl<'a> Lexer<'a> {
             "shr" => TokenKind::KeywordShiftLeft,
             "return" => TokenKind::KeywordReturn,
             "syscall" => TokenKind::KeywordSyscall,
+            "jump" => TokenKind::KeywordJump,
+            "ne" => TokenKind::KeywordNe,
+            "lt" => TokenKind::KeywordLt,
+            "le" => TokenKind::KeywordLe,
+            "peek" => TokenKind::KeywordPeek,
+            "poke" => TokenKind::KeywordPoke,
             _ => TokenKind::Identifier(identifier),
         };
         let span = self.span();
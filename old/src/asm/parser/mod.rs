@@ -427,6 +427,12 @@ impl Parser {
                 TokenKind::KeywordShiftLeft => self.parse_reg_3(token, Opcode::Shr),
                 TokenKind::KeywordReturn => self.parse_no_args(token, Opcode::Return),
                 TokenKind::KeywordSyscall => self.parse_no_args(token, Opcode::Syscall),
+                TokenKind::KeywordJump => self.parse_reg_0_label(token, Opcode::Jump),
+                TokenKind::KeywordNe => self.parse_reg_3(token, Opcode::Ne),
+                TokenKind::KeywordLt => self.parse_reg_3(token, Opcode::Lt),
+                TokenKind::KeywordLe => self.parse_reg_3(token, Opcode::Le),
+                TokenKind::KeywordPeek => self.parse_reg_imm(token, Opcode::Peek),
+                TokenKind::KeywordPoke => self.parse_reg_imm(token, Opcode::Poke),
                 TokenKind::Identifier(_) if matches!(self.peek_kind(), Some(&TokenKind::Colon)) => {
                     self.label = Some(token);
                     let _ = self.consume(&[TokenKind::Colon], ParserError::ExpectedColon)?;
@@ -125,6 +125,18 @@ pub enum Opcode {
     Shr,
     Return,
     Syscall,
+    Jump,
+    Ne,
+    /// Signed for `Type::I` and unsigned for `Type::U`
+    Lt,
+    /// Signed for `Type::I` and unsigned for `Type::U`
+    Le,
+    /// Copies the value the immediate counts down from the top of the stack into the register,
+    /// 0 is the top
+    Peek,
+    /// Overwrites the value the immediate counts down from the top of the stack with the
+    /// register
+    Poke,
 }
 
 impl TryFrom<u8> for Opcode {
@@ -153,6 +165,12 @@ impl TryFrom<u8> for Opcode {
             18 => Ok(Opcode::Shr),
             19 => Ok(Opcode::Return),
             20 => Ok(Opcode::Syscall),
+            21 => Ok(Opcode::Jump),
+            22 => Ok(Opcode::Ne),
+            23 => Ok(Opcode::Lt),
+            24 => Ok(Opcode::Le),
+            25 => Ok(Opcode::Peek),
+            26 => Ok(Opcode::Poke),
             _ => Err(BitBoxError::InvalidOpcode(value)),
         }
     }
@@ -198,6 +216,18 @@ impl Type {
             Type::Void => 0,
         }
     }
+
+    /// The register `value` as a number of this type, so signed values compare as signed
+    pub fn comparable(&self, value: u64) -> i128 {
+        match self {
+            Type::I(num) if (1..64).contains(num) => {
+                let shift = 64 - *num as u32;
+                ((value << shift) as i64 >> shift) as i128
+            }
+            Type::I(_) => value as i64 as i128,
+            _ => value as i128,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -77,6 +77,18 @@ impl Vm {
         self.stack.pop().unwrap()
     }
 
+    /// Index of the value the little endian `depth` counts down from the top of the stack
+    pub fn stack_index(&self, Imm(depth): &Imm) -> Result<usize> {
+        let depth = depth
+            .iter()
+            .rev()
+            .fold(0u64, |depth, byte| (depth << 8) | *byte as u64);
+        let Some(index) = (self.stack.len() as u64).checked_sub(depth + 1) else {
+            bail!(BitBoxError::StackUnderflow);
+        };
+        Ok(index as usize)
+    }
+
     pub fn set_heap_u8(&mut self, dest: u64, value: u8) {
         self.heap[dest as usize] = value
     }
@@ -131,6 +143,12 @@ impl Vm {
             Opcode::Shr => self.opcode_3reg(Opcode::Shr)?,
             Opcode::Return => self.opcode_noargs(Opcode::Return)?,
             Opcode::Syscall => self.opcode_noargs(Opcode::Syscall)?,
+            Opcode::Jump => self.opcode_label(Opcode::Jump)?,
+            Opcode::Ne => self.opcode_3reg(Opcode::Ne)?,
+            Opcode::Lt => self.opcode_3reg(Opcode::Lt)?,
+            Opcode::Le => self.opcode_3reg(Opcode::Le)?,
+            Opcode::Peek => self.opcode_1reg_imm(Opcode::Peek)?,
+            Opcode::Poke => self.opcode_1reg_imm(Opcode::Poke)?,
         }
         Ok(())
     }
@@ -532,6 +550,56 @@ impl Execute for Instruction {
                 }
                 _ => unreachable!("Error for Syscall instruction"),
             },
+            Opcode::Jump => match self.data {
+                Data::Label(Either::Right(value)) => {
+                    vm.pc = value as usize;
+                    Ok(())
+                }
+                _ => unreachable!("Error for Jump instruction"),
+            },
+            Opcode::Ne => match self.data {
+                Data::Reg3(des, reg_lhs, reg_rhs) => {
+                    let lhs = vm.get_regester(reg_lhs as u8);
+                    let rhs = vm.get_regester(reg_rhs as u8);
+                    vm.set_regester(des as u8, (lhs != rhs) as u64);
+                    Ok(())
+                }
+                _ => unreachable!("Error for Ne instruction"),
+            },
+            Opcode::Lt => match self.data {
+                Data::Reg3(des, reg_lhs, reg_rhs) => {
+                    let lhs = self.r#type.comparable(*vm.get_regester(reg_lhs as u8));
+                    let rhs = self.r#type.comparable(*vm.get_regester(reg_rhs as u8));
+                    vm.set_regester(des as u8, (lhs < rhs) as u64);
+                    Ok(())
+                }
+                _ => unreachable!("Error for Lt instruction"),
+            },
+            Opcode::Le => match self.data {
+                Data::Reg3(des, reg_lhs, reg_rhs) => {
+                    let lhs = self.r#type.comparable(*vm.get_regester(reg_lhs as u8));
+                    let rhs = self.r#type.comparable(*vm.get_regester(reg_rhs as u8));
+                    vm.set_regester(des as u8, (lhs <= rhs) as u64);
+                    Ok(())
+                }
+                _ => unreachable!("Error for Le instruction"),
+            },
+            Opcode::Peek => match &self.data {
+                Data::Imm(reg, depth) => {
+                    let index = vm.stack_index(depth)?;
+                    vm.set_regester(*reg as u8, vm.stack[index]);
+                    Ok(())
+                }
+                _ => unreachable!("Error for Peek instruction"),
+            },
+            Opcode::Poke => match &self.data {
+                Data::Imm(reg, depth) => {
+                    let index = vm.stack_index(depth)?;
+                    vm.stack[index] = *vm.get_regester(*reg as u8);
+                    Ok(())
+                }
+                _ => unreachable!("Error for Poke instruction"),
+            },
         }
     }
 }
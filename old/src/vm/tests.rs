@@ -246,3 +246,87 @@ fn aloc() -> Result<()> {
     assert_eq!(vm.heap.len(), 1);
     Ok(())
 }
+
+vm_test!(
+    ne,
+    r#"
+        load[u32] %0 123
+        load[u32] %1 321
+        ne[u32] %2 %0 %1
+        ne[u32] %3 %0 %0
+    "#,
+    (Register::R0, 123),
+    (Register::R1, 321),
+    (Register::R2, 1),
+    (Register::R3, 0),
+);
+
+vm_test!(
+    lt,
+    r#"
+        load[u8] %0 1
+        load[u8] %1 255
+        lt[u8] %2 %0 %1
+        lt[i8] %3 %0 %1
+        lt[u8] %4 %0 %0
+    "#,
+    (Register::R0, 1),
+    (Register::R1, 255),
+    (Register::R2, 1),
+    (Register::R3, 0),
+    (Register::R4, 0),
+);
+
+vm_test!(
+    le,
+    r#"
+        load[u8] %0 1
+        load[u8] %1 255
+        le[u8] %2 %0 %0
+        le[i8] %3 %1 %0
+        le[u8] %4 %1 %0
+    "#,
+    (Register::R0, 1),
+    (Register::R1, 255),
+    (Register::R2, 1),
+    (Register::R3, 1),
+    (Register::R4, 0),
+);
+
+#[test]
+fn jump() -> Result<()> {
+    let src = r#"
+    .entry main
+    main:
+        jump skip
+        load[u32] %0 1
+    skip:
+        load[u32] %1 2
+        hult
+    "#;
+    let program = asm::assemble(&src)?;
+    let mut vm = Vm::new(program)?;
+    vm.run()?;
+    assert_eq!(vm.get_regester(0), &0);
+    assert_eq!(vm.get_regester(1), &2);
+    Ok(())
+}
+
+vm_test!(
+    peek_poke,
+    r#"
+        load[u32] %0 10
+        load[u32] %1 20
+        push[u32] %0
+        push[u32] %1
+        peek[u32] %2 1
+        poke[u32] %2 0
+        pop[u32] %3
+        pop[u32] %4
+    "#,
+    (Register::R0, 10),
+    (Register::R1, 20),
+    (Register::R2, 10),
+    (Register::R3, 10),
+    (Register::R4, 10),
+);
@@ -0,0 +1,160 @@
+use crate::target::{Emit, Target};
+
+#[cfg(test)]
+mod test;
+
+pub const USAGE: &str = "usage: bitbox [<command>] [<options>] [<filename>|-]
+
+commands:
+    build        compile <filename> next to it with the target's extension, the default
+    check        report errors without compiling
+    run          interpret the program's main function and exit with what it returns,
+                 with --target vm or x86_64 compile it and run it on the vm or natively
+    fmt          format the files in place
+    dump-tokens  print the tokens of the program
+    dump-ir      print the program after it has been checked
+    dump-cfg     print the control flow graph of every function in graphviz dot
+    dump-regalloc
+                 print the vm register allocation of every function
+
+options:
+    -o <path>                    write the output to <path>, `-` for stdout
+    --target <wasm|vm|x86_64>    the backend to compile for, wasm by default
+    --emit <bin|text|asm>        write the binary, a readable form of it or, for x86_64,
+                                 GNU assembler input
+    -O<level>                    reserved optimization level from 0 to 3, -O is -O2. It is
+                                 checked but no optimizations run yet
+    --check                      with fmt, list the files that aren't formatted instead
+    --error-format=<human|json>  how errors are printed
+    --explain <code>             describe an error code
+    -h, --help                   print this message
+
+The program is read from stdin when <filename> is `-` or left out.";
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    #[default]
+    Build,
+    Check,
+    Run,
+    Fmt,
+    DumpTokens,
+    DumpIr,
+    DumpCfg,
+    DumpRegalloc,
+}
+
+impl Command {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "build" => Some(Self::Build),
+            "check" => Some(Self::Check),
+            "run" => Some(Self::Run),
+            "fmt" => Some(Self::Fmt),
+            "dump-tokens" => Some(Self::DumpTokens),
+            "dump-ir" => Some(Self::DumpIr),
+            "dump-cfg" => Some(Self::DumpCfg),
+            "dump-regalloc" => Some(Self::DumpRegalloc),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorFormat {
+    #[default]
+    Human,
+    Json,
+}
+
+#[derive(Debug, Default)]
+pub struct Options {
+    pub command: Command,
+    /// Files to read, `-` is stdin. Only `fmt` takes more than one.
+    pub inputs: Vec<String>,
+    pub output: Option<String>,
+    pub target: Target,
+    pub emit: Emit,
+    /// Reserved, the level is checked but no pass depends on it yet
+    pub opt_level: u8,
+    pub error_format: ErrorFormat,
+    /// `fmt --check`
+    pub check: bool,
+    /// `--explain <code>`, nothing else is done when it is given
+    pub explain: Option<String>,
+    /// `-h` or `--help`
+    pub help: bool,
+}
+
+/// Parses the arguments after the program name, the command is optional and must come first
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options::default();
+    let mut args = args.into_iter().peekable();
+    if let Some(command) = args.peek().and_then(|arg| Command::from_name(arg)) {
+        options.command = command;
+        args.next();
+    }
+
+    while let Some(arg) = args.next() {
+        let mut value = |option: &str| {
+            args.next()
+                .ok_or_else(|| format!("'{option}' expects a value"))
+        };
+        if arg == "-h" || arg == "--help" {
+            options.help = true;
+        } else if arg == "--explain" {
+            options.explain = Some(value(&arg)?);
+        } else if arg == "-o" {
+            options.output = Some(value(&arg)?);
+        } else if arg == "--target" {
+            let name = value(&arg)?;
+            options.target = Target::from_name(&name).ok_or_else(|| {
+                format!(
+                    "unknown target '{name}', expected one of {}",
+                    Target::NAMES.join(", ")
+                )
+            })?;
+        } else if arg == "--emit" {
+            options.emit = match value(&arg)?.as_str() {
+                "bin" => Emit::Bin,
+                "text" => Emit::Text,
+                "asm" => Emit::Asm,
+                other => {
+                    return Err(format!(
+                        "unknown emit kind '{other}', expected bin, text or asm"
+                    ))
+                }
+            };
+        } else if arg == "--check" && options.command == Command::Fmt {
+            options.check = true;
+        } else if let Some(level) = arg.strip_prefix("-O") {
+            options.opt_level = match level {
+                "" => 2,
+                "0" | "1" | "2" | "3" => level.parse().expect("level is a digit"),
+                _ => {
+                    return Err(format!(
+                        "unknown optimization level '{level}', expected 0 to 3"
+                    ))
+                }
+            };
+        } else if let Some(format) = arg.strip_prefix("--error-format=") {
+            options.error_format = match format {
+                "human" => ErrorFormat::Human,
+                "json" => ErrorFormat::Json,
+                _ => return Err(format!("unknown error format '{format}'")),
+            };
+        } else if arg.starts_with('-') && arg != "-" {
+            return Err(format!("unknown option '{arg}'"));
+        } else {
+            options.inputs.push(arg);
+        }
+    }
+
+    if options.emit == Emit::Asm && options.target != Target::X86_64 {
+        return Err("'--emit asm' needs '--target x86_64'".to_string());
+    }
+    if options.inputs.len() > 1 && options.command != Command::Fmt {
+        return Err("expected a single input file".to_string());
+    }
+    Ok(options)
+}
@@ -0,0 +1,67 @@
+use crate::cli::{parse, Command, ErrorFormat};
+use crate::target::{Emit, Target};
+
+fn parse_args(args: &str) -> Result<crate::cli::Options, String> {
+    parse(args.split_whitespace().map(String::from))
+}
+
+#[test]
+fn filename_alone_builds() {
+    let options = parse_args("./dir/a.b.bitbox").expect("valid arguments");
+    assert_eq!(options.command, Command::Build);
+    assert_eq!(options.inputs, ["./dir/a.b.bitbox"]);
+    assert_eq!(options.target, Target::Wasm);
+    assert_eq!(options.emit, Emit::Bin);
+}
+
+#[test]
+fn options_after_command() {
+    let options =
+        parse_args("build -o out.wasm --target wasm --emit text -O3 --error-format=json -")
+            .expect("valid arguments");
+    assert_eq!(options.command, Command::Build);
+    assert_eq!(options.output.as_deref(), Some("out.wasm"));
+    assert_eq!(options.emit, Emit::Text);
+    assert_eq!(options.opt_level, 3);
+    assert_eq!(options.error_format, ErrorFormat::Json);
+    assert_eq!(options.inputs, ["-"]);
+    assert_eq!(parse_args("-O").expect("valid arguments").opt_level, 2);
+}
+
+#[test]
+fn fmt_takes_many_files() {
+    let options = parse_args("fmt --check a.bitbox b.bitbox").expect("valid arguments");
+    assert_eq!(options.command, Command::Fmt);
+    assert!(options.check);
+    assert_eq!(options.inputs, ["a.bitbox", "b.bitbox"]);
+}
+
+#[test]
+fn native_assembly() {
+    let options = parse_args("--emit asm --target x86_64 a.bitbox").expect("valid arguments");
+    assert_eq!(options.target, Target::X86_64);
+    assert_eq!(options.emit, Emit::Asm);
+}
+
+#[test]
+fn dump_regalloc() {
+    let options = parse_args("dump-regalloc a.bitbox").expect("valid arguments");
+    assert_eq!(options.command, Command::DumpRegalloc);
+    assert_eq!(options.inputs, ["a.bitbox"]);
+}
+
+#[test]
+fn invalid_arguments() {
+    for args in [
+        "a.bitbox b.bitbox",
+        "--target nowhere a.bitbox",
+        "--emit pdf a.bitbox",
+        "--emit asm a.bitbox",
+        "-O9 a.bitbox",
+        "check --check a.bitbox",
+        "-o",
+        "--frobnicate",
+    ] {
+        assert!(parse_args(args).is_err(), "{args} should be rejected");
+    }
+}
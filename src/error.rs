@@ -1,4 +1,7 @@
-use crate::lexer::token::{Span, Token, TokenKind};
+pub mod explain;
+#[cfg(test)]
+mod test;
+use crate::lexer::token::{Instruction, Span, Token, TokenKind};
 use std::fmt::Write;
 
 #[derive(Debug)]
@@ -9,67 +12,284 @@ pub enum BitBoxError {
     },
     InvalidContantValue(Token),
     InvalidInstruction(Token),
+    InvalidCondition(Token),
     InvalidToken(Token),
     UnexpectedEndOfStream,
     ExpectedTopLevelItem(Token),
     ExpectedType(Token),
     UndefinedVariable(Token),
+    UndefinedFunction(Token),
+    UndefinedLabel(Token),
+    MissingTerminator(Token),
+    UnreachableInstruction(Token),
+    MisplacedPhi(Token),
+    UnexpectedPhiIncoming(Token),
+    MissingPhiIncoming {
+        phi: Token,
+        predecessor: String,
+    },
+    Redefinition {
+        name: Token,
+        previous: Token,
+    },
+    UseBeforeDefinition {
+        usage: Token,
+        definition: Token,
+    },
+    PhiValueUnavailable {
+        value: Token,
+        definition: Token,
+    },
+    DuplicateLabel {
+        label: Token,
+        previous: Token,
+    },
+    ArgumentCountMismatch {
+        call: Token,
+        expected: usize,
+        actual: usize,
+    },
+    ConstantOutOfRange {
+        value: Token,
+        ty: String,
+    },
     InvalidType {
         expected: String,
         actual_type: String,
         actual: Token,
+        /// Where the value with the wrong type was defined when it is a named value
+        definition: Option<Box<Token>>,
+    },
+    MalformedNumber {
+        literal: Token,
+        reason: String,
+    },
+    UnterminatedString(Token),
+    InvalidEscape {
+        escape: Token,
+        reason: String,
+    },
+    DanglingDocComment(Token),
+    /// The program is valid but the backend it is compiled for can't express part of it
+    UnsupportedByTarget {
+        token: Token,
+        target: &'static str,
+        feature: String,
     },
+    /// A float type other than `f32` and `f64`
+    InvalidFloatWidth(Token),
 }
 
 impl BitBoxError {
     pub fn report(&self, filename: &str, src: &str) -> String {
+        self.diagnostic(filename, src).build()
+    }
+
+    /// One JSON object describing the error, for tools that wrap bitbox
+    pub fn report_json(&self, filename: &str, src: &str) -> String {
+        self.diagnostic(filename, src).build_json()
+    }
+
+    /// Stable code identifying the kind of error. Codes are never reused or renumbered so
+    /// new variants get the next free code.
+    pub fn code(&self) -> &'static str {
         match self {
-            Self::InvalidToken(token) => ReportBuilder::new(filename, src, &token.span)
-                .with_message("invalid token found")
-                .build(),
+            Self::UnexpectedToken { .. } => "E0001",
+            Self::InvalidContantValue(_) => "E0002",
+            Self::InvalidInstruction(_) => "E0003",
+            Self::InvalidCondition(_) => "E0004",
+            Self::InvalidToken(_) => "E0005",
+            Self::UnexpectedEndOfStream => "E0006",
+            Self::ExpectedTopLevelItem(_) => "E0007",
+            Self::ExpectedType(_) => "E0008",
+            Self::UndefinedVariable(_) => "E0009",
+            Self::UndefinedFunction(_) => "E0010",
+            Self::UndefinedLabel(_) => "E0011",
+            Self::MissingTerminator(_) => "E0012",
+            Self::UnreachableInstruction(_) => "E0013",
+            Self::MisplacedPhi(_) => "E0014",
+            Self::UnexpectedPhiIncoming(_) => "E0015",
+            Self::MissingPhiIncoming { .. } => "E0016",
+            Self::Redefinition { .. } => "E0017",
+            Self::UseBeforeDefinition { .. } => "E0018",
+            Self::PhiValueUnavailable { .. } => "E0019",
+            Self::DuplicateLabel { .. } => "E0020",
+            Self::ArgumentCountMismatch { .. } => "E0021",
+            Self::ConstantOutOfRange { .. } => "E0022",
+            Self::InvalidType { .. } => "E0023",
+            Self::MalformedNumber { .. } => "E0024",
+            Self::UnterminatedString(_) => "E0025",
+            Self::InvalidEscape { .. } => "E0026",
+            Self::DanglingDocComment(_) => "E0027",
+            Self::UnsupportedByTarget { .. } => "E0028",
+            Self::InvalidFloatWidth(_) => "E0029",
+        }
+    }
+
+    fn diagnostic<'a>(&self, filename: &'a str, src: &'a str) -> ReportBuilder<'a> {
+        let builder = match self {
+            Self::InvalidToken(token) => {
+                ReportBuilder::new(filename, src, &token.span).with_message("invalid token found")
+            }
             Self::InvalidContantValue(token) => ReportBuilder::new(filename, src, &token.span)
                 .with_message(format!("invalid constant value '{}'", token.lexeme))
-                .with_note("expected a directive, number or string")
-                .build(),
+                .with_note("expected a directive, number or string"),
             Self::InvalidInstruction(token) => ReportBuilder::new(filename, src, &token.span)
                 .with_message("invalid instruction")
-                .with_note("expected one of: add, call, ret, sub ...")
-                .build(),
+                .with_note(format!(
+                    "expected one of: {}",
+                    Instruction::ALL.map(|(name, _)| name).join(", ")
+                )),
+            Self::InvalidCondition(token) => ReportBuilder::new(filename, src, &token.span)
+                .with_message(format!("invalid condition '{}'", token.lexeme))
+                .with_note("expected one of: eq, ne, lt, le, gt, ge"),
             Self::UnexpectedToken { expected, actual } => {
                 ReportBuilder::new(filename, src, &actual.span)
                     .with_message("unexpected token")
+                    .with_primary_label(format!("expected {:?}", expected))
                     .with_note(format!(
                         "expected: {:?}, found: {}",
                         expected, actual.lexeme
                     ))
-                    .build()
             }
             Self::UnexpectedEndOfStream => {
                 ReportBuilder::new(filename, src, &(src.len().saturating_sub(1)..src.len()))
                     .with_message("unexpected end of stream")
-                    .build()
             }
             Self::ExpectedTopLevelItem(token) => ReportBuilder::new(filename, src, &token.span)
                 .with_message("expected a top level item")
-                .with_note("function, import or constant")
-                .build(),
+                .with_note("function, import or constant"),
             Self::ExpectedType(token) => ReportBuilder::new(filename, src, &token.span)
                 .with_message(format!("expected a type but found {:?}", token.kind))
-                .with_note("expected a type: s32, u32, f32, ...")
-                .build(),
+                .with_note("expected a type: s32, u32, f32, ..."),
             Self::UndefinedVariable(token) => ReportBuilder::new(filename, src, &token.span)
-                .with_message("undefined variable")
-                .with_note("variable's must be forwarded defined")
-                .build(),
+                .with_message(format!("undefined variable '{}'", token.lexeme))
+                .with_primary_label("not found in this function or as a constant")
+                .with_note("variable's must be forwarded defined"),
+            Self::UndefinedFunction(token) => ReportBuilder::new(filename, src, &token.span)
+                .with_message(format!("undefined function '{}'", token.lexeme))
+                .with_note("functions must be defined or imported"),
+            Self::UndefinedLabel(token) => ReportBuilder::new(filename, src, &token.span)
+                .with_message(format!("undefined label '%{}'", token.lexeme))
+                .with_note("labels must be defined in the same function"),
+            Self::MissingTerminator(token) => ReportBuilder::new(filename, src, &token.span)
+                .with_message("block does not end with a terminator")
+                .with_note(
+                    "blocks end with @jump or @ret, @jumpif falls through to the next block",
+                ),
+            Self::UnreachableInstruction(token) => ReportBuilder::new(filename, src, &token.span)
+                .with_message("instruction after a terminator")
+                .with_primary_label("this can never run")
+                .with_help("start a new block with a label to continue"),
+            Self::MisplacedPhi(token) => ReportBuilder::new(filename, src, &token.span)
+                .with_message("phi after a non phi instruction")
+                .with_note("phis must be at the top of a block"),
+            Self::UnexpectedPhiIncoming(token) => ReportBuilder::new(filename, src, &token.span)
+                .with_message(format!("unexpected incoming value '{}'", token.lexeme))
+                .with_note("each predecessor of the block must have exactly one incoming value"),
+            Self::MissingPhiIncoming { phi, predecessor } => {
+                ReportBuilder::new(filename, src, &phi.span)
+                    .with_message(format!("phi '{}' is missing an incoming value", phi.lexeme))
+                    .with_note(format!("no value for predecessor {}", predecessor))
+            }
+            Self::Redefinition { name, previous } => ReportBuilder::new(filename, src, &name.span)
+                .with_message(format!("redefinition of '{}'", name.lexeme))
+                .with_primary_label("redefined here")
+                .with_label(&previous.span, "first defined here")
+                .with_note("names can only be defined once")
+                .with_help("rename one of the definitions"),
+            Self::UseBeforeDefinition { usage, definition } => {
+                ReportBuilder::new(filename, src, &usage.span)
+                    .with_message(format!("'{}' is used before it is defined", usage.lexeme))
+                    .with_primary_label("used here")
+                    .with_label(&definition.span, "defined here")
+                    .with_note("the definition must dominate every use")
+            }
+            Self::PhiValueUnavailable { value, definition } => {
+                ReportBuilder::new(filename, src, &value.span)
+                    .with_message(format!(
+                        "'{}' is not available on the incoming edge",
+                        value.lexeme
+                    ))
+                    .with_primary_label("used on this edge")
+                    .with_label(&definition.span, "defined here")
+                    .with_note("the definition must dominate the predecessor it comes from")
+            }
+            Self::DuplicateLabel { label, previous } => {
+                ReportBuilder::new(filename, src, &label.span)
+                    .with_message(format!("duplicate label '%{}'", label.lexeme))
+                    .with_primary_label("defined again here")
+                    .with_label(&previous.span, "first defined here")
+                    .with_help("labels must be unique within a function")
+            }
+            Self::ArgumentCountMismatch {
+                call,
+                expected,
+                actual,
+            } => ReportBuilder::new(filename, src, &call.span)
+                .with_message(format!("wrong number of arguments to '{}'", call.lexeme))
+                .with_note(format!("expected: {}, found: {}", expected, actual)),
+            Self::ConstantOutOfRange { value, ty } => {
+                ReportBuilder::new(filename, src, &value.span)
+                    .with_message(format!("'{}' does not fit in {}", value.lexeme, ty))
+            }
             Self::InvalidType {
                 expected,
                 actual_type,
                 actual,
-            } => ReportBuilder::new(filename, src, &actual.span)
-                .with_message(format!("invalid type for {}", actual.lexeme))
-                .with_note(format!("expected: {}, found: {}", expected, actual_type))
-                .build(),
-        }
+                definition,
+            } => {
+                let mut report = ReportBuilder::new(filename, src, &actual.span)
+                    .with_message(format!("invalid type for {}", actual.lexeme))
+                    .with_primary_label(format!("expected {}", expected))
+                    .with_note(format!("expected: {}, found: {}", expected, actual_type));
+                if let Some(definition) = definition {
+                    report =
+                        report.with_label(&definition.span, format!("has type {}", actual_type));
+                }
+                report
+            }
+            Self::MalformedNumber { literal, reason } => {
+                ReportBuilder::new(filename, src, &literal.span)
+                    .with_message(format!("malformed number literal '{}'", literal.lexeme))
+                    .with_primary_label(reason.clone())
+                    .with_help("numbers are written as 42, -7, 0xff, 0b1010, 0o17 or 1.5e3")
+            }
+            Self::UnterminatedString(token) => ReportBuilder::new(filename, src, &token.span)
+                .with_message("unterminated string")
+                .with_primary_label("string starts here")
+                .with_help(format!(
+                    "close the string with a '\"' followed by {} '#'",
+                    token.lexeme.matches('#').count()
+                )),
+            Self::InvalidEscape { escape, reason } => {
+                ReportBuilder::new(filename, src, &escape.span)
+                    .with_message(format!("invalid escape '{}'", escape.lexeme))
+                    .with_primary_label(reason.clone())
+                    .with_note(r#"escapes are \n, \t, \r, \0, \\, \", \xNN and \u{NNNN}"#)
+            }
+            Self::DanglingDocComment(token) => ReportBuilder::new(filename, src, &token.span)
+                .with_message("doc comment is not attached to anything")
+                .with_primary_label("documents nothing")
+                .with_help(
+                    "doc comments go before a function or constant, use // for other comments",
+                ),
+            Self::UnsupportedByTarget {
+                token,
+                target,
+                feature,
+            } => ReportBuilder::new(filename, src, &token.span)
+                .with_message(format!(
+                    "{} is not supported by the {} target",
+                    feature, target
+                ))
+                .with_help("pick another backend with --target"),
+            Self::InvalidFloatWidth(token) => ReportBuilder::new(filename, src, &token.span)
+                .with_message(format!("invalid float type '{}'", token.lexeme))
+                .with_primary_label("floats have 32 or 64 bits")
+                .with_help("use f32 or f64"),
+        };
+        builder.with_code(self.code())
     }
 }
 
@@ -79,74 +299,298 @@ impl From<Token> for BitBoxError {
     }
 }
 
+/// Every `BitBoxError` is an error, warnings and notes are for diagnostics that don't stop
+/// compilation
+// Nothing reports warnings or notes yet
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Error => write!(f, "error"),
+            Self::Warning => write!(f, "warning"),
+            Self::Note => write!(f, "note"),
+        }
+    }
+}
+
+/// A span of source with a message printed under it. The primary label is where the problem
+/// is, secondary labels give context such as where a value was defined.
+#[derive(Debug, Clone)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+    pub primary: bool,
+}
+
 pub struct ReportBuilder<'a> {
+    severity: Severity,
+    code: Option<&'static str>,
     message: String,
     filename: &'a str,
-    row: usize,
-    col: usize,
-    problem_line: &'a str,
-    note: Option<String>,
-    underline: String,
+    src: &'a str,
+    labels: Vec<Label>,
+    notes: Vec<String>,
+    help: Vec<String>,
 }
 
 impl<'a> ReportBuilder<'a> {
-    pub fn new(filename: &'a str, src: &'a str, span: &'a Span) -> Self {
-        let (row, col) = Self::get_row_col_from_span(src, span);
-        let problem_line = Self::get_problem_src_line(src, row);
-        let underline = Self::get_underline(col, span);
-
+    pub fn new(filename: &'a str, src: &'a str, span: &Span) -> Self {
         Self {
+            severity: Severity::Error,
+            code: None,
             message: String::new(),
             filename,
-            row,
-            col,
-            problem_line,
-            note: None,
-            underline,
+            src,
+            labels: vec![Label {
+                span: span.clone(),
+                message: String::new(),
+                primary: true,
+            }],
+            notes: vec![],
+            help: vec![],
         }
     }
 
+    #[allow(dead_code)]
+    pub fn with_severity(mut self, severity: Severity) -> Self {
+        self.severity = severity;
+        self
+    }
+
+    /// Code printed next to the severity, see `BitBoxError::code`
+    pub fn with_code(mut self, code: &'static str) -> Self {
+        self.code = Some(code);
+        self
+    }
+
     pub fn with_message(mut self, message: impl Into<String>) -> Self {
         self.message = message.into();
         self
     }
 
+    /// Message printed under the span the report was created with
+    pub fn with_primary_label(mut self, message: impl Into<String>) -> Self {
+        self.labels[0].message = message.into();
+        self
+    }
+
+    pub fn with_label(mut self, span: &Span, message: impl Into<String>) -> Self {
+        self.labels.push(Label {
+            span: span.clone(),
+            message: message.into(),
+            primary: false,
+        });
+        self
+    }
+
     pub fn with_note(mut self, note: impl Into<String>) -> Self {
-        self.note = Some(note.into());
+        self.notes.push(note.into());
         self
     }
 
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help.push(help.into());
+        self
+    }
+
+    /// 1-based line and column of the primary span
+    pub fn location(&self) -> (usize, usize) {
+        let (line, col) = self.line_col(self.labels[0].span.start);
+        (line + 1, col + 1)
+    }
+
     pub fn build(&self) -> String {
         let mut report = String::new();
-        writeln!(&mut report, "{}:{}:{}", self.row, self.col, self.filename)
-            .expect("failed to write report");
-        if !self.message.is_empty() {
-            writeln!(&mut report, " --> {}", self.message).expect("failed to write message");
+        write!(&mut report, "{}", self.severity).expect("failed to write severity");
+        if let Some(code) = self.code {
+            write!(&mut report, "[{}]", code).expect("failed to write code");
+        }
+        if self.message.is_empty() {
+            writeln!(&mut report).expect("failed to write message");
+        } else {
+            writeln!(&mut report, ": {}", self.message).expect("failed to write message");
         }
-        writeln!(&mut report, "{:<3}| {}", self.row, self.problem_line)
-            .expect("failed to write problem line");
-        writeln!(&mut report, "   | {}", self.underline).expect("failed to write underline");
-        if let Some(note) = &self.note {
-            writeln!(&mut report, "   | = note: {}", note).expect("failed to write help");
+
+        let segments = self.segments();
+        let last_line = segments
+            .iter()
+            .map(|segment| segment.line)
+            .max()
+            .unwrap_or(0);
+        let gutter = (last_line + 1).to_string().len();
+        let blank = " ".repeat(gutter);
+
+        let (line, col) = self.location();
+        writeln!(
+            &mut report,
+            "{}--> {}:{}:{}",
+            blank, self.filename, line, col
+        )
+        .expect("failed to write location");
+        writeln!(&mut report, "{} |", blank).expect("failed to write gutter");
+
+        let mut previous_line = None;
+        for (index, segment) in segments.iter().enumerate() {
+            if previous_line != Some(segment.line) {
+                if previous_line.is_some_and(|previous| segment.line > previous + 1) {
+                    writeln!(&mut report, "{}...", blank).expect("failed to write gap");
+                }
+                let source = self.src.lines().nth(segment.line).unwrap_or_default();
+                writeln!(&mut report, "{:>gutter$} | {}", segment.line + 1, source)
+                    .expect("failed to write source line");
+                previous_line = Some(segment.line);
+            }
+            let label = &self.labels[segment.label];
+            let marker = if label.primary { "^" } else { "-" };
+            let mut underline = format!(
+                "{}{}",
+                " ".repeat(segment.start),
+                marker.repeat(segment.end.saturating_sub(segment.start).max(1))
+            );
+            let last_of_label = segments[index + 1..]
+                .iter()
+                .all(|next| next.label != segment.label);
+            if last_of_label && !label.message.is_empty() {
+                underline = format!("{} {}", underline, label.message);
+            }
+            writeln!(&mut report, "{} | {}", blank, underline).expect("failed to write underline");
+        }
+
+        for note in self.notes.iter() {
+            writeln!(&mut report, "{} = note: {}", blank, note).expect("failed to write note");
+        }
+        for help in self.help.iter() {
+            writeln!(&mut report, "{} = help: {}", blank, help).expect("failed to write help");
         }
         report
     }
 
-    fn get_row_col_from_span(src: &str, span: &Span) -> (usize, usize) {
-        let row = src[..span.start].chars().filter(|&c| c == '\n').count();
-        let col = span.start - src[..span.start].rfind('\n').map(|n| n + 1).unwrap_or(0);
-        (row, col)
+    /// One line JSON object with the same content as `build`, spans are byte offsets into the
+    /// source and lines and columns are 1-based
+    pub fn build_json(&self) -> String {
+        let labels = self
+            .labels
+            .iter()
+            .map(|label| {
+                let (line, column) = self.line_col(label.span.start);
+                format!(
+                    r#"{{"span":{{"start":{},"end":{}}},"line":{},"column":{},"message":{},"primary":{}}}"#,
+                    label.span.start,
+                    label.span.end,
+                    line + 1,
+                    column + 1,
+                    json_string(&label.message),
+                    label.primary
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        let optional = |lines: &[String]| {
+            if lines.is_empty() {
+                "null".to_string()
+            } else {
+                json_string(&lines.join("\n"))
+            }
+        };
+        let span = &self.labels[0].span;
+        let (line, column) = self.location();
+        format!(
+            r#"{{"code":{},"severity":{},"message":{},"note":{},"help":{},"file":{},"span":{{"start":{},"end":{}}},"line":{},"column":{},"labels":[{}]}}"#,
+            self.code
+                .map(json_string)
+                .unwrap_or_else(|| "null".to_string()),
+            json_string(&self.severity.to_string()),
+            json_string(&self.message),
+            optional(&self.notes),
+            optional(&self.help),
+            json_string(self.filename),
+            span.start,
+            span.end,
+            line,
+            column,
+            labels
+        )
     }
 
-    fn get_problem_src_line(src: &'a str, row: usize) -> &'a str {
-        src.lines()
-            .nth(row)
-            .unwrap_or("Failed to extract source line")
+    /// Splits every label into one underline per line it covers, ordered by line with the
+    /// primary label first on a shared line
+    fn segments(&self) -> Vec<Segment> {
+        let mut segments = vec![];
+        for (label, Label { span, .. }) in self.labels.iter().enumerate() {
+            let end = span
+                .end
+                .max(span.start + 1)
+                .min(self.src.len().max(span.start));
+            let (first_line, first_col) = self.line_col(span.start);
+            let (last_line, last_col) = self.line_col(end.saturating_sub(1).max(span.start));
+            for line in first_line..=last_line {
+                let start = if line == first_line { first_col } else { 0 };
+                let end = if line == last_line {
+                    last_col + 1
+                } else {
+                    self.src
+                        .lines()
+                        .nth(line)
+                        .unwrap_or_default()
+                        .chars()
+                        .count()
+                };
+                segments.push(Segment {
+                    line,
+                    start,
+                    end,
+                    label,
+                });
+            }
+        }
+        segments.sort_by_key(|segment| (segment.line, segment.label));
+        segments
     }
 
-    fn get_underline(col: usize, span: &Span) -> String {
-        let spacing = " ".repeat(col);
-        let underline = "^".repeat(span.len());
-        format!("{}{}", spacing, underline)
+    /// 0-based line and column in characters of a byte offset
+    fn line_col(&self, offset: usize) -> (usize, usize) {
+        let mut offset = offset.min(self.src.len());
+        // Offsets inside a multi byte character belong to that character
+        while !self.src.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        let before = &self.src[..offset];
+        let line = before.chars().filter(|&c| c == '\n').count();
+        let line_start = before.rfind('\n').map(|n| n + 1).unwrap_or(0);
+        (line, before[line_start..].chars().count())
+    }
+}
+
+/// Part of a label on a single line
+struct Segment {
+    line: usize,
+    start: usize,
+    end: usize,
+    label: usize,
+}
+
+fn json_string(value: &str) -> String {
+    let mut output = String::from('"');
+    for c in value.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                write!(&mut output, "\\u{:04x}", c as u32).expect("failed to write escape")
+            }
+            c => output.push(c),
+        }
     }
+    output.push('"');
+    output
 }
@@ -0,0 +1,571 @@
+/// Long form description of an error code, printed by `bitbox --explain <code>`
+#[derive(Debug)]
+pub struct Explanation {
+    pub code: &'static str,
+    pub title: &'static str,
+    pub description: &'static str,
+    /// A minimal program that reports this error
+    pub bad: &'static str,
+    /// The same program with the error fixed
+    pub fixed: &'static str,
+}
+
+impl std::fmt::Display for Explanation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}: {}", self.code, self.title)?;
+        writeln!(f)?;
+        write_wrapped(f, self.description)?;
+        writeln!(f)?;
+        writeln!(f, "Erroneous code example:")?;
+        writeln!(f)?;
+        write_indented(f, self.bad)?;
+        writeln!(f)?;
+        writeln!(f, "Fixed example:")?;
+        writeln!(f)?;
+        write_indented(f, self.fixed)
+    }
+}
+
+/// Wraps prose at 80 columns so it reads well in a terminal
+fn write_wrapped(f: &mut std::fmt::Formatter<'_>, text: &str) -> std::fmt::Result {
+    let mut width = 0;
+    for word in text.split_whitespace() {
+        if width > 0 && width + 1 + word.len() > 80 {
+            writeln!(f)?;
+            width = 0;
+        }
+        if width > 0 {
+            write!(f, " ")?;
+            width += 1;
+        }
+        write!(f, "{}", word)?;
+        width += word.len();
+    }
+    writeln!(f)
+}
+
+fn write_indented(f: &mut std::fmt::Formatter<'_>, code: &str) -> std::fmt::Result {
+    for line in code.lines() {
+        if line.is_empty() {
+            writeln!(f)?;
+        } else {
+            writeln!(f, "    {}", line)?;
+        }
+    }
+    Ok(())
+}
+
+/// Looks up an error code such as `E0007`, case insensitive
+pub fn explain(code: &str) -> Option<&'static Explanation> {
+    EXPLANATIONS
+        .iter()
+        .find(|explanation| explanation.code.eq_ignore_ascii_case(code))
+}
+
+/// One entry per `BitBoxError` variant ordered by code
+pub const EXPLANATIONS: &[Explanation] = &[
+    Explanation {
+        code: "E0001",
+        title: "unexpected token",
+        description: "The parser found a token that can't appear at this point. The note \
+                      names the kind of token that was expected, the most common cause is a \
+                      missing `,` or `:`.",
+        bad: r#"import function core::write(*u8 u32) s32
+"#,
+        fixed: r#"import function core::write(*u8, u32) s32
+"#,
+    },
+    Explanation {
+        code: "E0002",
+        title: "invalid constant value",
+        description: "The value of a `const` must be a number, a string such as `#\"text\"#` or \
+                      a directive such as `.sizeof u32`. Other constants and expressions can't \
+                      be used as a value.",
+        bad: r#"const answer : u32 = answer
+"#,
+        fixed: r#"const answer : u32 = 42
+"#,
+    },
+    Explanation {
+        code: "E0003",
+        title: "invalid instruction",
+        description: "Every line of a function body is a label or an instruction starting \
+                      with `@`. The instruction wasn't recognised, see the README for the \
+                      list of builtin instructions.",
+        bad: r#"public function inc(x: s32) s32 {
+    @increment s32 : y, x
+    @ret s32 : y
+}
+"#,
+        fixed: r#"public function inc(x: s32) s32 {
+    @add s32 : y, x, 1
+    @ret s32 : y
+}
+"#,
+    },
+    Explanation {
+        code: "E0004",
+        title: "invalid condition",
+        description: "`@cmp` takes one of the conditions `eq`, `ne`, `lt`, `le`, `gt` or `ge` \
+                      before its type.",
+        bad: r#"public function less(x: s32) s32 {
+    @cmp less s32 : y, x, 0
+    @ret s32 : y
+}
+"#,
+        fixed: r#"public function less(x: s32) s32 {
+    @cmp lt s32 : y, x, 0
+    @ret s32 : y
+}
+"#,
+    },
+    Explanation {
+        code: "E0005",
+        title: "invalid token",
+        description: "The source contains a character that isn't part of the language. Names \
+                      may only contain ASCII letters, digits and `_`. A `/*` comment that is \
+                      never closed with `*/` is also reported as an invalid token.",
+        bad: r#"public function add$one(x: s32) s32 {
+    @add s32 : y, x, 1
+    @ret s32 : y
+}
+"#,
+        fixed: r#"public function add_one(x: s32) s32 {
+    @add s32 : y, x, 1
+    @ret s32 : y
+}
+"#,
+    },
+    Explanation {
+        code: "E0006",
+        title: "unexpected end of stream",
+        description: "The file ended in the middle of an item, usually a function without a \
+                      body or a body without its closing `}`.",
+        bad: r#"public function main() s32 // the body comes later"#,
+        fixed: r#"public function main() s32 {
+    @ret s32 : 0
+}
+"#,
+    },
+    Explanation {
+        code: "E0007",
+        title: "expected a top level item",
+        description: "A file is a list of top level items: `function`s, `import function`s \
+                      and `const`s. Anything else, such as an instruction, must be inside \
+                      the body of a function.",
+        bad: r#"@ret s32 : 0
+"#,
+        fixed: r#"public function main() s32 {
+    @ret s32 : 0
+}
+"#,
+    },
+    Explanation {
+        code: "E0008",
+        title: "expected a type",
+        description: "Types are unsigned `u8` to `u64`, signed `s8` to `s64`, floats `f32` and \
+                      `f64`, `void`, pointers `*T` and arrays `[N; T]`.",
+        bad: r#"public function id(x: int) s32 {
+    @ret s32 : x
+}
+"#,
+        fixed: r#"public function id(x: s32) s32 {
+    @ret s32 : x
+}
+"#,
+    },
+    Explanation {
+        code: "E0009",
+        title: "undefined variable",
+        description: "The name is not a parameter, not assigned by any instruction in the \
+                      function and not a `const`.",
+        bad: r#"public function id(x: s32) s32 {
+    @ret s32 : y
+}
+"#,
+        fixed: r#"public function id(x: s32) s32 {
+    @ret s32 : x
+}
+"#,
+    },
+    Explanation {
+        code: "E0010",
+        title: "undefined function",
+        description: "`@call` can only call functions defined in the same file or imported \
+                      with `import function`.",
+        bad: r#"public function quadruple(x: s32) s32 {
+    @call s32 : y double(x)
+    @ret s32 : y
+}
+"#,
+        fixed: r#"function double(x: s32) s32 {
+    @add s32 : y, x, x
+    @ret s32 : y
+}
+
+public function quadruple(x: s32) s32 {
+    @call s32 : y double(x)
+    @call s32 : z double(y)
+    @ret s32 : z
+}
+"#,
+    },
+    Explanation {
+        code: "E0011",
+        title: "undefined label",
+        description: "`@jump`, `@jumpif` and `@phi` refer to blocks by their label. The label \
+                      must be defined with `%name:` in the same function.",
+        bad: r#"public function main() s32 {
+    @jump %end
+%done:
+    @ret s32 : 0
+}
+"#,
+        fixed: r#"public function main() s32 {
+    @jump %done
+%done:
+    @ret s32 : 0
+}
+"#,
+    },
+    Explanation {
+        code: "E0012",
+        title: "block does not end with a terminator",
+        description: "Every block must end with `@ret` or `@jump` so it is clear where control \
+                      goes next. `@jumpif` falls through to the next block when the condition \
+                      is 0, so it can't end the last block of a function. A function with an \
+                      empty body has no block to end.",
+        bad: r#"public function inc(x: s32) s32 {
+    @add s32 : y, x, 1
+}
+"#,
+        fixed: r#"public function inc(x: s32) s32 {
+    @add s32 : y, x, 1
+    @ret s32 : y
+}
+"#,
+    },
+    Explanation {
+        code: "E0013",
+        title: "instruction after a terminator",
+        description: "A block ends at its terminator, so an instruction after `@ret` or \
+                      `@jump` can never run. Start a new block with a label if it is the \
+                      target of a jump, otherwise remove it.",
+        bad: r#"public function main() s32 {
+    @ret s32 : 0
+    @add s32 : y, 1, 2
+}
+"#,
+        fixed: r#"public function main() s32 {
+    @add s32 : y, 1, 2
+    @ret s32 : y
+}
+"#,
+    },
+    Explanation {
+        code: "E0014",
+        title: "phi after a non phi instruction",
+        description: "A `@phi` picks a value depending on the block control came from, so it \
+                      must come before any other instruction in its block.",
+        bad: r#"public function pick(x: s32) s32 {
+    @jumpif x, %then
+%else:
+    @jump %done
+%then:
+    @jump %done
+%done:
+    @add s32 : y, x, 1
+    @phi s32 : z, [x, %then], [y, %else]
+    @ret s32 : z
+}
+"#,
+        fixed: r#"public function pick(x: s32) s32 {
+    @jumpif x, %then
+%else:
+    @add s32 : y, x, 1
+    @jump %done
+%then:
+    @jump %done
+%done:
+    @phi s32 : z, [x, %then], [y, %else]
+    @ret s32 : z
+}
+"#,
+    },
+    Explanation {
+        code: "E0015",
+        title: "unexpected incoming value",
+        description: "Each incoming value of a `@phi` names a block that jumps or falls \
+                      through to the phi's block, and each block may only be named once.",
+        bad: r#"public function pick(x: s32) s32 {
+%entry:
+    @jump %done
+%done:
+    @phi s32 : y, [x, %entry], [x, %done]
+    @ret s32 : y
+}
+"#,
+        fixed: r#"public function pick(x: s32) s32 {
+%entry:
+    @jump %done
+%done:
+    @phi s32 : y, [x, %entry]
+    @ret s32 : y
+}
+"#,
+    },
+    Explanation {
+        code: "E0016",
+        title: "phi is missing an incoming value",
+        description: "A `@phi` needs a value for every block that can jump or fall through to \
+                      its block, otherwise it has no value when control comes from the \
+                      missing one.",
+        bad: r#"public function pick(x: s32) s32 {
+    @jumpif x, %then
+%else:
+    @jump %done
+%then:
+    @jump %done
+%done:
+    @phi s32 : y, [x, %then]
+    @ret s32 : y
+}
+"#,
+        fixed: r#"public function pick(x: s32) s32 {
+    @jumpif x, %then
+%else:
+    @jump %done
+%then:
+    @jump %done
+%done:
+    @phi s32 : y, [x, %then], [x, %else]
+    @ret s32 : y
+}
+"#,
+    },
+    Explanation {
+        code: "E0017",
+        title: "redefinition",
+        description: "The IR is in SSA form, each name is assigned exactly once. This includes \
+                      parameters, and names assigned in different blocks. Functions and imports \
+                      are called by name so no two of them may share one.",
+        bad: r#"public function main() s32 {
+    @add s32 : y, 1, 2
+    @add s32 : y, y, 3
+    @ret s32 : y
+}
+"#,
+        fixed: r#"public function main() s32 {
+    @add s32 : y, 1, 2
+    @add s32 : z, y, 3
+    @ret s32 : z
+}
+"#,
+    },
+    Explanation {
+        code: "E0018",
+        title: "used before it is defined",
+        description: "A name can only be used where its definition has already run on every \
+                      path from the start of the function, that is where its definition \
+                      dominates the use.",
+        bad: r#"public function main() s32 {
+    @add s32 : y, z, 1
+    @add s32 : z, 1, 2
+    @ret s32 : y
+}
+"#,
+        fixed: r#"public function main() s32 {
+    @add s32 : z, 1, 2
+    @add s32 : y, z, 1
+    @ret s32 : y
+}
+"#,
+    },
+    Explanation {
+        code: "E0019",
+        title: "not available on the incoming edge",
+        description: "An incoming value of a `@phi` is read at the end of the block it is \
+                      paired with, so its definition must dominate that block. This usually \
+                      means two incoming values were swapped.",
+        bad: r#"public function pick(x: s32) s32 {
+    @jumpif x, %then
+%else:
+    @add s32 : a, x, 1
+    @jump %done
+%then:
+    @add s32 : b, x, 2
+    @jump %done
+%done:
+    @phi s32 : y, [b, %else], [a, %then]
+    @ret s32 : y
+}
+"#,
+        fixed: r#"public function pick(x: s32) s32 {
+    @jumpif x, %then
+%else:
+    @add s32 : a, x, 1
+    @jump %done
+%then:
+    @add s32 : b, x, 2
+    @jump %done
+%done:
+    @phi s32 : y, [a, %else], [b, %then]
+    @ret s32 : y
+}
+"#,
+    },
+    Explanation {
+        code: "E0020",
+        title: "duplicate label",
+        description: "Labels name blocks so they must be unique within a function. Different \
+                      functions may use the same labels.",
+        bad: r#"public function main() s32 {
+    @jump %done
+%done:
+    @jump %done
+%done:
+    @ret s32 : 0
+}
+"#,
+        fixed: r#"public function main() s32 {
+    @jump %next
+%next:
+    @jump %done
+%done:
+    @ret s32 : 0
+}
+"#,
+    },
+    Explanation {
+        code: "E0021",
+        title: "wrong number of arguments",
+        description: "A `@call` must pass exactly as many arguments as the function or import \
+                      declares parameters.",
+        bad: r#"function double(x: s32) s32 {
+    @add s32 : y, x, x
+    @ret s32 : y
+}
+
+public function main() s32 {
+    @call s32 : y double(1, 2)
+    @ret s32 : y
+}
+"#,
+        fixed: r#"function double(x: s32) s32 {
+    @add s32 : y, x, x
+    @ret s32 : y
+}
+
+public function main() s32 {
+    @call s32 : y double(1)
+    @ret s32 : y
+}
+"#,
+    },
+    Explanation {
+        code: "E0022",
+        title: "constant out of range",
+        description: "A number literal must fit in the type it is used as, `u8` holds 0 to \
+                      255 and `s8` holds -128 to 127 for example.",
+        bad: r#"const big : u8 = 256
+"#,
+        fixed: r#"const big : u16 = 256
+"#,
+    },
+    Explanation {
+        code: "E0023",
+        title: "mismatched types",
+        description: "A value is used with a different type than the one it was defined with. \
+                      Values are never converted implicitly, the only exception is that a \
+                      pointer to an array can be used as a pointer to its elements.",
+        bad: r#"public function widen(x: u32) u64 {
+    @add u64 : y, x, 1
+    @ret u64 : y
+}
+"#,
+        fixed: r#"public function widen(x: u64) u64 {
+    @add u64 : y, x, 1
+    @ret u64 : y
+}
+"#,
+    },
+    Explanation {
+        code: "E0024",
+        title: "malformed number literal",
+        description: "Numbers are decimal integers such as `42` or `-7`, hexadecimal `0xff`, \
+                      binary `0b1010` or octal `0o17` integers, or decimal floats such as \
+                      `1.5`, `1e9` or `2.5e-3`. Digits may be separated with `_`. The digits \
+                      must be valid for the base and every part of a float needs at least one \
+                      digit.",
+        bad: r#"const mask : u8 = 0b1012
+"#,
+        fixed: r#"const mask : u8 = 0b1010
+"#,
+    },
+    Explanation {
+        code: "E0025",
+        title: "unterminated string",
+        description: "A string opened with `#\"` must be closed with `\"#`. Strings opened with \
+                      more `#`s, such as `##\"`, are closed by a `\"` followed by the same \
+                      number of `#`s, which lets them contain `\"#`.",
+        bad: r##"const greeting : *[6; u8] = #"Hello
+"##,
+        fixed: r##"const greeting : *[6; u8] = #"Hello\n"#
+"##,
+    },
+    Explanation {
+        code: "E0026",
+        title: "invalid escape",
+        description: "Strings support the escapes `\\n`, `\\t`, `\\r`, `\\0`, `\\\\`, `\\\"`, \
+                      `\\xNN` and `\\u{NNNN}`. In strings `\\xNN` is at most `\\x7f` so the \
+                      string stays valid UTF-8, byte strings such as `b#\"\\xff\"#` can contain \
+                      any byte but no `\\u` escapes. Raw strings such as `r#\"C:\\dir\"#` have no \
+                      escapes.",
+        bad: r##"const path : *[6; u8] = #"C:\dir"#
+"##,
+        fixed: r##"const path : *[6; u8] = r#"C:\dir"#
+"##,
+    },
+    Explanation {
+        code: "E0027",
+        title: "doc comment is not attached to anything",
+        description: "A `///` doc comment documents the function or constant that follows it. \
+                      Doc comments before imports, inside function bodies or at the end of a \
+                      file have nothing to document, use a `//` or `/* */` comment there.",
+        bad: r#"/// Writes to stdout
+import function core::write(*u8, u32) s32
+"#,
+        fixed: r#"// Writes to stdout
+import function core::write(*u8, u32) s32
+"#,
+    },
+    Explanation {
+        code: "E0028",
+        title: "not supported by the target",
+        description: "The program is valid but the backend it is compiled for can't express \
+                      part of it. The `vm` target keeps values in 64 bit integer registers so \
+                      it has no floats or integers wider than 64 bits, and the only imports it \
+                      provides are `core::write` and `core::exit`. The examples are compiled \
+                      with `--target vm`.",
+        bad: r#"public function half(x: f64) f64 {
+    @mul f64 : y, x, 0.5
+    @ret f64 : y
+}
+"#,
+        fixed: r#"public function double(x: s64) s64 {
+    @mul s64 : y, x, 2
+    @ret s64 : y
+}
+"#,
+    },
+    Explanation {
+        code: "E0029",
+        title: "invalid float type",
+        description: "Floats are IEEE 754 single or double precision numbers, written `f32` \
+                      and `f64`. There are no floats of other widths such as `f16`.",
+        bad: r#"const half : f16 = 0.5
+"#,
+        fixed: r#"const half : f32 = 0.5
+"#,
+    },
+];
@@ -0,0 +1,238 @@
+use super::explain::{explain, EXPLANATIONS};
+use super::{ReportBuilder, Severity};
+use crate::lexer::lex;
+use crate::parser;
+use crate::ssa;
+use crate::target::{Emit, Target};
+use crate::test::snapshot_sources;
+
+/// Runs every check `main` runs before emitting and returns the errors found
+fn check(input: &str) -> Vec<crate::error::BitBoxError> {
+    let tokens = lex(input);
+    let mut program = match parser::Parser::new(tokens).parse() {
+        Ok(program) => program,
+        Err(errors) => return errors,
+    };
+    ssa::analyse(&mut program).err().into_iter().collect()
+}
+
+/// `check`, and compiling for the vm when it passes, which reports what the vm can't run
+fn check_for_vm(input: &str) -> Vec<crate::error::BitBoxError> {
+    let tokens = lex(input);
+    let mut program = match parser::Parser::new(tokens).parse() {
+        Ok(program) => program,
+        Err(errors) => return errors,
+    };
+    let result = ssa::analyse(&mut program).and_then(|_| Target::Vm.compile(program, Emit::Bin));
+    result.err().into_iter().collect()
+}
+
+pub fn snapshot_report(input: &str) -> String {
+    let errors = check(input);
+    if errors.is_empty() {
+        return "ok".to_string();
+    }
+    errors
+        .iter()
+        .map(|err| err.report("test.bitbox", input))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn snapshot_json(input: &str) -> String {
+    check(input)
+        .iter()
+        .map(|err| err.report_json("test.bitbox", input))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+macro_rules! snapshot {
+    ($name:tt, $snapshot:ident, $path:tt) => {
+        #[test]
+        fn $name() {
+            let contents = include_str!($path);
+            let mut settings = insta::Settings::clone_current();
+            settings.set_snapshot_path("testdata/output/");
+            settings.bind(|| {
+                insta::assert_snapshot!($snapshot(contents));
+            });
+        }
+    };
+}
+
+snapshot!(recovery, snapshot_report, "../../snapshots/recovery.bitbox");
+snapshot!(
+    duplicate_label,
+    snapshot_report,
+    "../../snapshots/duplicate_label.bitbox"
+);
+snapshot!(
+    empty_body,
+    snapshot_report,
+    "../../snapshots/empty_body.bitbox"
+);
+snapshot!(
+    redefinition,
+    snapshot_report,
+    "../../snapshots/redefinition.bitbox"
+);
+snapshot!(
+    function_redefinition,
+    snapshot_report,
+    "../../snapshots/function_redefinition.bitbox"
+);
+snapshot!(
+    use_before_definition,
+    snapshot_report,
+    "../../snapshots/use_before_definition.bitbox"
+);
+snapshot!(
+    phi_unavailable,
+    snapshot_report,
+    "../../snapshots/phi_unavailable.bitbox"
+);
+snapshot!(
+    type_mismatch,
+    snapshot_report,
+    "../../snapshots/type_mismatch.bitbox"
+);
+snapshot!(
+    malformed_numbers,
+    snapshot_report,
+    "../../snapshots/malformed_numbers.bitbox"
+);
+snapshot!(
+    string_errors,
+    snapshot_report,
+    "../../snapshots/string_errors.bitbox"
+);
+snapshot!(
+    dangling_doc_comment,
+    snapshot_report,
+    "../../snapshots/dangling_doc_comment.bitbox"
+);
+snapshot!(
+    float_width,
+    snapshot_report,
+    "../../snapshots/float_width.bitbox"
+);
+snapshot!(
+    recovery_json,
+    snapshot_json,
+    "../../snapshots/recovery.bitbox"
+);
+snapshot!(
+    redefinition_json,
+    snapshot_json,
+    "../../snapshots/redefinition.bitbox"
+);
+
+/// Every fixture goes through the checks `main` runs, so one that stops passing them shows up
+/// here even when its other snapshots skip some of the checks
+#[test]
+fn analyse_every_fixture() {
+    let mut output = String::new();
+    for (name, source) in snapshot_sources() {
+        let codes = check(&source)
+            .iter()
+            .map(|err| err.code())
+            .collect::<Vec<_>>();
+        if codes.is_empty() {
+            output += &format!("{name}: ok\n");
+        } else {
+            output += &format!("{name}: {}\n", codes.join(", "));
+        }
+    }
+    let mut settings = insta::Settings::clone_current();
+    settings.set_snapshot_path("testdata/output/");
+    settings.bind(|| {
+        insta::assert_snapshot!(output);
+    });
+}
+
+#[test]
+fn multi_line_span() {
+    let src = "first line\nsecond line\nthird line\n";
+    let report = ReportBuilder::new("test.bitbox", src, &(6..17))
+        .with_message("spans two lines")
+        .with_primary_label("covers both")
+        .with_label(&(23..28), "context")
+        .with_help("a suggestion")
+        .build();
+    let mut settings = insta::Settings::clone_current();
+    settings.set_snapshot_path("testdata/output/");
+    settings.bind(|| {
+        insta::assert_snapshot!(report);
+    });
+}
+
+#[test]
+fn severities() {
+    let src = "first line\n";
+    for (severity, name) in [
+        (Severity::Error, "error"),
+        (Severity::Warning, "warning"),
+        (Severity::Note, "note"),
+    ] {
+        let report = ReportBuilder::new("test.bitbox", src, &(0..5))
+            .with_severity(severity)
+            .with_code("E0001")
+            .with_message("a message");
+        assert!(
+            report
+                .build()
+                .starts_with(&format!("{name}[E0001]: a message\n")),
+            "{}",
+            report.build()
+        );
+        assert!(
+            report
+                .build_json()
+                .contains(&format!(r#""severity":"{name}""#)),
+            "{}",
+            report.build_json()
+        );
+    }
+}
+
+#[test]
+fn explanations_cover_every_code() {
+    for (index, explanation) in EXPLANATIONS.iter().enumerate() {
+        assert_eq!(explanation.code, format!("E{:04}", index + 1));
+        // The vm has no floats, so the fixed float type can only be checked
+        let check = match explanation.code {
+            "E0029" => check,
+            _ => check_for_vm,
+        };
+        let codes = check(explanation.bad)
+            .iter()
+            .map(|err| err.code())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            codes,
+            [explanation.code],
+            "bad example of {}",
+            explanation.code
+        );
+        let codes = check(explanation.fixed)
+            .iter()
+            .map(|err| err.code())
+            .collect::<Vec<_>>();
+        assert!(
+            codes.is_empty(),
+            "fixed example of {} reports {:?}",
+            explanation.code,
+            codes
+        );
+    }
+}
+
+#[test]
+fn explain_top_level_item() {
+    let mut settings = insta::Settings::clone_current();
+    settings.set_snapshot_path("testdata/output/");
+    settings.bind(|| {
+        insta::assert_snapshot!(explain("e0007").unwrap().to_string());
+    });
+}
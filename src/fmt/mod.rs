@@ -0,0 +1,213 @@
+use crate::error::BitBoxError;
+use crate::lexer::token::{Token, TokenKind};
+use crate::lexer::{lex, lex_with_trivia};
+use crate::parser::Parser;
+use std::collections::HashMap;
+
+#[cfg(test)]
+mod test;
+
+const INDENT: &str = "    ";
+
+/// Rewrites `src` in the canonical layout: instructions indented inside function bodies and
+/// labels at the start of the line, the `:` after a type aligned within each function body,
+/// `, ` between arguments, at most one blank line in a row and one after every function.
+/// Comments are kept where they are. Only programs that parse are formatted.
+pub fn format(src: &str) -> Result<String, Vec<BitBoxError>> {
+    Parser::new(lex(src)).parse()?;
+    let tokens = lex_with_trivia(src);
+
+    let mut output: Vec<Rendered> = vec![];
+    let mut depth = 0usize;
+    let mut functions = 0usize;
+    let mut previous: Option<&Line> = None;
+    let lines = lines(src, &tokens);
+    for line in lines.iter() {
+        let first = &line.tokens[0].kind;
+        if *first == TokenKind::RightBrace {
+            depth = depth.saturating_sub(1);
+        }
+        if let Some(previous) = previous {
+            let opens_block = previous.last_kind() == TokenKind::LeftBrace;
+            let closes_function = depth == 0 && previous.tokens[0].kind == TokenKind::RightBrace;
+            let blank = line.blank_before && !opens_block && *first != TokenKind::RightBrace;
+            if blank || closes_function {
+                output.push(Rendered::default());
+            }
+        }
+
+        // Labels are outdented to the level of the function they belong to
+        let indent = match first {
+            TokenKind::LabelDefinition => depth.saturating_sub(1),
+            _ => depth,
+        };
+        let mut rendered = render(&line.tokens);
+        rendered.text.insert_str(0, &INDENT.repeat(indent));
+        rendered.colon = rendered.colon.map(|colon| colon + INDENT.len() * indent);
+        rendered.function = (depth > 0).then_some(functions);
+        output.push(rendered);
+
+        for token in line.tokens.iter().skip(1) {
+            match token.kind {
+                TokenKind::LeftBrace => depth += 1,
+                TokenKind::RightBrace => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+        }
+        if *first == TokenKind::LeftBrace {
+            depth += 1;
+        }
+        if depth == 0 {
+            functions += 1;
+        }
+        previous = Some(line);
+    }
+    Ok(align(output))
+}
+
+/// A line of output, with where the `:` after a type is and the function body it is in
+#[derive(Debug, Default)]
+struct Rendered {
+    text: String,
+    /// Offset of the space before the `:`
+    colon: Option<usize>,
+    function: Option<usize>,
+}
+
+/// Pads the types before `:` so the `:`s of a function body are in the same column
+fn align(lines: Vec<Rendered>) -> String {
+    let mut columns = HashMap::new();
+    for line in lines.iter() {
+        if let (Some(function), Some(colon)) = (line.function, line.colon) {
+            let column = columns.entry(function).or_insert(colon);
+            *column = colon.max(*column);
+        }
+    }
+
+    let mut output = String::new();
+    for mut line in lines {
+        if let (Some(function), Some(colon)) = (line.function, line.colon) {
+            line.text
+                .insert_str(colon, &" ".repeat(columns[&function] - colon));
+        }
+        output += &line.text;
+        output.push('\n');
+    }
+    output
+}
+
+/// The tokens of one line of output and whether a blank line came before it in the source
+#[derive(Debug)]
+struct Line<'a> {
+    tokens: Vec<&'a Token>,
+    blank_before: bool,
+}
+
+impl Line<'_> {
+    fn last_kind(&self) -> TokenKind {
+        self.tokens
+            .last()
+            .expect("lines are never empty")
+            .kind
+            .clone()
+    }
+}
+
+/// Splits the tokens into lines, dropping whitespace. Lines also end after a `{` or a label
+/// definition and before a `}` so every instruction is on its own line.
+fn lines<'a>(src: &str, tokens: &'a [Token]) -> Vec<Line<'a>> {
+    let mut lines = vec![];
+    let mut current: Option<Line> = None;
+    let mut newlines = 0;
+    for token in tokens.iter() {
+        match token.kind {
+            TokenKind::Whitespace => continue,
+            TokenKind::Delimiter => {
+                newlines += src[token.span.clone()].matches('\n').count();
+                continue;
+            }
+            _ => {}
+        }
+
+        if let Some(line) = current.take() {
+            let breaks = match (line.last_kind(), &token.kind) {
+                (_, TokenKind::RightBrace) => true,
+                // A comment on the same line stays there
+                (_, TokenKind::Comment) if newlines == 0 => false,
+                (TokenKind::LeftBrace | TokenKind::LabelDefinition, _) => true,
+                _ => newlines > 0,
+            };
+            if breaks {
+                lines.push(line);
+            } else {
+                current = Some(line);
+            }
+        }
+        match &mut current {
+            Some(line) => line.tokens.push(token),
+            None => {
+                current = Some(Line {
+                    tokens: vec![token],
+                    blank_before: newlines > 1,
+                })
+            }
+        }
+
+        // `{` and `}` take the newlines after them
+        newlines = match token.kind {
+            TokenKind::LeftBrace | TokenKind::RightBrace => {
+                src[token.span.clone()].matches('\n').count()
+            }
+            _ => 0,
+        };
+    }
+    lines.extend(current);
+    lines
+}
+
+/// Joins the tokens of a line with single spaces, except around `::` and inside brackets
+/// and parameter lists where the tokens are written together
+fn render(tokens: &[&Token]) -> Rendered {
+    let mut output = String::new();
+    let mut colon = None;
+    let mut parens = 0usize;
+    let mut previous: Option<&TokenKind> = None;
+    for token in tokens.iter() {
+        let kind = &token.kind;
+        let space = match (previous, kind) {
+            (None, _) => false,
+            (_, TokenKind::Comma | TokenKind::Semicolon) => false,
+            (_, TokenKind::RightParen | TokenKind::RightBracket) => false,
+            (_, TokenKind::PathSeparator) | (Some(TokenKind::PathSeparator), _) => false,
+            (Some(TokenKind::LeftParen | TokenKind::LeftBracket | TokenKind::Star), _) => false,
+            // Function names are written next to their parameters or arguments
+            (Some(TokenKind::Identifier), TokenKind::LeftParen) => false,
+            // `name: type` in parameter lists, `type : name` everywhere else
+            (_, TokenKind::Colon) => parens == 0,
+            _ => true,
+        };
+        if *kind == TokenKind::Colon && space && colon.is_none() {
+            colon = Some(output.len());
+        }
+        if space {
+            output.push(' ');
+        }
+        match kind {
+            TokenKind::LeftParen => parens += 1,
+            TokenKind::RightParen => parens = parens.saturating_sub(1),
+            _ => {}
+        }
+        match kind {
+            TokenKind::Comment | TokenKind::DocComment => output += token.lexeme.trim_end(),
+            TokenKind::Label => output += &format!("%{}", token.lexeme),
+            TokenKind::LabelDefinition => output += &format!("%{}:", token.lexeme),
+            _ => output += &token.lexeme,
+        }
+        previous = Some(kind);
+    }
+    Rendered {
+        text: output,
+        colon,
+        function: None,
+    }
+}
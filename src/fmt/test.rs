@@ -0,0 +1,56 @@
+use crate::fmt::format;
+use crate::lexer::lex;
+use crate::parser::Parser;
+use crate::test::snapshot_sources;
+
+pub fn snapshot_format(input: &str) -> String {
+    match format(input) {
+        Ok(output) => output,
+        Err(errors) => format!("{:#?}", errors),
+    }
+}
+
+macro_rules! snapshot {
+    ($name:tt, $path:tt) => {
+        #[test]
+        fn $name() {
+            let contents = include_str!($path);
+            let mut settings = insta::Settings::clone_current();
+            settings.set_snapshot_path("testdata/output/");
+            settings.bind(|| {
+                insta::assert_snapshot!(snapshot_format(contents));
+            });
+        }
+    };
+}
+
+snapshot!(unformatted, "../../snapshots/unformatted.bitbox");
+snapshot!(comments, "../../snapshots/comments.bitbox");
+snapshot!(recovery, "../../snapshots/recovery.bitbox");
+snapshot!(aligned, "../../snapshots/aligned.bitbox");
+
+/// Formatting doesn't change the program and formatting the output again changes nothing
+#[test]
+fn format_keeps_program() {
+    for (name, source) in snapshot_sources() {
+        let Ok(formatted) = format(&source) else {
+            continue;
+        };
+        let program = Parser::new(lex(&source))
+            .parse()
+            .expect("formatted sources parse");
+        let reformatted = Parser::new(lex(&formatted)).parse().unwrap_or_else(|err| {
+            panic!(
+                "failed to parse formatted {} {:?}\n{}",
+                name, err, formatted
+            )
+        });
+        assert_eq!(reformatted.to_string(), program.to_string(), "{}", name);
+        assert_eq!(
+            format(&formatted).expect("formatted sources parse"),
+            formatted,
+            "{}",
+            name
+        );
+    }
+}
@@ -0,0 +1,579 @@
+use crate::lexer::token::Token;
+use crate::ssa::literal::{self, Value};
+use crate::ssa::{
+    find_block, Condition, ConstantValue, Function, FunctionSpec, Import, Instruction, Operand,
+    Program, Type,
+};
+use std::collections::HashMap;
+
+#[cfg(test)]
+mod test;
+
+/// Memory is allocated in pages of the same size as wasm's so constants are at the same
+/// addresses as in a wasm module
+const PAGE_SIZE: usize = 1 << 16;
+/// Deepest call stack before `Trap::StackOverflow`
+const MAX_DEPTH: usize = 10_000;
+
+/// Why the program stopped before its function returned
+#[derive(Debug, Clone, PartialEq)]
+pub enum Trap {
+    UndefinedFunction(String),
+    /// An import was called that no host function was given for
+    MissingHostFunction(String),
+    ArgumentCountMismatch {
+        function: String,
+        expected: usize,
+        actual: usize,
+    },
+    OutOfBounds {
+        address: u32,
+        len: u32,
+    },
+    StackOverflow,
+    /// Control reached the end of a function without returning, which `ssa::cfg` rules out for
+    /// checked programs
+    EndOfFunction(String),
+    /// More instructions were run than `Interpreter::with_fuel` allowed
+    OutOfFuel,
+    /// A host function ended the program, such as `core::exit`
+    Exit(i32),
+    /// A host function failed
+    Host(String),
+}
+
+impl std::fmt::Display for Trap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UndefinedFunction(name) => write!(f, "undefined function {}", name),
+            Self::MissingHostFunction(name) => {
+                write!(f, "no host function was given for import {}", name)
+            }
+            Self::ArgumentCountMismatch {
+                function,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "{} expects {} arguments but was called with {}",
+                function, expected, actual
+            ),
+            Self::OutOfBounds { address, len } => write!(
+                f,
+                "{} bytes at {:#x} are out of bounds of memory",
+                len, address
+            ),
+            Self::StackOverflow => write!(f, "stack overflow"),
+            Self::EndOfFunction(name) => write!(f, "{} ended without returning", name),
+            Self::OutOfFuel => write!(f, "ran out of fuel"),
+            Self::Exit(status) => write!(f, "exited with status {}", status),
+            Self::Host(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for Trap {}
+
+/// Byte-addressable memory, holding the string constants from address 0
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Memory {
+    bytes: Vec<u8>,
+}
+
+impl Memory {
+    pub fn read(&self, address: u32, len: u32) -> Result<&[u8], Trap> {
+        let range = self.range(address, len)?;
+        Ok(&self.bytes[range])
+    }
+
+    // For host functions that fill a buffer, none of the `core` ones do yet
+    #[allow(dead_code)]
+    pub fn write(&mut self, address: u32, bytes: &[u8]) -> Result<(), Trap> {
+        let range = self.range(address, bytes.len() as u32)?;
+        self.bytes[range].copy_from_slice(bytes);
+        Ok(())
+    }
+
+    #[cfg(test)]
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    fn range(&self, address: u32, len: u32) -> Result<std::ops::Range<usize>, Trap> {
+        let start = address as usize;
+        let end = start + len as usize;
+        if end > self.bytes.len() {
+            return Err(Trap::OutOfBounds { address, len });
+        }
+        Ok(start..end)
+    }
+}
+
+/// Stands in for an imported function, gets the memory and the arguments and returns the
+/// result, `None` for `void` functions
+pub type HostFunction<'a> =
+    Box<dyn FnMut(&mut Memory, &[Value]) -> Result<Option<Value>, Trap> + 'a>;
+
+/// Runs a program without compiling it, the reference the backends are tested against.
+/// Integers wrap at the width of their type and signed integers are sign extended.
+/// The program must have passed every check in `ssa` and had its constants evaluated.
+pub struct Interpreter<'a> {
+    program: &'a Program,
+    memory: Memory,
+    /// Value of every constant, string constants are their address
+    constants: HashMap<&'a str, Value>,
+    /// Keyed by `module::name`
+    host_functions: HashMap<String, HostFunction<'a>>,
+    /// Instructions left to run, unlimited when `None`
+    fuel: Option<u64>,
+}
+
+impl<'a> Interpreter<'a> {
+    pub fn new(program: &'a Program) -> Self {
+        let mut bytes = vec![];
+        let mut constants = HashMap::new();
+        for constant in program.constants.iter() {
+            let value = match &constant.value {
+                ConstantValue::String(token) => {
+                    let address = Value::Unsigned(bytes.len() as u128);
+                    bytes.extend(
+                        literal::string_bytes(token).expect("strings are checked by the parser"),
+                    );
+                    address
+                }
+                ConstantValue::Number(token) => literal::typed_value(token, &constant.ty)
+                    .expect("constants are checked by ssa::typeck"),
+                ConstantValue::Directive(_) => {
+                    unreachable!("directives are evaluated by ssa::const_eval before running")
+                }
+            };
+            constants.insert(constant.name.lexeme.as_str(), value);
+        }
+        bytes.resize(bytes.len().div_ceil(PAGE_SIZE).max(1) * PAGE_SIZE, 0);
+
+        Self {
+            program,
+            memory: Memory { bytes },
+            constants,
+            host_functions: HashMap::new(),
+            fuel: None,
+        }
+    }
+
+    /// Calls `function` whenever the program calls the import `module::name`
+    pub fn with_host_function(
+        mut self,
+        module: &str,
+        name: &str,
+        function: impl FnMut(&mut Memory, &[Value]) -> Result<Option<Value>, Trap> + 'a,
+    ) -> Self {
+        self.host_functions
+            .insert(format!("{}::{}", module, name), Box::new(function));
+        self
+    }
+
+    /// Host functions for the `core` imports the wasm examples get from their host:
+    /// `core::write(*u8, u32) s32` writes to `output` and returns the number of bytes written,
+    /// `core::exit(s32) void` stops the program with `Trap::Exit`
+    pub fn with_core(self, mut output: impl std::io::Write + 'a) -> Self {
+        self.with_host_function("core", "write", move |memory, arguments| {
+            let [pointer, len] = arguments else {
+                return Err(Trap::Host(
+                    "core::write takes a pointer and a length".to_string(),
+                ));
+            };
+            let bytes = memory.read(pointer.bits() as u32, len.bits() as u32)?;
+            output
+                .write_all(bytes)
+                .and_then(|_| output.flush())
+                .map_err(|err| Trap::Host(format!("core::write failed: {}", err)))?;
+            Ok(Some(Value::Signed(bytes.len() as i128)))
+        })
+        .with_host_function("core", "exit", |_, arguments| match arguments {
+            [status] => Err(Trap::Exit(status.bits() as i32)),
+            _ => Err(Trap::Host("core::exit takes a status".to_string())),
+        })
+    }
+
+    /// Stops with `Trap::OutOfFuel` after running `fuel` instructions
+    #[cfg(test)]
+    pub fn with_fuel(mut self, fuel: u64) -> Self {
+        self.fuel = Some(fuel);
+        self
+    }
+
+    #[cfg(test)]
+    pub fn memory(&self) -> &Memory {
+        &self.memory
+    }
+
+    /// Calls a defined or imported function, returning `None` for `void` functions
+    pub fn call(&mut self, name: &str, arguments: &[Value]) -> Result<Option<Value>, Trap> {
+        match self.enter(name, arguments)? {
+            Call::Host(result) => Ok(result),
+            Call::Function(frame) => self.run(frame),
+        }
+    }
+
+    /// Calls a host function right away, or sets up the frame of a defined function
+    fn enter(&mut self, name: &str, arguments: &[Value]) -> Result<Call<'a>, Trap> {
+        let program = self.program;
+        if let Some(function) = program
+            .functions
+            .iter()
+            .find(|function| function.name == name)
+        {
+            check_arguments(name, function.params.len(), arguments)?;
+            let locals = function
+                .params
+                .iter()
+                .zip(arguments.iter())
+                .map(|(param, argument)| (param.name.lexeme.as_str(), *argument))
+                .collect();
+            return Ok(Call::Function(Frame {
+                function,
+                locals,
+                block: 0,
+                index: 0,
+                predecessor: None,
+            }));
+        }
+
+        let spec = program.imports.iter().find_map(|import| match import {
+            Import::Function(spec) if spec.name.lexeme == name => Some(spec),
+            Import::Function(_) => None,
+        });
+        let Some(FunctionSpec {
+            module_name,
+            name,
+            params,
+            ..
+        }) = spec
+        else {
+            return Err(Trap::UndefinedFunction(name.to_string()));
+        };
+        check_arguments(&name.lexeme, params.len(), arguments)?;
+        let key = format!("{}::{}", module_name.lexeme, name.lexeme);
+        let Some(host_function) = self.host_functions.get_mut(&key) else {
+            return Err(Trap::MissingHostFunction(key));
+        };
+        host_function(&mut self.memory, arguments).map(Call::Host)
+    }
+
+    /// Runs until `frame` returns. Calls push a frame instead of recursing so deep call
+    /// chains are limited by `MAX_DEPTH` rather than the native stack.
+    fn run(&mut self, frame: Frame<'a>) -> Result<Option<Value>, Trap> {
+        let mut stack = vec![frame];
+        loop {
+            let frame = stack.last_mut().expect("the stack is never empty");
+            let Some(block) = frame.function.blocks.get(frame.block) else {
+                return Err(Trap::EndOfFunction(frame.function.name.clone()));
+            };
+            if frame.index == 0 {
+                frame.resolve_phis(&self.constants);
+            }
+            let Some(instruction) = block.instructions.get(frame.index) else {
+                return Err(Trap::EndOfFunction(frame.function.name.clone()));
+            };
+            frame.index += 1;
+            if let Some(fuel) = self.fuel.as_mut() {
+                if *fuel == 0 {
+                    return Err(Trap::OutOfFuel);
+                }
+                *fuel -= 1;
+            }
+
+            let step = self.execute(&frame.locals, instruction)?;
+            let frame = stack.last_mut().expect("the stack is never empty");
+            match step {
+                Step::Next => {}
+                Step::Define(name, value) => {
+                    frame.locals.insert(name, value);
+                }
+                Step::Branch(label) => frame.branch(label),
+                Step::Call(callee) => {
+                    if stack.len() == MAX_DEPTH {
+                        return Err(Trap::StackOverflow);
+                    }
+                    stack.push(callee);
+                }
+                Step::Return(value) => {
+                    stack.pop();
+                    let Some(caller) = stack.last_mut() else {
+                        return Ok(value);
+                    };
+                    let block = &caller.function.blocks[caller.block];
+                    let Instruction::Call(variable, ..) = &block.instructions[caller.index - 1]
+                    else {
+                        unreachable!("frames are only pushed by calls");
+                    };
+                    if let Some(value) = value {
+                        caller.locals.insert(&variable.name.lexeme, value);
+                    }
+                }
+            }
+        }
+    }
+
+    fn execute(
+        &mut self,
+        locals: &HashMap<&str, Value>,
+        instruction: &'a Instruction,
+    ) -> Result<Step<'a>, Trap> {
+        let step = match instruction {
+            // Phis are resolved when their block is entered
+            Instruction::Phi(..) => Step::Next,
+            Instruction::Assign(variable, value) => Step::Define(
+                &variable.name.lexeme,
+                self.operand(locals, value, &variable.ty),
+            ),
+            Instruction::Add(variable, lhs, rhs)
+            | Instruction::Sub(variable, lhs, rhs)
+            | Instruction::Mul(variable, lhs, rhs) => {
+                let ty = &variable.ty;
+                let (lhs, rhs) = (self.operand(locals, lhs, ty), self.operand(locals, rhs, ty));
+                Step::Define(&variable.name.lexeme, arithmetic(instruction, lhs, rhs, ty))
+            }
+            Instruction::Cmp(variable, condition, lhs, rhs) => {
+                let ty = &variable.ty;
+                let (lhs, rhs) = (self.operand(locals, lhs, ty), self.operand(locals, rhs, ty));
+                let holds = compare(condition, lhs, rhs);
+                Step::Define(&variable.name.lexeme, wrap(holds as i128, ty))
+            }
+            Instruction::Jump(label) => Step::Branch(Some(label)),
+            Instruction::JumpIf(condition, label) => {
+                let taken = self.condition(locals, condition);
+                Step::Branch(taken.then_some(label))
+            }
+            Instruction::Return(Type::Void, _) => Step::Return(None),
+            Instruction::Return(ty, value) => Step::Return(Some(self.operand(locals, value, ty))),
+            Instruction::Call(variable, name, arguments) => {
+                let params = self.signature(&name.lexeme)?;
+                let arguments = arguments
+                    .iter()
+                    .zip(params.iter())
+                    .map(|(argument, ty)| self.operand(locals, argument, ty))
+                    .collect::<Vec<_>>();
+                match self.enter(&name.lexeme, &arguments)? {
+                    Call::Function(frame) => Step::Call(frame),
+                    Call::Host(Some(value)) => Step::Define(&variable.name.lexeme, value),
+                    Call::Host(None) => Step::Next,
+                }
+            }
+        };
+        Ok(step)
+    }
+
+    /// Parameter types of a defined or imported function, arguments are read as these types
+    fn signature(&self, name: &str) -> Result<Vec<Type>, Trap> {
+        if let Some(function) = self
+            .program
+            .functions
+            .iter()
+            .find(|function| function.name == name)
+        {
+            return Ok(function
+                .params
+                .iter()
+                .map(|param| param.ty.clone())
+                .collect());
+        }
+        self.program
+            .imports
+            .iter()
+            .find_map(|import| match import {
+                Import::Function(spec) if spec.name.lexeme == name => Some(spec.params.clone()),
+                Import::Function(_) => None,
+            })
+            .ok_or_else(|| Trap::UndefinedFunction(name.to_string()))
+    }
+
+    fn operand(&self, locals: &HashMap<&str, Value>, operand: &Operand, ty: &Type) -> Value {
+        match operand {
+            Operand::Constant(token) => {
+                literal::typed_value(token, ty).expect("constants are checked by ssa::typeck")
+            }
+            Operand::Variable(name) => self.variable(locals, &name.lexeme),
+        }
+    }
+
+    fn variable(&self, locals: &HashMap<&str, Value>, name: &str) -> Value {
+        lookup(locals, &self.constants, name)
+    }
+
+    fn condition(&self, locals: &HashMap<&str, Value>, condition: &Operand) -> bool {
+        match condition {
+            Operand::Constant(token) => literal::Literal::parse(token)
+                .map(|literal| literal != literal::Literal::Integer(0))
+                .expect("constants are checked by the parser"),
+            Operand::Variable(name) => integer(self.variable(locals, &name.lexeme)) != 0,
+        }
+    }
+}
+
+/// A call to a defined function that hasn't returned yet
+struct Frame<'a> {
+    function: &'a Function,
+    locals: HashMap<&'a str, Value>,
+    /// Position of the current block in `Function::blocks`
+    block: usize,
+    /// Position of the next instruction in the current block
+    index: usize,
+    /// Id of the block control came from
+    predecessor: Option<usize>,
+}
+
+impl<'a> Frame<'a> {
+    /// Phis read their incoming values together, before any of them is written
+    fn resolve_phis(&mut self, constants: &HashMap<&str, Value>) {
+        let block = &self.function.blocks[self.block];
+        let phis = block
+            .instructions
+            .iter()
+            .map_while(|instruction| match instruction {
+                Instruction::Phi(variable, incoming) => Some((variable, incoming)),
+                _ => None,
+            })
+            .map(|(variable, incoming)| {
+                let (value, _, _) = incoming
+                    .iter()
+                    .find(|(_, _, id)| Some(*id) == self.predecessor)
+                    .expect("phis have a value for every predecessor");
+                let value = lookup(&self.locals, constants, &value.name.lexeme);
+                (variable.name.lexeme.as_str(), value)
+            })
+            .collect::<Vec<_>>();
+        self.locals.extend(phis);
+    }
+
+    /// Moves to the block with `label`, or the next block when `@jumpif` falls through
+    fn branch(&mut self, label: Option<&Token>) {
+        let blocks = &self.function.blocks;
+        self.predecessor = Some(blocks[self.block].id);
+        self.index = 0;
+        self.block = match label {
+            Some(label) => {
+                let target = find_block(blocks, label).expect("labels are checked by the parser");
+                blocks
+                    .iter()
+                    .position(|block| block.id == target.id)
+                    .expect("block was found")
+            }
+            None => self.block + 1,
+        };
+    }
+}
+
+enum Call<'a> {
+    /// A host function that already returned
+    Host(Option<Value>),
+    Function(Frame<'a>),
+}
+
+/// What running an instruction does besides moving on to the next one
+enum Step<'a> {
+    Next,
+    Define(&'a str, Value),
+    /// Jump to the label, or fall through to the next block for `None`
+    Branch(Option<&'a Token>),
+    Call(Frame<'a>),
+    Return(Option<Value>),
+}
+
+fn lookup(locals: &HashMap<&str, Value>, constants: &HashMap<&str, Value>, name: &str) -> Value {
+    *locals
+        .get(name)
+        .or_else(|| constants.get(name))
+        .expect("names are checked by ssa::verify")
+}
+
+fn check_arguments(function: &str, expected: usize, arguments: &[Value]) -> Result<(), Trap> {
+    if expected != arguments.len() {
+        return Err(Trap::ArgumentCountMismatch {
+            function: function.to_string(),
+            expected,
+            actual: arguments.len(),
+        });
+    }
+    Ok(())
+}
+
+/// An integer value as an `i128`, unsigned values above `i128::MAX` wrap which is fine as
+/// every result is truncated to its type
+fn integer(value: Value) -> i128 {
+    match value {
+        Value::Unsigned(value) => value as i128,
+        Value::Signed(value) => value,
+        Value::F32(_) | Value::F64(_) => unreachable!("floats are not integers"),
+    }
+}
+
+fn float(value: Value) -> f64 {
+    match value {
+        Value::F32(value) => value as f64,
+        Value::F64(value) => value,
+        Value::Unsigned(_) | Value::Signed(_) => unreachable!("integers are not floats"),
+    }
+}
+
+/// Truncates an integer to the width of `ty`, sign extending it for signed types
+fn wrap(value: i128, ty: &Type) -> Value {
+    let bits = match ty {
+        Type::Unsigned(bits) | Type::Signed(bits) => *bits as u32,
+        Type::Pointer(_) => 32,
+        Type::Float(32) => return Value::F32(value as f32),
+        Type::Float(_) => return Value::F64(value as f64),
+        Type::Array(..) | Type::Void => unreachable!("values have a number type"),
+    };
+    let unused = 128 - bits.min(128);
+    match ty {
+        Type::Signed(_) => Value::Signed((value << unused) >> unused),
+        _ => Value::Unsigned(((value as u128) << unused) >> unused),
+    }
+}
+
+fn arithmetic(instruction: &Instruction, lhs: Value, rhs: Value, ty: &Type) -> Value {
+    if let Type::Float(bits) = ty {
+        let (lhs, rhs) = (float(lhs), float(rhs));
+        let value = match instruction {
+            Instruction::Add(..) => lhs + rhs,
+            Instruction::Sub(..) => lhs - rhs,
+            Instruction::Mul(..) => lhs * rhs,
+            _ => unreachable!("not an arithmetic instruction"),
+        };
+        return match bits {
+            32 => Value::F32(value as f32),
+            _ => Value::F64(value),
+        };
+    }
+    let (lhs, rhs) = (integer(lhs), integer(rhs));
+    let value = match instruction {
+        Instruction::Add(..) => lhs.wrapping_add(rhs),
+        Instruction::Sub(..) => lhs.wrapping_sub(rhs),
+        Instruction::Mul(..) => lhs.wrapping_mul(rhs),
+        _ => unreachable!("not an arithmetic instruction"),
+    };
+    wrap(value, ty)
+}
+
+/// Both values have the operands' type so comparing them as numbers is signed for signed
+/// types and unsigned otherwise
+fn compare(condition: &Condition, lhs: Value, rhs: Value) -> bool {
+    let ordering = match (lhs, rhs) {
+        (Value::Unsigned(lhs), Value::Unsigned(rhs)) => lhs.partial_cmp(&rhs),
+        (Value::Signed(lhs), Value::Signed(rhs)) => lhs.partial_cmp(&rhs),
+        (lhs, rhs) => float(lhs).partial_cmp(&float(rhs)),
+    };
+    // Every comparison with NaN is false except `ne`
+    let Some(ordering) = ordering else {
+        return *condition == Condition::Ne;
+    };
+    match condition {
+        Condition::Eq => ordering.is_eq(),
+        Condition::Ne => ordering.is_ne(),
+        Condition::Lt => ordering.is_lt(),
+        Condition::Le => ordering.is_le(),
+        Condition::Gt => ordering.is_gt(),
+        Condition::Ge => ordering.is_ge(),
+    }
+}
@@ -0,0 +1,162 @@
+use crate::interp::{Interpreter, Trap};
+use crate::lexer::lex;
+use crate::parser;
+use crate::ssa::literal::Value;
+use crate::ssa::{self, Program};
+
+fn checked(input: &str) -> Program {
+    let tokens = lex(input);
+    let mut program = parser::Parser::new(tokens)
+        .parse()
+        .expect("failed to parse program");
+    ssa::cfg::build(&mut program)
+        .and_then(|_| ssa::phi::validate(&program))
+        .and_then(|_| ssa::verify::verify(&program))
+        .and_then(|_| ssa::typeck::check(&program))
+        .and_then(|_| ssa::const_eval::evaluate(&mut program))
+        .expect("failed to check program");
+    program
+}
+
+fn call(program: &Program, function: &str, arguments: &[Value]) -> Result<Option<Value>, Trap> {
+    Interpreter::new(program)
+        .with_fuel(10_000)
+        .call(function, arguments)
+}
+
+#[test]
+fn integers_wrap_at_their_width() {
+    let program = checked(include_str!("../../snapshots/interp.bitbox"));
+    let cases = [
+        ("wrap_u8", vec![Value::Unsigned(100)], Value::Unsigned(44)),
+        ("wrap_s8", vec![Value::Signed(100)], Value::Signed(-56)),
+        (
+            "wrap_u64",
+            vec![Value::Unsigned(0)],
+            Value::Unsigned(u64::MAX as u128),
+        ),
+        (
+            "signed_less",
+            vec![Value::Signed(-1), Value::Signed(1)],
+            Value::Signed(1),
+        ),
+        (
+            "unsigned_less",
+            vec![Value::Unsigned(65535), Value::Unsigned(1)],
+            Value::Unsigned(0),
+        ),
+        ("half", vec![Value::F64(3.0)], Value::F64(1.5)),
+        ("copy", vec![Value::Signed(-7)], Value::Signed(-7)),
+    ];
+    for (function, arguments, expected) in cases {
+        assert_eq!(
+            call(&program, function, &arguments),
+            Ok(Some(expected)),
+            "{function}"
+        );
+    }
+}
+
+#[test]
+fn phis_resolve_by_predecessor() {
+    let program = checked(include_str!("../../snapshots/phi.bitbox"));
+    assert_eq!(
+        call(&program, "abs", &[Value::Signed(-5)]),
+        Ok(Some(Value::Signed(5)))
+    );
+    assert_eq!(
+        call(&program, "abs", &[Value::Signed(7)]),
+        Ok(Some(Value::Signed(7)))
+    );
+
+    let program = checked(include_str!("../../snapshots/interp.bitbox"));
+    // a and b swap on every iteration after the first
+    assert_eq!(
+        call(&program, "swap", &[Value::Signed(1)]),
+        Ok(Some(Value::Signed(1)))
+    );
+    assert_eq!(
+        call(&program, "swap", &[Value::Signed(2)]),
+        Ok(Some(Value::Signed(2)))
+    );
+    assert_eq!(
+        call(&program, "swap", &[Value::Signed(3)]),
+        Ok(Some(Value::Signed(1)))
+    );
+}
+
+#[test]
+fn calls_and_loops() {
+    let program = checked(include_str!("../../snapshots/calls.bitbox"));
+    assert_eq!(call(&program, "main", &[]), Ok(Some(Value::Signed(4))));
+
+    let program = checked(include_str!("../../snapshots/loop.bitbox"));
+    assert_eq!(
+        call(&program, "spin", &[Value::Signed(0)]),
+        Ok(Some(Value::Signed(0)))
+    );
+    assert_eq!(
+        call(&program, "spin", &[Value::Signed(1)]),
+        Err(Trap::OutOfFuel)
+    );
+}
+
+#[test]
+fn traps() {
+    let program = checked(include_str!("../../snapshots/interp.bitbox"));
+    let recurse = Interpreter::new(&program).call("recurse", &[Value::Signed(1)]);
+    assert_eq!(recurse, Err(Trap::StackOverflow));
+    assert_eq!(
+        call(&program, "quit", &[]),
+        Err(Trap::MissingHostFunction("core::exit".to_string()))
+    );
+    let quit = Interpreter::new(&program)
+        .with_core(vec![])
+        .call("quit", &[]);
+    assert_eq!(quit, Err(Trap::Exit(3)));
+    assert_eq!(
+        call(&program, "nowhere", &[]),
+        Err(Trap::UndefinedFunction("nowhere".to_string()))
+    );
+    assert_eq!(
+        call(&program, "copy", &[]),
+        Err(Trap::ArgumentCountMismatch {
+            function: "copy".to_string(),
+            expected: 1,
+            actual: 0
+        })
+    );
+
+    // The parser rejects empty bodies, a function built without it has no block to run
+    let mut program = checked("public function main() s32 {\n    @ret s32 : 0\n}\n");
+    program.functions[0].blocks.clear();
+    assert_eq!(
+        call(&program, "main", &[]),
+        Err(Trap::EndOfFunction("main".to_string()))
+    );
+}
+
+#[test]
+fn host_functions_read_constants_from_memory() {
+    let program = checked(include_str!(
+        "../target/wasm/examples/hello_world/hello_world.bitbox"
+    ));
+    let mut output = vec![];
+    let result = Interpreter::new(&program)
+        .with_core(&mut output)
+        .call("main", &[]);
+    assert_eq!(result, Ok(Some(Value::Signed(12))));
+    assert_eq!(output, b"Hello World\n");
+
+    let program = checked(include_str!("../../snapshots/strings.bitbox"));
+    let interpreter = Interpreter::new(&program);
+    assert_eq!(interpreter.memory().len(), 1 << 16);
+    assert!(interpreter.memory().read(0, 1 << 16).is_ok());
+    assert_eq!(
+        interpreter.memory().read(1 << 16, 1),
+        Err(Trap::OutOfBounds {
+            address: 1 << 16,
+            len: 1
+        })
+    );
+}
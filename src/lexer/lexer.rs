@@ -2,7 +2,10 @@ use super::token::{Directive, Instruction, Keyword, Span, Token, TokenKind};
 
 pub struct Lexer<'a> {
     chars: std::iter::Peekable<std::str::Chars<'a>>,
+    /// Byte offsets into the input
     span: Span,
+    /// Whether whitespace and comments are returned as tokens
+    trivia: bool,
 }
 
 impl<'a> Lexer<'a> {
@@ -10,13 +13,20 @@ impl<'a> Lexer<'a> {
         Self {
             chars: input.chars().peekable(),
             span: 0..0,
+            trivia: false,
         }
     }
 
+    /// Also returns whitespace and comments so the spans of the tokens cover the whole input
+    pub fn with_trivia(mut self) -> Self {
+        self.trivia = true;
+        self
+    }
+
     fn next(&mut self) -> Option<char> {
         let value = self.chars.next();
-        if value.is_some() {
-            self.span.end += 1;
+        if let Some(value) = value {
+            self.span.end += value.len_utf8();
         }
         value
     }
@@ -28,6 +38,10 @@ impl<'a> Lexer<'a> {
         }
     }
 
+    fn peek_is(&mut self, predicate: impl Fn(char) -> bool) -> bool {
+        matches!(self.chars.peek(), Some(value) if predicate(*value))
+    }
+
     fn peek(&mut self, expected: char) -> bool {
         matches!(self.chars.peek(), Some(value) if *value == expected)
     }
@@ -42,12 +56,23 @@ impl<'a> Lexer<'a> {
         }
     }
 
+    /// Takes everything that could belong to a number literal, including a leading `-`, a
+    /// `0x`, `0b` or `0o` prefix and a signed exponent. Whether the literal is well formed is
+    /// checked by `ssa::literal` so malformed literals are reported as a single token.
     fn parse_number(&mut self, value: char) -> Token {
         let mut lexeme = String::from(value);
 
-        while let Some(value) =
-            self.next_if(|value| value.is_ascii_digit() || ['.', '_'].contains(&value))
-        {
+        loop {
+            let is_exponent = !lexeme.starts_with("0x")
+                && !lexeme.starts_with("-0x")
+                && lexeme.ends_with(['e', 'E']);
+            let Some(value) = self.next_if(|value| {
+                value.is_ascii_alphanumeric()
+                    || ['.', '_'].contains(&value)
+                    || (is_exponent && ['+', '-'].contains(&value))
+            }) else {
+                break;
+            };
             lexeme.push(value);
         }
 
@@ -61,6 +86,11 @@ impl<'a> Lexer<'a> {
             lexeme.push(value);
         }
 
+        // Raw and byte strings start with a prefix such as r#"..."# or b#"..."#
+        if ["r", "b", "br"].contains(&lexeme.as_str()) && self.peek('#') {
+            return self.parse_string(lexeme);
+        }
+
         match lexeme.as_str() {
             "import" => self.spanned(TokenKind::Keyword(Keyword::Import), lexeme),
             "const" => self.spanned(TokenKind::Keyword(Keyword::Const), lexeme),
@@ -77,30 +107,51 @@ impl<'a> Lexer<'a> {
             lexeme.push(value);
         }
 
-        let kind = match lexeme.as_str() {
-            "@add" => Instruction::Add,
-            "@mul" => Instruction::Mul,
-            "@sub" => Instruction::Sub,
-            "@call" => Instruction::Call,
-            "@ret" => Instruction::Ret,
-            "@cmp" => Instruction::Cmp,
-            "@jumpif" => Instruction::JumpIf,
-            "@jump" => Instruction::Jump,
-            _ => return self.spanned(TokenKind::InvalidToken, lexeme),
-        };
-
-        self.spanned(TokenKind::Instruction(kind), lexeme)
+        let kind = Instruction::ALL
+            .iter()
+            .find(|(name, _)| lexeme[1..] == **name)
+            .map(|(_, kind)| kind.clone());
+        match kind {
+            Some(kind) => self.spanned(TokenKind::Instruction(kind), lexeme),
+            None => self.spanned(TokenKind::InvalidToken, lexeme),
+        }
     }
 
-    fn parse_string(&mut self) -> Token {
-        let mut lexeme = String::from('#');
-        while let Some(value) = self.next() {
+    /// Takes a string up to the `"` followed by as many `#` as it was opened with, or up to
+    /// the end of the input when it is never closed. The lexeme is the string as written,
+    /// `ssa::literal::string_bytes` checks it and resolves its escapes.
+    fn parse_string(&mut self, prefix: impl Into<String>) -> Token {
+        let mut lexeme = prefix.into();
+        let raw = lexeme.contains('r');
+        while let Some(value) = self.next_if(|value| value == '#') {
             lexeme.push(value);
-            if lexeme.ends_with("\"#") {
+        }
+        let hashes = lexeme.chars().filter(|&value| value == '#').count();
+        let Some(quote) = self.next_if(|value| value == '"') else {
+            return self.spanned(TokenKind::InvalidToken, lexeme);
+        };
+        lexeme.push(quote);
+
+        // Number of `#` seen since the last `"` that wasn't escaped
+        let mut closing = None;
+        let mut body = String::new();
+        while let Some(value) = self.next() {
+            body.push(value);
+            closing = match (value, closing) {
+                ('"', _) => Some(0),
+                ('#', Some(count)) => Some(count + 1),
+                _ => None,
+            };
+            if closing == Some(hashes) {
                 break;
             }
+            if !raw && value == '\\' {
+                if let Some(escaped) = self.next() {
+                    body.push(escaped);
+                }
+            }
         }
-        let lexeme = lexeme[2..lexeme.len() - 2].replace("\\n", "\n");
+        lexeme.push_str(&body);
         self.spanned(TokenKind::String, lexeme)
     }
 
@@ -113,6 +164,8 @@ impl<'a> Lexer<'a> {
 
         let kind = match lexeme.as_str() {
             ".len" => Directive::Len,
+            ".sizeof" => Directive::SizeOf,
+            ".alignof" => Directive::AlignOf,
             _ => return self.spanned(TokenKind::InvalidToken, lexeme),
         };
 
@@ -120,12 +173,12 @@ impl<'a> Lexer<'a> {
     }
 
     fn parse_delimiter(&mut self) -> Token {
-        while let Some(_) = self.next_if(|value| value == '\n') {}
+        while self.next_if(|value| value == '\n').is_some() {}
         self.spanned(TokenKind::Delimiter, "\\n")
     }
 
     fn parser_char_delemiter(&mut self, kind: TokenKind, char: char) -> Option<Token> {
-        while let Some(_) = self.next_if(|value| value == '\n') {}
+        while self.next_if(|value| value == '\n').is_some() {}
         Some(self.spanned(kind, char.to_string()))
     }
 
@@ -143,33 +196,77 @@ impl<'a> Lexer<'a> {
         self.spanned(TokenKind::Label, lexeme)
     }
 
-    fn skip_char(&mut self) -> Option<Token> {
-        self.spanned(TokenKind::InvalidToken, ' ');
+    /// Whitespace and comments are only returned in trivia mode, otherwise they are skipped
+    fn trivia(&mut self, kind: TokenKind, lexeme: String) -> Option<Token> {
+        let token = self.spanned(kind, lexeme);
+        if self.trivia {
+            return Some(token);
+        }
         self.parse()
     }
 
+    fn whitespace(&mut self, value: char) -> Option<Token> {
+        let mut lexeme = String::from(value);
+        while let Some(value) = self.next_if(|value| value.is_whitespace() && value != '\n') {
+            lexeme.push(value);
+        }
+        self.trivia(TokenKind::Whitespace, lexeme)
+    }
+
     fn double_char(&mut self, kind: TokenKind, value: impl Into<String>) -> Option<Token> {
         self.next();
         Some(self.spanned(kind, value))
     }
 
+    /// A `//` comment up to the end of the line, the newline is left for the delimiter.
+    /// `///` starts a doc comment which is always returned so the parser can attach it.
     fn comment(&mut self) -> Option<Token> {
+        let mut lexeme = String::from('/');
+        while let Some(value) = self.next_if(|value| value != '\n') {
+            lexeme.push(value);
+        }
+        if lexeme.starts_with("///") && !lexeme.starts_with("////") {
+            return Some(self.spanned(TokenKind::DocComment, lexeme));
+        }
+        self.trivia(TokenKind::Comment, lexeme)
+    }
+
+    /// A `/* */` comment, which may contain other block comments
+    fn block_comment(&mut self) -> Option<Token> {
+        let mut lexeme = String::from('/');
+        lexeme.extend(self.next());
+        let mut depth = 1;
         while let Some(value) = self.next() {
-            if value == '\n' {
-                break;
+            lexeme.push(value);
+            if value == '/' && self.peek('*') {
+                lexeme.extend(self.next());
+                depth += 1;
+            } else if value == '*' && self.peek('/') {
+                lexeme.extend(self.next());
+                depth -= 1;
+                if depth == 0 {
+                    return self.trivia(TokenKind::Comment, lexeme);
+                }
             }
         }
-        self.parse()
+        // Unterminated, the rest of the input is part of the comment
+        Some(self.spanned(TokenKind::InvalidToken, lexeme))
     }
 
     fn parse(&mut self) -> Option<Token> {
         match self.next() {
             Some(value @ '0'..='9') => Some(self.parse_number(value)),
+            Some('-') if self.peek_is(|value| value.is_ascii_digit()) => {
+                Some(self.parse_number('-'))
+            }
             Some(value) if value.is_ascii_alphabetic() => Some(self.parse_identifier(value)),
             Some('\n') => Some(self.parse_delimiter()),
-            Some(value) if value.is_ascii_whitespace() => self.skip_char(),
-            Some('#') if self.peek('"') => Some(self.parse_string()),
+            Some(value) if value.is_whitespace() => self.whitespace(value),
+            Some('#') if self.peek_is(|value| value == '"' || value == '#') => {
+                Some(self.parse_string('#'))
+            }
             Some('/') if self.peek('/') => self.comment(),
+            Some('/') if self.peek('*') => self.block_comment(),
             Some('.') if !self.peek(' ') => Some(self.parse_directive()),
             Some(':') if self.peek(':') => self.double_char(TokenKind::PathSeparator, "::"),
             Some('%') => Some(self.parse_label()),
@@ -177,8 +274,8 @@ impl<'a> Lexer<'a> {
             Some('+') => Some(self.spanned(TokenKind::Plus, '+')),
             Some('(') => Some(self.spanned(TokenKind::LeftParen, '(')),
             Some(')') => Some(self.spanned(TokenKind::RightParen, ')')),
-            Some('{') if self.peek('\n') => self.parser_char_delemiter(TokenKind::LeftBrace, '{'),
-            Some('}') if self.peek('\n') => self.parser_char_delemiter(TokenKind::RightBrace, '}'),
+            Some('{') => self.parser_char_delemiter(TokenKind::LeftBrace, '{'),
+            Some('}') => self.parser_char_delemiter(TokenKind::RightBrace, '}'),
             Some('[') => Some(self.spanned(TokenKind::LeftBracket, '[')),
             Some(']') => Some(self.spanned(TokenKind::RightBracket, ']')),
             Some(':') => Some(self.spanned(TokenKind::Colon, ':')),
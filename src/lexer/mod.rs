@@ -1,3 +1,4 @@
+#[allow(clippy::module_inception)]
 mod lexer;
 pub mod token;
 
@@ -7,3 +8,9 @@ mod test;
 pub fn lex(src: &str) -> Vec<token::Token> {
     lexer::Lexer::new(src).collect()
 }
+
+/// Lexes every character of `src` into a token, including whitespace and comments, so
+/// the source can be rebuilt exactly from the tokens' spans
+pub fn lex_with_trivia(src: &str) -> Vec<token::Token> {
+    lexer::Lexer::new(src).with_trivia().collect()
+}
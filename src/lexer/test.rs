@@ -1,8 +1,16 @@
-use crate::lexer::lex;
+use crate::lexer::{lex, lex_with_trivia};
+use crate::test::snapshot_sources;
 use std::fmt::Write;
 
 pub fn snapshot_lexing(input: &str) -> String {
-    let tokens = lex(input);
+    snapshot_tokens(input, lex(input))
+}
+
+pub fn snapshot_trivia(input: &str) -> String {
+    snapshot_tokens(input, lex_with_trivia(input))
+}
+
+fn snapshot_tokens(input: &str, tokens: Vec<crate::lexer::token::Token>) -> String {
     let mut tokens = std::collections::VecDeque::from(tokens);
     let mut output = String::new();
     let mut total = 0;
@@ -15,7 +23,7 @@ pub fn snapshot_lexing(input: &str) -> String {
                 break;
             }
 
-            output += &" ".repeat(tok.span.start - total);
+            output += &" ".repeat(tok.span.start.saturating_sub(total));
             output += &"^".repeat(tok.span.len());
             write!(&mut output, " {tok:?}").expect("failed to write()");
             output += "\n"
@@ -29,13 +37,16 @@ pub fn snapshot_lexing(input: &str) -> String {
 
 macro_rules! snapshot {
     ($name:tt, $path:tt) => {
+        snapshot!($name, snapshot_lexing, $path);
+    };
+    ($name:tt, $snapshot:ident, $path:tt) => {
         #[test]
         fn $name() {
             let contents = include_str!($path);
             let mut settings = insta::Settings::clone_current();
             settings.set_snapshot_path("testdata/output/");
             settings.bind(|| {
-                insta::assert_snapshot!(snapshot_lexing(contents));
+                insta::assert_snapshot!($snapshot(contents));
             });
         }
     };
@@ -43,3 +54,40 @@ macro_rules! snapshot {
 
 snapshot!(binary, "../../snapshots/basic.bitbox");
 snapshot!(import_function, "../../snapshots/import_function.bitbox");
+snapshot!(string_constant, "../../snapshots/string_constant.bitbox");
+snapshot!(directives, "../../snapshots/directives.bitbox");
+snapshot!(calls, "../../snapshots/calls.bitbox");
+snapshot!(mul, "../../snapshots/mul.bitbox");
+snapshot!(cmp, "../../snapshots/cmp.bitbox");
+snapshot!(jump, "../../snapshots/jump.bitbox");
+snapshot!(jumpif, "../../snapshots/jumpif.bitbox");
+snapshot!(phi, "../../snapshots/phi.bitbox");
+snapshot!(numbers, "../../snapshots/numbers.bitbox");
+snapshot!(
+    malformed_numbers,
+    "../../snapshots/malformed_numbers.bitbox"
+);
+snapshot!(strings, "../../snapshots/strings.bitbox");
+snapshot!(comments, "../../snapshots/comments.bitbox");
+snapshot!(
+    trivia_comments,
+    snapshot_trivia,
+    "../../snapshots/comments.bitbox"
+);
+
+#[test]
+fn trivia_round_trips() {
+    for (name, source) in snapshot_sources() {
+        let tokens = lex_with_trivia(&source);
+        let mut end = 0;
+        for token in tokens.iter() {
+            assert_eq!(token.span.start, end, "gap before {:?} in {}", token, name);
+            end = token.span.end;
+        }
+        let rebuilt = tokens
+            .iter()
+            .map(|token| &source[token.span.clone()])
+            .collect::<String>();
+        assert_eq!(rebuilt, source, "{}", name);
+    }
+}
@@ -11,22 +11,18 @@ impl Token {
     pub fn is_keyword(&self, keyword: Keyword) -> bool {
         matches!(self.kind, TokenKind::Keyword(ref k) if k == &keyword)
     }
-
-    pub fn is_instruction(&self, instruction: Instruction) -> bool {
-        matches!(self.kind, TokenKind::Instruction(ref i) if i == &instruction)
-    }
-
-    pub fn is_directive(&self, directive: Directive) -> bool {
-        matches!(self.kind, TokenKind::Directive(ref d) if d == &directive)
-    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum TokenKind {
     Colon,
     Comma,
+    /// Only returned in trivia mode
+    Comment,
     Delimiter,
     Directive(Directive),
+    /// `///` comment documenting the item after it
+    DocComment,
     Dot,
     Equals,
     Identifier,
@@ -47,11 +43,14 @@ pub enum TokenKind {
     Semicolon,
     Star,
     String,
+    /// Only returned in trivia mode
+    Whitespace,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Instruction {
     Add,
+    Assign,
     Mul,
     Call,
     Phi,
@@ -62,9 +61,27 @@ pub enum Instruction {
     Jump,
 }
 
+impl Instruction {
+    /// Every instruction with its name as written after the `@`
+    pub const ALL: [(&'static str, Self); 10] = [
+        ("add", Self::Add),
+        ("assign", Self::Assign),
+        ("call", Self::Call),
+        ("cmp", Self::Cmp),
+        ("jump", Self::Jump),
+        ("jumpif", Self::JumpIf),
+        ("mul", Self::Mul),
+        ("phi", Self::Phi),
+        ("ret", Self::Ret),
+        ("sub", Self::Sub),
+    ];
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Directive {
     Len,
+    SizeOf,
+    AlignOf,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -1,35 +1,303 @@
 use std::env::args;
+use std::io::{IsTerminal, Read, Write};
+use std::path::Path;
 
+mod cli;
 mod error;
+mod fmt;
+mod interp;
 mod lexer;
 mod parser;
 mod ssa;
 mod target;
+#[cfg(test)]
+mod test;
+
+use cli::{Command, ErrorFormat, Options, USAGE};
+use error::BitBoxError;
+use target::{Emit, Target};
+
+/// Name errors use for a program read from stdin
+const STDIN: &str = "<stdin>";
 
 fn main() {
-    let Some(filename) = args().nth(1) else {
-        eprintln!("usage: bitbox <filename>");
-        std::process::exit(1);
+    let options = match cli::parse(args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("error: {message}\n\n{USAGE}");
+            std::process::exit(1);
+        }
     };
-    let src = std::fs::read_to_string(&filename).expect("failed to read file");
-    let tokens = lexer::lex(&src);
-    let program = match parser::Parser::new(tokens).parse() {
+    if options.help {
+        println!("{USAGE}");
+        return;
+    }
+    if let Some(code) = &options.explain {
+        explain(code);
+    }
+
+    match options.command {
+        Command::Fmt => fmt(&options),
+        Command::DumpTokens => {
+            let (_, src) = read_input(options.inputs.first());
+            let mut output = String::new();
+            for token in lexer::lex(&src) {
+                output += &format!("{:?} {:?} {:?}\n", token.span, token.kind, token.lexeme);
+            }
+            write_output(options.output.as_deref(), output.as_bytes());
+        }
+        Command::DumpIr => {
+            let (filename, src) = read_input(options.inputs.first());
+            let program = analyse(&options, &filename, &src);
+            write_output(options.output.as_deref(), program.to_string().as_bytes());
+        }
+        Command::DumpCfg => {
+            let (filename, src) = read_input(options.inputs.first());
+            let program = analyse(&options, &filename, &src);
+            write_output(
+                options.output.as_deref(),
+                ssa::cfg::dot(&program).as_bytes(),
+            );
+        }
+        Command::DumpRegalloc => {
+            let (filename, src) = read_input(options.inputs.first());
+            let program = analyse(&options, &filename, &src);
+            let mut output = String::new();
+            for function in program.functions.iter() {
+                output += &target::vm::Allocation::new(function, target::vm::REGISTERS).to_string();
+            }
+            write_output(options.output.as_deref(), output.as_bytes());
+        }
+        Command::Check => {
+            let (filename, src) = read_input(options.inputs.first());
+            analyse(&options, &filename, &src);
+        }
+        Command::Build => {
+            let (filename, src) = read_input(options.inputs.first());
+            let bytes = compile(&options, options.emit, &filename, &src);
+            // Binaries and assembly are written next to the source, text and stdin input go
+            // to stdout
+            let extension = match options.emit {
+                Emit::Bin => Some(options.target.extension()),
+                Emit::Asm => Some("s"),
+                Emit::Text => None,
+            };
+            let default = match (options.inputs.first(), extension) {
+                (Some(input), Some(extension)) if input != "-" => Path::new(input)
+                    .with_extension(extension)
+                    .to_string_lossy()
+                    .into_owned(),
+                _ => "-".to_string(),
+            };
+            let path = options.output.as_deref().unwrap_or(&default);
+            write_output(Some(path), &bytes);
+            if options.target == Target::X86_64 && options.emit == Emit::Bin && path != "-" {
+                make_executable(path);
+            }
+        }
+        Command::Run => {
+            let (filename, src) = read_input(options.inputs.first());
+            let program = analyse(&options, &filename, &src);
+            // Without this the vm and executables would start nowhere and exit 0
+            if !program
+                .functions
+                .iter()
+                .any(|function| function.name == "main")
+            {
+                fail(interp::Trap::UndefinedFunction("main".to_string()).to_string());
+            }
+            let status = match options.target {
+                Target::Wasm => run(&program),
+                Target::Vm => run_vm(compile_program(
+                    &options,
+                    program,
+                    Emit::Bin,
+                    &filename,
+                    &src,
+                )),
+                Target::X86_64 => {
+                    let bytes = compile_program(&options, program, Emit::Bin, &filename, &src);
+                    run_native(&bytes)
+                }
+            };
+            std::process::exit(status);
+        }
+    }
+}
+
+/// Parses and checks the program, exits with the errors when there are any
+fn analyse(options: &Options, filename: &str, src: &str) -> ssa::Program {
+    let tokens = lexer::lex(src);
+    let mut program = match parser::Parser::new(tokens).parse() {
         Ok(program) => program,
-        Err(err) => {
-            let formated_error = err.report(&filename, &src);
-            eprintln!("{formated_error}");
-            std::process::exit(1);
+        Err(errors) => report_errors(&errors, options.error_format, filename, src),
+    };
+    if let Err(err) = ssa::analyse(&mut program) {
+        report_errors(&[err], options.error_format, filename, src);
+    }
+    program
+}
+
+fn compile(options: &Options, emit: Emit, filename: &str, src: &str) -> Vec<u8> {
+    let program = analyse(options, filename, src);
+    compile_program(options, program, emit, filename, src)
+}
+
+/// Compiles a program `analyse` returned for the target, exits with the error when it fails
+fn compile_program(
+    options: &Options,
+    program: ssa::Program,
+    emit: Emit,
+    filename: &str,
+    src: &str,
+) -> Vec<u8> {
+    match options.target.compile(program, emit) {
+        Ok(bytes) => bytes,
+        Err(err) => report_errors(&[err], options.error_format, filename, src),
+    }
+}
+
+/// Interprets `main`, returning the status the program exits with
+fn run(program: &ssa::Program) -> i32 {
+    let mut interpreter = interp::Interpreter::new(program).with_core(std::io::stdout());
+    match interpreter.call("main", &[]) {
+        Ok(Some(status)) => status.bits() as i32,
+        Ok(None) => 0,
+        Err(interp::Trap::Exit(status)) => status,
+        Err(trap) => fail(format!("{trap}")),
+    }
+}
+
+/// Runs bytecode compiled for the vm, returning the status the program exits with
+fn run_vm(bytes: Vec<u8>) -> i32 {
+    let vm = target::vm::Vm::new(bytes).map(|vm| vm.with_output(std::io::stdout()));
+    match vm.and_then(|mut vm| vm.run()) {
+        Ok(status) => status,
+        Err(fault) => fail(format!("{fault}")),
+    }
+}
+
+/// Runs an executable from a temporary file, returning the status it exits with
+fn run_native(bytes: &[u8]) -> i32 {
+    let path = std::env::temp_dir().join(format!("bitbox-{}", std::process::id()));
+    let path = path.to_string_lossy();
+    write_output(Some(&path), bytes);
+    make_executable(&path);
+    let status = std::process::Command::new(path.as_ref()).status();
+    // The program has already run, a file left behind in the temporary directory is harmless
+    let _ = std::fs::remove_file(path.as_ref());
+    match status {
+        Ok(status) => status.code().unwrap_or(1),
+        Err(err) => fail(format!("failed to run '{path}': {err}")),
+    }
+}
+
+/// Lets everyone run the file at `path`
+fn make_executable(path: &str) {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let permissions = std::fs::Permissions::from_mode(0o755);
+        if let Err(err) = std::fs::set_permissions(path, permissions) {
+            fail(format!("failed to make '{path}' executable: {err}"));
         }
+    }
+}
+
+/// Formats every file in place, or with `--check` only reports the files that aren't
+/// formatted. Stdin is formatted to stdout.
+fn fmt(options: &Options) {
+    let inputs = if options.inputs.is_empty() {
+        vec!["-".to_string()]
+    } else {
+        options.inputs.clone()
     };
-    let module = match target::wasm::Emitter::new(program).with_no_main().emit() {
-        Ok(module) => module,
-        Err(err) => {
-            let formated_error = err.report(&filename, &src);
-            eprintln!("{formated_error}");
-            std::process::exit(1);
+    let mut unformatted = false;
+    for input in inputs.iter() {
+        let (filename, src) = read_input(Some(input));
+        let formatted = match fmt::format(&src) {
+            Ok(formatted) => formatted,
+            Err(errors) => report_errors(&errors, options.error_format, &filename, &src),
+        };
+        if options.check {
+            if formatted != src {
+                eprintln!("{filename} is not formatted");
+                unformatted = true;
+            }
+        } else if input == "-" {
+            write_output(None, formatted.as_bytes());
+        } else if formatted != src {
+            write_output(Some(input), formatted.as_bytes());
+        }
+    }
+    if unformatted {
+        std::process::exit(1);
+    }
+}
+
+/// The name to report errors with and the source of a file, or of stdin for `-` and no file
+fn read_input(input: Option<&String>) -> (String, String) {
+    match input.map(String::as_str) {
+        None | Some("-") => {
+            let mut stdin = std::io::stdin();
+            if input.is_none() && stdin.is_terminal() {
+                eprintln!("error: no input file\n\n{USAGE}");
+                std::process::exit(1);
+            }
+            let mut src = String::new();
+            if let Err(err) = stdin.read_to_string(&mut src) {
+                fail(format!("failed to read stdin: {err}"));
+            }
+            (STDIN.to_string(), src)
         }
+        Some(filename) => match std::fs::read_to_string(filename) {
+            Ok(src) => (filename.to_string(), src),
+            Err(err) => fail(format!("failed to read '{filename}': {err}")),
+        },
+    }
+}
+
+/// Writes to the file at `path`, or to stdout for `-` and no path
+fn write_output(path: Option<&str>, bytes: &[u8]) {
+    let written = match path {
+        None | Some("-") => std::io::stdout().write_all(bytes),
+        Some(path) => std::fs::write(path, bytes),
     };
-    let bytes = module.to_bytes().unwrap();
-    let (binary_name, _) = filename.split_once('.').unwrap();
-    std::fs::write(format!("{}.wasm", binary_name), bytes).unwrap();
+    if let Err(err) = written {
+        fail(format!(
+            "failed to write '{}': {err}",
+            path.unwrap_or("stdout")
+        ));
+    }
+}
+
+fn fail(message: String) -> ! {
+    eprintln!("error: {message}");
+    std::process::exit(1);
+}
+
+fn explain(code: &str) -> ! {
+    match error::explain::explain(code) {
+        Some(explanation) => {
+            print!("{}", explanation);
+            std::process::exit(0);
+        }
+        None => {
+            eprintln!("'{code}' is not a bitbox error code");
+            std::process::exit(1);
+        }
+    }
+}
+
+fn report_errors(errors: &[BitBoxError], format: ErrorFormat, filename: &str, src: &str) -> ! {
+    for err in errors.iter() {
+        match format {
+            ErrorFormat::Human => eprintln!("{}", err.report(filename, src)),
+            ErrorFormat::Json => eprintln!("{}", err.report_json(filename, src)),
+        }
+    }
+    if format == ErrorFormat::Human {
+        eprintln!("for more information about an error, try `bitbox --explain <code>`");
+    }
+    std::process::exit(1);
 }
@@ -1,36 +1,116 @@
 #[cfg(test)]
 mod test;
 use crate::error::BitBoxError;
-use crate::lexer::token::{self, Span, Token, TokenKind};
+use crate::lexer::token::{self, Token, TokenKind};
+use crate::ssa::literal::{self, Literal};
 use crate::ssa::{self, IntoSsaType};
 
 enum TopLevel {
-    Function(ssa::Function),
+    /// The function and its name's token, `ssa::Function` only keeps the name's text
+    Function(ssa::Function, Token),
     Import(ssa::Import),
     Constant(ssa::Constant),
 }
 
 pub struct Parser {
     stream: std::iter::Peekable<std::vec::IntoIter<Token>>,
+    /// Kind of the last token taken from the stream
+    previous: Option<TokenKind>,
+    errors: Vec<BitBoxError>,
 }
 
 // Helpers
 impl Parser {
     fn parse_visibility(&mut self) -> ssa::Visibility {
-        self.stream
-            .next_if(|token| token.is_keyword(token::Keyword::Public))
-            .map(|_| ssa::Visibility::Public)
-            .unwrap_or_default()
+        let public = self
+            .stream
+            .peek()
+            .is_some_and(|token| token.is_keyword(token::Keyword::Public));
+        if !public {
+            return ssa::Visibility::default();
+        }
+        self.advance();
+        ssa::Visibility::Public
     }
 
+    /// Only takes the next token when it is the expected kind so recovery can resume from it
     fn consume(&mut self, expected: TokenKind) -> Result<Token, BitBoxError> {
-        match self.stream.next() {
-            Some(actual) if actual.kind == expected => Ok(actual),
-            Some(actual) => Err(BitBoxError::UnexpectedToken { expected, actual }),
+        match self.stream.peek() {
+            Some(actual) if actual.kind == expected => {
+                Ok(self.advance().expect("token was peeked"))
+            }
+            Some(actual) if actual.kind == TokenKind::InvalidToken => {
+                Err(BitBoxError::InvalidToken(actual.clone()))
+            }
+            Some(actual) => Err(BitBoxError::UnexpectedToken {
+                expected,
+                actual: actual.clone(),
+            }),
             None => Err(BitBoxError::UnexpectedEndOfStream),
         }
     }
 
+    fn advance(&mut self) -> Option<Token> {
+        let token = self.stream.next()?;
+        self.previous = Some(token.kind.clone());
+        Some(token)
+    }
+
+    fn is_peek_top_level(&mut self) -> bool {
+        [
+            token::Keyword::Public,
+            token::Keyword::Function,
+            token::Keyword::Import,
+            token::Keyword::Const,
+        ]
+        .into_iter()
+        .any(|keyword| self.is_peek_a(TokenKind::Keyword(keyword)))
+    }
+
+    /// Skips to the start of the next top level item
+    fn synchronize_top_level(&mut self) {
+        while !self.end_of_stream() && !self.is_peek_top_level() {
+            self.advance();
+        }
+    }
+
+    /// Skips past the end of the broken instruction, stopping early at anything that can't be
+    /// part of an instruction
+    fn synchronize_instruction(&mut self) {
+        if self.previous == Some(TokenKind::Delimiter) {
+            return;
+        }
+        while !self.end_of_stream()
+            && !self.is_peek_a(TokenKind::RightBrace)
+            && !self.is_peek_a(TokenKind::LabelDefinition)
+            && !self.is_peek_top_level()
+        {
+            if self
+                .advance()
+                .is_some_and(|token| token.kind == TokenKind::Delimiter)
+            {
+                return;
+            }
+        }
+    }
+
+    /// Blank lines and lines with only a comment leave extra delimiters
+    fn skip_delimiters(&mut self) {
+        while self.is_peek_a(TokenKind::Delimiter) {
+            self.advance();
+        }
+    }
+
+    /// Takes the `///` comments before an item, they may be separated by blank lines
+    fn parse_doc_comments(&mut self) -> Vec<Token> {
+        let mut comments = vec![];
+        while self.is_peek_a(TokenKind::DocComment) {
+            comments.extend(self.advance());
+            self.skip_delimiters();
+        }
+        comments
+    }
+
     fn is_peek_a(&mut self, kind: TokenKind) -> bool {
         matches!(self.stream.peek(), Some(token) if token.kind == kind)
     }
@@ -40,7 +120,7 @@ impl Parser {
     }
 
     fn next(&mut self) -> Result<Token, BitBoxError> {
-        let Some(token) = self.stream.next() else {
+        let Some(token) = self.advance() else {
             return Err(BitBoxError::UnexpectedEndOfStream);
         };
         Ok(token)
@@ -51,22 +131,46 @@ impl Parser {
     pub fn new(tokens: Vec<Token>) -> Self {
         Self {
             stream: tokens.into_iter().peekable(),
+            previous: None,
+            errors: vec![],
         }
     }
 
-    pub fn parse(&mut self) -> Result<ssa::Program, BitBoxError> {
+    /// Parses the whole stream, reporting every error found rather than stopping at the first
+    pub fn parse(&mut self) -> Result<ssa::Program, Vec<BitBoxError>> {
         let mut imports = vec![];
         let mut functions = vec![];
         let mut constants = vec![];
+        // Names of the functions and imports so far, calls can't tell two of them apart
+        let mut defined: Vec<Token> = vec![];
 
-        while !self.end_of_stream() {
-            match self.parse_top_level()? {
-                TopLevel::Import(import) => imports.push(import),
-                TopLevel::Function(func) => functions.push(func),
-                TopLevel::Constant(constant) => constants.push(constant),
+        loop {
+            self.skip_delimiters();
+            if self.end_of_stream() {
+                break;
+            }
+            match self.parse_top_level() {
+                Ok(TopLevel::Import(import)) => {
+                    let ssa::Import::Function(spec) = &import;
+                    self.define(&mut defined, &spec.name);
+                    imports.push(import);
+                }
+                Ok(TopLevel::Function(func, name)) => {
+                    self.define(&mut defined, &name);
+                    functions.push(func);
+                }
+                Ok(TopLevel::Constant(constant)) => constants.push(constant),
+                Err(err) => {
+                    self.errors.push(err);
+                    self.synchronize_top_level();
+                }
             }
         }
 
+        if !self.errors.is_empty() {
+            return Err(std::mem::take(&mut self.errors));
+        }
+
         Ok(ssa::Program {
             functions,
             imports,
@@ -74,16 +178,39 @@ impl Parser {
         })
     }
 
+    /// Reports `name` when a function or import was already defined with it
+    fn define(&mut self, defined: &mut Vec<Token>, name: &Token) {
+        match defined
+            .iter()
+            .find(|previous| previous.lexeme == name.lexeme)
+        {
+            Some(previous) => self.errors.push(BitBoxError::Redefinition {
+                name: name.clone(),
+                previous: previous.clone(),
+            }),
+            None => defined.push(name.clone()),
+        }
+    }
+
     fn parse_top_level(&mut self) -> Result<TopLevel, BitBoxError> {
+        let comments = self.parse_doc_comments();
+        let doc = doc_text(&comments);
         let visibility = self.parse_visibility();
         if self.is_peek_a(TokenKind::Keyword(token::Keyword::Function)) {
-            Ok(TopLevel::Function(self.parse_function(visibility)?))
+            let (function, name) = self.parse_function(visibility)?;
+            Ok(TopLevel::Function(ssa::Function { doc, ..function }, name))
+        } else if self.is_peek_a(TokenKind::Keyword(token::Keyword::Const)) {
+            let constant = self.parse_constant()?;
+            Ok(TopLevel::Constant(ssa::Constant { doc, ..constant }))
+        } else if let Some(comment) = comments.into_iter().next() {
+            Err(BitBoxError::DanglingDocComment(comment))
         } else if self.is_peek_a(TokenKind::Keyword(token::Keyword::Import)) {
             Ok(TopLevel::Import(self.parse_import()?))
-        } else if self.is_peek_a(TokenKind::Keyword(token::Keyword::Const)) {
-            Ok(TopLevel::Constant(self.parse_constant()?))
         } else {
             let tok = self.next()?;
+            if tok.kind == TokenKind::InvalidToken {
+                return Err(BitBoxError::InvalidToken(tok));
+            }
             Err(BitBoxError::ExpectedTopLevelItem(tok))
         }
     }
@@ -91,20 +218,22 @@ impl Parser {
     fn parse_function(
         &mut self,
         visibility: ssa::Visibility,
-    ) -> Result<ssa::Function, BitBoxError> {
+    ) -> Result<(ssa::Function, Token), BitBoxError> {
         self.consume(TokenKind::Keyword(token::Keyword::Function))?;
         let func_name = self.consume(TokenKind::Identifier)?;
         let params = self.parse_function_params()?;
         let return_type = self.parse_type()?;
         let blocks = self.parse_function_block()?;
 
-        Ok(ssa::Function {
+        let function = ssa::Function {
+            doc: None,
             visibility,
-            name: func_name.lexeme,
+            name: func_name.lexeme.clone(),
             params,
             return_type,
             blocks,
-        })
+        };
+        Ok((function, func_name))
     }
 
     fn parse_function_params(&mut self) -> Result<Vec<ssa::Variable>, BitBoxError> {
@@ -130,49 +259,109 @@ impl Parser {
     }
 
     fn parse_function_block(&mut self) -> Result<Vec<ssa::BasicBlock>, BitBoxError> {
-        let mut blocks = vec![];
+        let mut blocks: Vec<ssa::BasicBlock> = vec![];
         self.consume(TokenKind::LeftBrace)?;
-        while !self.end_of_stream() && !self.is_peek_a(TokenKind::RightBrace) {
-            let block = self.parse_basic_block()?;
+        loop {
+            self.skip_delimiters();
+            if self.end_of_stream() || self.is_peek_a(TokenKind::RightBrace) {
+                break;
+            }
+            let label = self.parse_label_definition()?;
+            if let Some(label) = &label {
+                let previous = blocks
+                    .iter()
+                    .filter_map(|block| block.label.as_ref())
+                    .find(|previous| previous.lexeme == label.lexeme);
+                if let Some(previous) = previous {
+                    self.errors.push(BitBoxError::DuplicateLabel {
+                        label: label.clone(),
+                        previous: previous.clone(),
+                    });
+                }
+            }
+            let block = self.parse_basic_block(blocks.len(), label);
             blocks.push(block);
+            if self.is_peek_top_level() {
+                break;
+            }
+        }
+        let closing = self.consume(TokenKind::RightBrace)?;
+        // An empty body has no block to end with a terminator, control would run off its end
+        if blocks.is_empty() {
+            // The brace's token also holds the newlines after it
+            let span = closing.span.start..closing.span.start + 1;
+            self.errors
+                .push(BitBoxError::MissingTerminator(Token { span, ..closing }));
         }
-        self.consume(TokenKind::RightBrace)?;
+        self.errors.extend(check_labels(&blocks));
+        self.errors.extend(resolve_phi_labels(&mut blocks));
         Ok(blocks)
     }
 
-    fn parse_basic_block(&mut self) -> Result<ssa::BasicBlock, BitBoxError> {
+    /// A `%name:` starts a new block, the entry block may be left unnamed
+    fn parse_label_definition(&mut self) -> Result<Option<Token>, BitBoxError> {
+        if !self.is_peek_a(TokenKind::LabelDefinition) {
+            return Ok(None);
+        }
+        let label = self.consume(TokenKind::LabelDefinition)?;
+        if self.is_peek_a(TokenKind::Delimiter) {
+            self.consume(TokenKind::Delimiter)?;
+        }
+        Ok(Some(label))
+    }
+
+    /// Errors in an instruction are recorded and parsing resumes at the next instruction
+    fn parse_basic_block(&mut self, id: usize, label: Option<Token>) -> ssa::BasicBlock {
         let mut instructions = vec![];
 
-        while !self.end_of_stream() && !self.is_peek_a(TokenKind::RightBrace) {
-            let Some(instruction) = self.parse_instruction()? else {
+        loop {
+            self.skip_delimiters();
+            if self.end_of_stream()
+                || self.is_peek_a(TokenKind::RightBrace)
+                || self.is_peek_a(TokenKind::LabelDefinition)
+                || self.is_peek_top_level()
+            {
                 break;
-            };
-            instructions.push(instruction);
+            }
+            if self.is_peek_a(TokenKind::DocComment) {
+                let comment = self.advance().expect("token was peeked");
+                self.errors.push(BitBoxError::DanglingDocComment(comment));
+                continue;
+            }
+            match self.parse_instruction() {
+                Ok(Some(instruction)) => instructions.push(instruction),
+                Ok(None) => break,
+                Err(err) => {
+                    self.errors.push(err);
+                    self.synchronize_instruction();
+                }
+            }
         }
 
-        Ok(ssa::BasicBlock {
-            id: 0,
+        ssa::BasicBlock {
+            id,
+            label,
             instructions,
-            // TODO: Add predecessors and successors
+            // Filled in by ssa::cfg::build
             successors: vec![],
             predecessors: vec![],
-        })
+        }
     }
 
     fn parse_instruction(&mut self) -> Result<Option<ssa::Instruction>, BitBoxError> {
         let tok = self.next()?;
-        let Token {
-            kind: TokenKind::Instruction(instruction),
-            lexeme,
-            span,
-        } = tok
-        else {
+        let TokenKind::Instruction(instruction) = tok.kind else {
             return Err(BitBoxError::InvalidInstruction(tok));
         };
         match instruction {
             token::Instruction::Ret => self.parse_return(),
+            token::Instruction::Assign => self.parse_assign(),
             token::Instruction::Add => self.parse_add(),
             token::Instruction::Sub => self.parse_sub(),
+            token::Instruction::Mul => self.parse_mul(),
+            token::Instruction::Cmp => self.parse_cmp(),
+            token::Instruction::Jump => self.parse_jump(),
+            token::Instruction::JumpIf => self.parse_jumpif(),
             token::Instruction::Call => self.parse_call(),
             token::Instruction::Phi => self.parse_phi(),
         }
@@ -196,6 +385,7 @@ impl Parser {
     fn parse_operand(&mut self) -> Result<ssa::Operand, BitBoxError> {
         if self.is_peek_a(TokenKind::Number) {
             let tok = self.consume(TokenKind::Number)?;
+            Literal::parse(&tok)?;
             return Ok(ssa::Operand::Constant(tok));
         }
         let tok = self.consume(TokenKind::Identifier)?;
@@ -237,12 +427,27 @@ impl Parser {
     fn parse_constant_value(&mut self) -> Result<ssa::ConstantValue, BitBoxError> {
         let tok = self.next()?;
         match tok.kind {
-            TokenKind::String => Ok(ssa::ConstantValue::String(tok)),
+            TokenKind::String => {
+                literal::string_bytes(&tok)?;
+                Ok(ssa::ConstantValue::String(tok))
+            }
+            TokenKind::Number => {
+                Literal::parse(&tok)?;
+                Ok(ssa::ConstantValue::Number(tok))
+            }
             TokenKind::Directive(directive) => match directive {
                 token::Directive::Len => {
                     let value = self.consume(TokenKind::Identifier)?;
                     Ok(ssa::ConstantValue::Directive(ssa::Directive::Len(value)))
                 }
+                token::Directive::SizeOf => {
+                    let ty = self.parse_type()?;
+                    Ok(ssa::ConstantValue::Directive(ssa::Directive::SizeOf(ty)))
+                }
+                token::Directive::AlignOf => {
+                    let ty = self.parse_type()?;
+                    Ok(ssa::ConstantValue::Directive(ssa::Directive::AlignOf(ty)))
+                }
             },
             _ => Err(BitBoxError::InvalidContantValue(tok)),
         }
@@ -256,28 +461,34 @@ impl Parser {
         self.consume(TokenKind::Equals)?;
         let value = self.parse_constant_value()?;
         self.consume(TokenKind::Delimiter)?;
-        Ok(ssa::Constant { name, ty, value })
+        Ok(ssa::Constant {
+            doc: None,
+            name,
+            ty,
+            value,
+        })
     }
 
     fn parse_type(&mut self) -> Result<ssa::Type, BitBoxError> {
         let tok = self.next()?;
         match tok.kind {
-            TokenKind::Identifier => tok
-                .into_ssa_type()
-                .map_err(|tok| BitBoxError::ExpectedType(tok)),
+            TokenKind::Identifier => match tok.into_ssa_type() {
+                Ok(ssa::Type::Float(bits)) if bits != 32 && bits != 64 => {
+                    Err(BitBoxError::InvalidFloatWidth(tok))
+                }
+                ty => ty.map_err(BitBoxError::ExpectedType),
+            },
             TokenKind::Star => {
                 let ty = self.parse_type()?;
                 Ok(ssa::Type::Pointer(Box::new(ty)))
             }
             TokenKind::LeftBracket => {
                 let count = self.consume(TokenKind::Number)?;
+                let length = literal::typed_value(&count, &ssa::Type::Unsigned(32))?;
                 self.consume(TokenKind::Semicolon)?;
                 let ty = self.parse_type()?;
                 self.consume(TokenKind::RightBracket)?;
-                Ok(ssa::Type::Array(
-                    count.lexeme.parse().unwrap(),
-                    Box::new(ty),
-                ))
+                Ok(ssa::Type::Array(length.bits() as usize, Box::new(ty)))
             }
             _ => Err(BitBoxError::ExpectedType(tok)),
         }
@@ -325,6 +536,71 @@ impl Parser {
         Ok(Some(ssa::Instruction::Sub(des, lhs, rhs)))
     }
 
+    fn parse_mul(&mut self) -> Result<Option<ssa::Instruction>, BitBoxError> {
+        let ty = self.parse_type()?;
+        self.consume(TokenKind::Colon)?;
+        let name = self.consume(TokenKind::Identifier)?;
+        let des = ssa::Variable {
+            name,
+            ty,
+            version: 0,
+        };
+        self.consume(TokenKind::Comma)?;
+        let lhs = self.parse_operand()?;
+        self.consume(TokenKind::Comma)?;
+        let rhs = self.parse_operand()?;
+        self.consume(TokenKind::Delimiter)?;
+        Ok(Some(ssa::Instruction::Mul(des, lhs, rhs)))
+    }
+
+    fn parse_cmp(&mut self) -> Result<Option<ssa::Instruction>, BitBoxError> {
+        let tok = self.consume(TokenKind::Identifier)?;
+        let condition = ssa::Condition::try_from(&tok).map_err(BitBoxError::InvalidCondition)?;
+        let ty = self.parse_type()?;
+        self.consume(TokenKind::Colon)?;
+        let name = self.consume(TokenKind::Identifier)?;
+        let des = ssa::Variable {
+            name,
+            ty,
+            version: 0,
+        };
+        self.consume(TokenKind::Comma)?;
+        let lhs = self.parse_operand()?;
+        self.consume(TokenKind::Comma)?;
+        let rhs = self.parse_operand()?;
+        self.consume(TokenKind::Delimiter)?;
+        Ok(Some(ssa::Instruction::Cmp(des, condition, lhs, rhs)))
+    }
+
+    fn parse_jump(&mut self) -> Result<Option<ssa::Instruction>, BitBoxError> {
+        let label = self.consume(TokenKind::Label)?;
+        self.consume(TokenKind::Delimiter)?;
+        Ok(Some(ssa::Instruction::Jump(label)))
+    }
+
+    fn parse_jumpif(&mut self) -> Result<Option<ssa::Instruction>, BitBoxError> {
+        let condition = self.parse_operand()?;
+        self.consume(TokenKind::Comma)?;
+        let label = self.consume(TokenKind::Label)?;
+        self.consume(TokenKind::Delimiter)?;
+        Ok(Some(ssa::Instruction::JumpIf(condition, label)))
+    }
+
+    fn parse_assign(&mut self) -> Result<Option<ssa::Instruction>, BitBoxError> {
+        let ty = self.parse_type()?;
+        self.consume(TokenKind::Colon)?;
+        let name = self.consume(TokenKind::Identifier)?;
+        let des = ssa::Variable {
+            name,
+            ty,
+            version: 0,
+        };
+        self.consume(TokenKind::Comma)?;
+        let value = self.parse_operand()?;
+        self.consume(TokenKind::Delimiter)?;
+        Ok(Some(ssa::Instruction::Assign(des, value)))
+    }
+
     fn parse_call(&mut self) -> Result<Option<ssa::Instruction>, BitBoxError> {
         let ty = self.parse_type()?;
         self.consume(TokenKind::Colon)?;
@@ -340,7 +616,85 @@ impl Parser {
         Ok(Some(ssa::Instruction::Call(des, name, arguments)))
     }
 
-    fn parse_phi(&self) -> Result<Option<ssa::Instruction>, BitBoxError> {
-        todo!("implement phi instruciton parser")
+    fn parse_phi(&mut self) -> Result<Option<ssa::Instruction>, BitBoxError> {
+        let ty = self.parse_type()?;
+        self.consume(TokenKind::Colon)?;
+        let name = self.consume(TokenKind::Identifier)?;
+        let des = ssa::Variable {
+            name,
+            ty: ty.clone(),
+            version: 0,
+        };
+        let mut incoming = vec![];
+        while self.is_peek_a(TokenKind::Comma) {
+            self.consume(TokenKind::Comma)?;
+            self.consume(TokenKind::LeftBracket)?;
+            let name = self.consume(TokenKind::Identifier)?;
+            self.consume(TokenKind::Comma)?;
+            let label = self.consume(TokenKind::Label)?;
+            self.consume(TokenKind::RightBracket)?;
+            let value = ssa::Variable {
+                name,
+                ty: ty.clone(),
+                version: 0,
+            };
+            // The block id is filled in by resolve_phi_labels
+            incoming.push((value, label, usize::MAX));
+        }
+        self.consume(TokenKind::Delimiter)?;
+        Ok(Some(ssa::Instruction::Phi(des, incoming)))
+    }
+}
+
+/// Every label a jump refers to must be defined in the same function
+fn check_labels(blocks: &[ssa::BasicBlock]) -> Vec<BitBoxError> {
+    blocks
+        .iter()
+        .flat_map(|block| block.instructions.iter())
+        .filter_map(|instruction| match instruction {
+            ssa::Instruction::Jump(label) | ssa::Instruction::JumpIf(_, label) => Some(label),
+            _ => None,
+        })
+        .filter(|label| ssa::find_block(blocks, label).is_none())
+        .map(|label| BitBoxError::UndefinedLabel(label.clone()))
+        .collect()
+}
+
+/// Replaces the placeholder block ids in every phi with the id of the block its label names
+fn resolve_phi_labels(blocks: &mut [ssa::BasicBlock]) -> Vec<BitBoxError> {
+    let ids = blocks
+        .iter()
+        .filter_map(|block| Some((block.label.as_ref()?.lexeme.clone(), block.id)))
+        .collect::<Vec<_>>();
+    let mut errors = vec![];
+    for instruction in blocks
+        .iter_mut()
+        .flat_map(|block| block.instructions.iter_mut())
+    {
+        let ssa::Instruction::Phi(_, incoming) = instruction else {
+            continue;
+        };
+        for (_, label, id) in incoming.iter_mut() {
+            match ids.iter().find(|(name, _)| *name == label.lexeme) {
+                Some((_, block)) => *id = *block,
+                None => errors.push(BitBoxError::UndefinedLabel(label.clone())),
+            }
+        }
+    }
+    errors
+}
+
+/// Text of doc comments without the `///` and the space after it, one line per comment
+fn doc_text(comments: &[Token]) -> Option<String> {
+    if comments.is_empty() {
+        return None;
     }
+    let lines = comments.iter().map(|comment| {
+        let text = comment
+            .lexeme
+            .strip_prefix("///")
+            .unwrap_or(&comment.lexeme);
+        text.strip_prefix(' ').unwrap_or(text).trim_end()
+    });
+    Some(lines.collect::<Vec<_>>().join("\n"))
 }
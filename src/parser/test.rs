@@ -23,3 +23,19 @@ macro_rules! snapshot {
 
 snapshot!(binary, "../../snapshots/basic.bitbox");
 snapshot!(import_function, "../../snapshots/import_function.bitbox");
+snapshot!(string_constant, "../../snapshots/string_constant.bitbox");
+snapshot!(directives, "../../snapshots/directives.bitbox");
+snapshot!(calls, "../../snapshots/calls.bitbox");
+snapshot!(mul, "../../snapshots/mul.bitbox");
+snapshot!(cmp, "../../snapshots/cmp.bitbox");
+snapshot!(jump, "../../snapshots/jump.bitbox");
+snapshot!(jumpif, "../../snapshots/jumpif.bitbox");
+snapshot!(duplicate_label, "../../snapshots/duplicate_label.bitbox");
+snapshot!(
+    function_redefinition,
+    "../../snapshots/function_redefinition.bitbox"
+);
+snapshot!(undefined_label, "../../snapshots/undefined_label.bitbox");
+snapshot!(phi, "../../snapshots/phi.bitbox");
+snapshot!(recovery, "../../snapshots/recovery.bitbox");
+snapshot!(comments, "../../snapshots/comments.bitbox");
@@ -0,0 +1,100 @@
+use super::{find_block, BasicBlock, Instruction, Program};
+use crate::error::BitBoxError;
+use std::fmt::Write;
+
+/// Fills `BasicBlock::successors` and `BasicBlock::predecessors` for every function from the
+/// terminator each block ends with. `@jumpif` also falls through to the block after it.
+pub fn build(program: &mut Program) -> Result<(), BitBoxError> {
+    for function in program.functions.iter_mut() {
+        build_function(&mut function.blocks)?;
+    }
+    Ok(())
+}
+
+fn build_function(blocks: &mut [BasicBlock]) -> Result<(), BitBoxError> {
+    let mut edges = vec![];
+    for index in 0..blocks.len() {
+        for successor in successors_of(blocks, index)? {
+            if !edges.contains(&(index, successor)) {
+                edges.push((index, successor));
+            }
+        }
+    }
+
+    for block in blocks.iter_mut() {
+        block.successors.clear();
+        block.predecessors.clear();
+    }
+    for (from, to) in edges {
+        blocks[from].successors.push(blocks[to].id);
+        blocks[to].predecessors.push(blocks[from].id);
+    }
+    Ok(())
+}
+
+/// The CFG of every function as a graphviz digraph, needs `build` to have run first
+pub fn dot(program: &Program) -> String {
+    let mut output = String::new();
+    for function in program.functions.iter() {
+        writeln!(&mut output, "digraph \"{}\" {{", function.name).expect("failed to write()");
+        for block in function.blocks.iter() {
+            let name = match &block.label {
+                Some(label) => format!("%{}", label.lexeme),
+                None => "entry".to_string(),
+            };
+            writeln!(&mut output, "    {} [label=\"{}\"];", block.id, name)
+                .expect("failed to write()");
+        }
+        for block in function.blocks.iter() {
+            for successor in block.successors.iter() {
+                writeln!(&mut output, "    {} -> {};", block.id, successor)
+                    .expect("failed to write()");
+            }
+        }
+        output += "}\n";
+    }
+    output
+}
+
+/// Indices of the blocks control can move to from the end of `blocks[index]`
+fn successors_of(blocks: &[BasicBlock], index: usize) -> Result<Vec<usize>, BitBoxError> {
+    let block = &blocks[index];
+    let Some(position) = block
+        .instructions
+        .iter()
+        .position(Instruction::is_terminator)
+    else {
+        let token = block
+            .instructions
+            .last()
+            .map(Instruction::token)
+            .or(block.label.as_ref())
+            .expect("only the entry block can be unnamed and it is never empty");
+        return Err(BitBoxError::MissingTerminator(token.clone()));
+    };
+    if let Some(unreachable) = block.instructions.get(position + 1) {
+        return Err(BitBoxError::UnreachableInstruction(
+            unreachable.token().clone(),
+        ));
+    }
+
+    let position_of = |label| {
+        let target = find_block(blocks, label).expect("labels are checked by the parser");
+        blocks
+            .iter()
+            .position(|block| block.id == target.id)
+            .expect("block was found")
+    };
+
+    match &block.instructions[position] {
+        Instruction::Jump(label) => Ok(vec![position_of(label)]),
+        Instruction::JumpIf(_, label) => {
+            if index + 1 == blocks.len() {
+                return Err(BitBoxError::MissingTerminator(label.clone()));
+            }
+            Ok(vec![position_of(label), index + 1])
+        }
+        Instruction::Return(..) => Ok(vec![]),
+        _ => unreachable!("not a terminator"),
+    }
+}
@@ -0,0 +1,61 @@
+use super::{literal, Constant, ConstantValue, Directive, Program, Type};
+use crate::error::BitBoxError;
+use crate::lexer::token::{Token, TokenKind};
+
+/// Replaces every directive in `Program::constants` with the number it evaluates to, which must
+/// fit in the constant's type. A directive may only refer to constants defined before it.
+pub fn evaluate(program: &mut Program) -> Result<(), BitBoxError> {
+    for index in 0..program.constants.len() {
+        let (defined, rest) = program.constants.split_at_mut(index);
+        let constant = &mut rest[0];
+        let ConstantValue::Directive(directive) = &constant.value else {
+            continue;
+        };
+        let value = evaluate_directive(directive, defined)?;
+        let number = Token {
+            kind: TokenKind::Number,
+            lexeme: value.to_string(),
+            span: constant.name.span.clone(),
+        };
+        literal::typed_value(&number, &constant.ty)?;
+        constant.value = ConstantValue::Number(number);
+    }
+    Ok(())
+}
+
+fn evaluate_directive(directive: &Directive, defined: &[Constant]) -> Result<usize, BitBoxError> {
+    match directive {
+        Directive::Len(target) => {
+            let Some(constant) = defined
+                .iter()
+                .find(|constant| constant.name.lexeme == target.lexeme)
+            else {
+                return Err(BitBoxError::UndefinedVariable(target.clone()));
+            };
+            length_of(constant)?.ok_or_else(|| BitBoxError::InvalidType {
+                expected: "string or array".to_string(),
+                actual_type: constant.ty.to_string(),
+                actual: target.clone(),
+                definition: Some(Box::new(constant.name.clone())),
+            })
+        }
+        Directive::SizeOf(ty) => Ok(ty.size()),
+        Directive::AlignOf(ty) => Ok(ty.align()),
+    }
+}
+
+/// Strings are measured in bytes, arrays and pointers to arrays by their element count
+fn length_of(constant: &Constant) -> Result<Option<usize>, BitBoxError> {
+    if let ConstantValue::String(value) = &constant.value {
+        return literal::string_bytes(value).map(|bytes| Some(bytes.len()));
+    }
+    let length = match &constant.ty {
+        Type::Array(count, _) => Some(*count),
+        Type::Pointer(ty) => match ty.as_ref() {
+            Type::Array(count, _) => Some(*count),
+            _ => None,
+        },
+        _ => None,
+    };
+    Ok(length)
+}
@@ -0,0 +1,211 @@
+//! Prints the IR back as `.bitbox` source. The output parses back into the same program, so
+//! it can be used to dump the IR between passes.
+use super::{
+    BasicBlock, Condition, Constant, ConstantValue, Directive, Function, FunctionSpec, Import,
+    Instruction, Operand, Program, Type, Variable, Visibility,
+};
+use std::fmt::{Display, Formatter, Result};
+
+impl Display for Type {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Self::Unsigned(bits) => write!(f, "u{}", bits),
+            Self::Signed(bits) => write!(f, "s{}", bits),
+            Self::Float(bits) => write!(f, "f{}", bits),
+            Self::Pointer(ty) => write!(f, "*{}", ty),
+            Self::Array(count, ty) => write!(f, "[{}; {}]", count, ty),
+            Self::Void => write!(f, "void"),
+        }
+    }
+}
+
+impl Display for Condition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let condition = match self {
+            Self::Eq => "eq",
+            Self::Ne => "ne",
+            Self::Lt => "lt",
+            Self::Le => "le",
+            Self::Gt => "gt",
+            Self::Ge => "ge",
+        };
+        write!(f, "{}", condition)
+    }
+}
+
+impl Display for Operand {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{}", self.token().lexeme)
+    }
+}
+
+/// A parameter, `name: type`
+impl Display for Variable {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{}: {}", self.name.lexeme, self.ty)
+    }
+}
+
+impl Display for Instruction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Self::Assign(variable, value) => {
+                write!(
+                    f,
+                    "@assign {} : {}, {}",
+                    variable.ty, variable.name.lexeme, value
+                )
+            }
+            Self::Add(variable, lhs, rhs) => binary(f, "@add", variable, lhs, rhs),
+            Self::Sub(variable, lhs, rhs) => binary(f, "@sub", variable, lhs, rhs),
+            Self::Mul(variable, lhs, rhs) => binary(f, "@mul", variable, lhs, rhs),
+            Self::Cmp(variable, condition, lhs, rhs) => {
+                binary(f, &format!("@cmp {}", condition), variable, lhs, rhs)
+            }
+            Self::Jump(label) => write!(f, "@jump %{}", label.lexeme),
+            Self::JumpIf(condition, label) => {
+                write!(f, "@jumpif {}, %{}", condition, label.lexeme)
+            }
+            Self::Return(ty, value) => write!(f, "@ret {} : {}", ty, value),
+            Self::Call(variable, name, arguments) => {
+                write!(
+                    f,
+                    "@call {} : {} {}(",
+                    variable.ty, variable.name.lexeme, name.lexeme
+                )?;
+                separated(f, arguments, ", ")?;
+                write!(f, ")")
+            }
+            Self::Phi(variable, incoming) => {
+                write!(f, "@phi {} : {}", variable.ty, variable.name.lexeme)?;
+                for (value, label, _) in incoming.iter() {
+                    write!(f, ", [{}, %{}]", value.name.lexeme, label.lexeme)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn binary(
+    f: &mut Formatter<'_>,
+    instruction: &str,
+    variable: &Variable,
+    lhs: &Operand,
+    rhs: &Operand,
+) -> Result {
+    write!(
+        f,
+        "{} {} : {}, {}, {}",
+        instruction, variable.ty, variable.name.lexeme, lhs, rhs
+    )
+}
+
+fn separated(f: &mut Formatter<'_>, items: &[impl Display], separator: &str) -> Result {
+    for (index, item) in items.iter().enumerate() {
+        if index > 0 {
+            write!(f, "{}", separator)?;
+        }
+        write!(f, "{}", item)?;
+    }
+    Ok(())
+}
+
+fn doc_comment(f: &mut Formatter<'_>, doc: &Option<String>) -> Result {
+    for line in doc.iter().flat_map(|doc| doc.lines()) {
+        if line.is_empty() {
+            writeln!(f, "///")?;
+        } else {
+            writeln!(f, "/// {}", line)?;
+        }
+    }
+    Ok(())
+}
+
+/// The block's label followed by its instructions, one per line
+impl Display for BasicBlock {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        if let Some(label) = &self.label {
+            writeln!(f, "%{}:", label.lexeme)?;
+        }
+        for instruction in self.instructions.iter() {
+            writeln!(f, "    {}", instruction)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Function {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        doc_comment(f, &self.doc)?;
+        if let Visibility::Public = self.visibility {
+            write!(f, "public ")?;
+        }
+        write!(f, "function {}(", self.name)?;
+        separated(f, &self.params, ", ")?;
+        writeln!(f, ") {} {{", self.return_type)?;
+        for block in self.blocks.iter() {
+            write!(f, "{}", block)?;
+        }
+        writeln!(f, "}}")
+    }
+}
+
+impl Display for Import {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let Self::Function(FunctionSpec {
+            module_name,
+            name,
+            params,
+            return_type,
+        }) = self;
+        write!(
+            f,
+            "import function {}::{}(",
+            module_name.lexeme, name.lexeme
+        )?;
+        separated(f, params, ", ")?;
+        writeln!(f, ") {}", return_type)
+    }
+}
+
+impl Display for ConstantValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Self::String(value) | Self::Number(value) => write!(f, "{}", value.lexeme),
+            Self::Directive(Directive::Len(name)) => write!(f, ".len {}", name.lexeme),
+            Self::Directive(Directive::SizeOf(ty)) => write!(f, ".sizeof {}", ty),
+            Self::Directive(Directive::AlignOf(ty)) => write!(f, ".alignof {}", ty),
+        }
+    }
+}
+
+impl Display for Constant {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        doc_comment(f, &self.doc)?;
+        writeln!(
+            f,
+            "const {} : {} = {}",
+            self.name.lexeme, self.ty, self.value
+        )
+    }
+}
+
+/// Imports, then constants, then functions, with a blank line between the groups and
+/// between functions
+impl Display for Program {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let mut groups = vec![];
+        let mut group = |items: String| {
+            if !items.is_empty() {
+                groups.push(items);
+            }
+        };
+        group(self.imports.iter().map(ToString::to_string).collect());
+        group(self.constants.iter().map(ToString::to_string).collect());
+        for function in self.functions.iter() {
+            group(function.to_string());
+        }
+        separated(f, &groups, "\n")
+    }
+}
@@ -0,0 +1,340 @@
+use super::Type;
+use crate::error::BitBoxError;
+use crate::lexer::token::{Token, TokenKind};
+
+/// Value of a number literal before it is given the type of the instruction or constant it
+/// is used in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Literal {
+    Integer(i128),
+    Float(f64),
+}
+
+/// A number literal converted to the type it is used as
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Value {
+    Unsigned(u128),
+    Signed(i128),
+    F32(f32),
+    F64(f64),
+}
+
+impl Value {
+    /// The value's bit pattern, two's complement for signed values and IEEE 754 for floats
+    pub fn bits(&self) -> u64 {
+        match self {
+            Self::Unsigned(value) => *value as u64,
+            Self::Signed(value) => *value as i64 as u64,
+            Self::F32(value) => value.to_bits() as u64,
+            Self::F64(value) => value.to_bits(),
+        }
+    }
+}
+
+impl Literal {
+    /// Parses decimal, `0x` hexadecimal, `0b` binary and `0o` octal integers and decimal
+    /// floats with an optional exponent. Any of them may start with `-` and use `_` to
+    /// separate digits.
+    pub fn parse(token: &Token) -> Result<Self, BitBoxError> {
+        let malformed = |reason: String| BitBoxError::MalformedNumber {
+            literal: token.clone(),
+            reason,
+        };
+        let (negative, lexeme) = match token.lexeme.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, token.lexeme.as_str()),
+        };
+        let radix = match lexeme.get(..2) {
+            Some("0x" | "0X") => Some((16, "hexadecimal")),
+            Some("0b" | "0B") => Some((2, "binary")),
+            Some("0o" | "0O") => Some((8, "octal")),
+            _ => None,
+        };
+
+        let Some((radix, name)) = radix else {
+            return parse_decimal(lexeme, negative).map_err(malformed);
+        };
+        let digits = lexeme[2..].replace('_', "");
+        if digits.is_empty() {
+            return Err(malformed(format!("{} literal has no digits", name)));
+        }
+        if let Some(digit) = digits.chars().find(|digit| !digit.is_digit(radix)) {
+            return Err(malformed(format!(
+                "invalid digit '{}' in {} literal",
+                digit, name
+            )));
+        }
+        let magnitude = u128::from_str_radix(&digits, radix)
+            .map_err(|_| malformed("literal is too large".to_string()))?;
+        integer(magnitude, negative).map_err(malformed)
+    }
+
+    /// Converts the literal to `ty`, checking that it fits
+    pub fn typed(&self, token: &Token, ty: &Type) -> Result<Value, BitBoxError> {
+        let out_of_range = || BitBoxError::ConstantOutOfRange {
+            value: token.clone(),
+            ty: ty.to_string(),
+        };
+        match (self, ty) {
+            (Self::Integer(value), Type::Unsigned(bits)) => {
+                let fits = *value >= 0 && (*bits >= 128 || value >> bits == 0);
+                fits.then_some(Value::Unsigned(*value as u128))
+                    .ok_or_else(out_of_range)
+            }
+            (Self::Integer(value), Type::Signed(bits)) => {
+                // Shifting out all but the sign bit leaves 0 or -1 when the number fits
+                let fits = *bits >= 128 || (*bits > 0 && matches!(value >> (bits - 1), 0 | -1));
+                fits.then_some(Value::Signed(*value))
+                    .ok_or_else(out_of_range)
+            }
+            // Pointers are offsets into a 32 bit address space
+            (Self::Integer(value), Type::Pointer(_)) => u32::try_from(*value)
+                .map(|value| Value::Unsigned(value as u128))
+                .map_err(|_| out_of_range()),
+            (Self::Integer(value), Type::Float(_)) => Self::Float(*value as f64).typed(token, ty),
+            (Self::Float(value), Type::Float(32)) => {
+                let value = *value as f32;
+                value
+                    .is_finite()
+                    .then_some(Value::F32(value))
+                    .ok_or_else(out_of_range)
+            }
+            (Self::Float(value), Type::Float(64)) => Ok(Value::F64(*value)),
+            // The parser only accepts `f32` and `f64`
+            (_, Type::Float(_)) => Err(BitBoxError::InvalidType {
+                expected: "f32 or f64".to_string(),
+                actual_type: ty.to_string(),
+                actual: token.clone(),
+                definition: None,
+            }),
+            (Self::Float(_), Type::Unsigned(_) | Type::Signed(_) | Type::Pointer(_)) => {
+                Err(BitBoxError::InvalidType {
+                    expected: ty.to_string(),
+                    actual_type: "a float literal".to_string(),
+                    actual: token.clone(),
+                    definition: None,
+                })
+            }
+            (_, Type::Array(..) | Type::Void) => Err(BitBoxError::InvalidType {
+                expected: "a number type".to_string(),
+                actual_type: ty.to_string(),
+                actual: token.clone(),
+                definition: None,
+            }),
+        }
+    }
+}
+
+/// Parses a number literal and converts it to `ty`
+pub fn typed_value(token: &Token, ty: &Type) -> Result<Value, BitBoxError> {
+    Literal::parse(token)?.typed(token, ty)
+}
+
+/// Contents of a string literal with its escapes resolved. Strings may contain any character,
+/// byte strings (`b#"..."#`) only ASCII characters but any byte through `\xNN`. Raw strings
+/// (`r#"..."#` and `br#"..."#`) have no escapes.
+pub fn string_bytes(token: &Token) -> Result<Vec<u8>, BitBoxError> {
+    // The prefix, `#`s and quote are ASCII so byte and character offsets agree
+    let opening = token
+        .lexeme
+        .find('"')
+        .expect("strings are lexed with an opening quote")
+        + 1;
+    let prefix = &token.lexeme[..opening];
+    let raw = prefix.contains('r');
+    let byte_string = prefix.contains('b');
+    let hashes = prefix.matches('#').count();
+
+    let body = token.lexeme[opening..].chars().collect::<Vec<_>>();
+    // Byte offset in the source of every character in the body and of its end
+    let offsets = token.lexeme[opening..]
+        .char_indices()
+        .map(|(offset, _)| offset)
+        .chain([token.lexeme.len() - opening])
+        .map(|offset| token.span.start + opening + offset)
+        .collect::<Vec<_>>();
+    let invalid = |from: usize, to: usize, reason: String| BitBoxError::InvalidEscape {
+        escape: Token {
+            kind: TokenKind::String,
+            lexeme: body[from..to.min(body.len())].iter().collect(),
+            span: offsets[from]..offsets[to.min(body.len())],
+        },
+        reason,
+    };
+
+    let mut bytes = vec![];
+    let mut index = 0;
+    // Number of `#` seen since the last `"` that wasn't escaped
+    let mut closing = None;
+    while let Some(&value) = body.get(index) {
+        index += 1;
+        closing = match (value, closing) {
+            ('"', _) => Some(0),
+            ('#', Some(count)) => Some(count + 1),
+            _ => None,
+        };
+        if closing == Some(hashes) {
+            // The closing quote and all but the last `#` were pushed as they were read
+            bytes.truncate(bytes.len() - hashes);
+            return Ok(bytes);
+        }
+
+        if raw || value != '\\' {
+            if byte_string && !value.is_ascii() {
+                return Err(invalid(
+                    index - 1,
+                    index,
+                    "byte strings can only contain ASCII, use \\xNN for other bytes".to_string(),
+                ));
+            }
+            let mut buffer = [0; 4];
+            bytes.extend_from_slice(value.encode_utf8(&mut buffer).as_bytes());
+            continue;
+        }
+
+        let escape_start = index - 1;
+        let Some(&escaped) = body.get(index) else {
+            break;
+        };
+        index += 1;
+        match escaped {
+            'n' => bytes.push(b'\n'),
+            't' => bytes.push(b'\t'),
+            'r' => bytes.push(b'\r'),
+            '0' => bytes.push(0),
+            '\\' => bytes.push(b'\\'),
+            '"' => bytes.push(b'"'),
+            'x' => {
+                let digits = body.get(index..index + 2).unwrap_or_default();
+                let value = Some(digits.iter().collect::<String>())
+                    .filter(|digits| digits.len() == 2)
+                    .filter(|digits| digits.chars().all(|digit| digit.is_ascii_hexdigit()))
+                    .and_then(|digits| u8::from_str_radix(&digits, 16).ok())
+                    .ok_or_else(|| {
+                        invalid(
+                            escape_start,
+                            index + 2,
+                            "expected two hexadecimal digits after \\x".to_string(),
+                        )
+                    })?;
+                index += 2;
+                if !byte_string && value > 0x7f {
+                    return Err(invalid(
+                        escape_start,
+                        index,
+                        "\\x escapes above \\x7f are only allowed in byte strings".to_string(),
+                    ));
+                }
+                bytes.push(value);
+            }
+            'u' if !byte_string => {
+                let Some(close) = body[index..].iter().position(|&value| value == '}') else {
+                    return Err(invalid(
+                        escape_start,
+                        index,
+                        "expected \\u{...}".to_string(),
+                    ));
+                };
+                let end = index + close + 1;
+                let digits = body[index..end].iter().collect::<String>();
+                let value = digits
+                    .strip_prefix('{')
+                    .and_then(|digits| digits.strip_suffix('}'))
+                    .filter(|digits| (1..=6).contains(&digits.len()))
+                    .filter(|digits| digits.chars().all(|digit| digit.is_ascii_hexdigit()))
+                    .and_then(|digits| u32::from_str_radix(digits, 16).ok())
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| {
+                        invalid(
+                            escape_start,
+                            end,
+                            "expected 1 to 6 hexadecimal digits of a unicode scalar value"
+                                .to_string(),
+                        )
+                    })?;
+                index = end;
+                let mut buffer = [0; 4];
+                bytes.extend_from_slice(value.encode_utf8(&mut buffer).as_bytes());
+            }
+            'u' => {
+                return Err(invalid(
+                    escape_start,
+                    index,
+                    "byte strings can't contain \\u escapes".to_string(),
+                ))
+            }
+            other => {
+                return Err(invalid(
+                    escape_start,
+                    index,
+                    format!("unknown escape '\\{}'", other),
+                ))
+            }
+        }
+    }
+
+    Err(BitBoxError::UnterminatedString(Token {
+        kind: TokenKind::String,
+        lexeme: prefix.to_string(),
+        span: token.span.start..token.span.start + opening,
+    }))
+}
+
+fn integer(magnitude: u128, negative: bool) -> Result<Literal, String> {
+    let too_large = || "literal is too large".to_string();
+    if negative {
+        // The magnitude of i128::MIN doesn't fit in an i128
+        return 0i128
+            .checked_sub_unsigned(magnitude)
+            .map(Literal::Integer)
+            .ok_or_else(too_large);
+    }
+    i128::try_from(magnitude)
+        .map(Literal::Integer)
+        .map_err(|_| too_large())
+}
+
+/// `digits [. digits] [(e|E) [+|-] digits]`
+fn parse_decimal(lexeme: &str, negative: bool) -> Result<Literal, String> {
+    let lexeme = lexeme.replace('_', "");
+    let (mantissa, exponent) = match lexeme.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+        None => (lexeme.as_str(), None),
+    };
+    let (whole, fraction) = match mantissa.split_once('.') {
+        Some((whole, fraction)) => (whole, Some(fraction)),
+        None => (mantissa, None),
+    };
+
+    let check_digits = |digits: &str, part: &str| -> Result<(), String> {
+        if digits.is_empty() {
+            return Err(format!("expected digits in the {}", part));
+        }
+        match digits.chars().find(|digit| !digit.is_ascii_digit()) {
+            Some(digit) => Err(format!("invalid digit '{}' in the {}", digit, part)),
+            None => Ok(()),
+        }
+    };
+    check_digits(whole, "integer part")?;
+    if let Some(fraction) = fraction {
+        check_digits(fraction, "fraction")?;
+    }
+    if let Some(exponent) = exponent {
+        let digits = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+        check_digits(digits, "exponent")?;
+    }
+
+    if fraction.is_none() && exponent.is_none() {
+        let magnitude = whole
+            .parse::<u128>()
+            .map_err(|_| "literal is too large".to_string())?;
+        return integer(magnitude, negative);
+    }
+    let value = lexeme
+        .parse::<f64>()
+        .map_err(|_| "invalid float literal".to_string())?;
+    if !value.is_finite() {
+        return Err("literal is too large".to_string());
+    }
+    Ok(Literal::Float(if negative { -value } else { value }))
+}
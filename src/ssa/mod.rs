@@ -1,6 +1,20 @@
 #![allow(dead_code)]
+pub mod cfg;
+pub mod const_eval;
+mod display;
+pub mod literal;
+pub mod phi;
+#[cfg(test)]
+mod test;
+pub mod typeck;
+pub mod verify;
+
+use crate::error::BitBoxError;
 use crate::lexer::token::{Token, TokenKind};
 
+/// Size in bytes of a pointer, pointers are offsets into a 32 bit address space.
+pub const POINTER_SIZE: usize = 4;
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Type {
     Unsigned(u8),
@@ -11,6 +25,31 @@ pub enum Type {
     Void,
 }
 
+impl Type {
+    /// Size of the type in bytes
+    pub fn size(&self) -> usize {
+        match self {
+            Self::Unsigned(bits) | Self::Signed(bits) | Self::Float(bits) => {
+                (*bits as usize).div_ceil(8)
+            }
+            Self::Pointer(_) => POINTER_SIZE,
+            Self::Array(count, ty) => count * ty.size(),
+            Self::Void => 0,
+        }
+    }
+
+    /// Alignment of the type in bytes, always a power of two
+    pub fn align(&self) -> usize {
+        match self {
+            Self::Unsigned(_) | Self::Signed(_) | Self::Float(_) => self.size().next_power_of_two(),
+            Self::Pointer(_) => POINTER_SIZE,
+            Self::Array(_, ty) => ty.align(),
+            Self::Void => 1,
+        }
+    }
+}
+
+#[allow(clippy::wrong_self_convention)]
 pub trait IntoSsaType {
     type Error;
     fn into_ssa_type(&self) -> Result<Type, Self::Error>;
@@ -63,9 +102,97 @@ pub enum Instruction {
     Assign(Variable, Operand),
     Add(Variable, Operand, Operand),
     Sub(Variable, Operand, Operand),
+    Mul(Variable, Operand, Operand),
+    /// Sets the variable to 1 if the condition holds and 0 otherwise, the variable's type is the
+    /// type the operands are compared as.
+    Cmp(Variable, Condition, Operand, Operand),
+    /// Jumps to the block with the label
+    Jump(Token),
+    /// Jumps to the block with the label when the operand is not 0, otherwise falls through to
+    /// the next block.
+    JumpIf(Operand, Token),
     Return(Type, Operand),
     Call(Variable, Token, Vec<Operand>),
-    Phi(Variable, Vec<(Variable, usize)>),
+    /// Each incoming value is paired with the label and id of the block it comes from
+    Phi(Variable, Vec<(Variable, Token, usize)>),
+}
+
+impl Instruction {
+    /// Whether the instruction ends a basic block
+    pub fn is_terminator(&self) -> bool {
+        matches!(self, Self::Jump(_) | Self::JumpIf(..) | Self::Return(..))
+    }
+
+    /// The variable the instruction defines
+    pub fn destination(&self) -> Option<&Variable> {
+        match self {
+            Self::Assign(variable, _)
+            | Self::Add(variable, ..)
+            | Self::Sub(variable, ..)
+            | Self::Mul(variable, ..)
+            | Self::Cmp(variable, ..)
+            | Self::Call(variable, ..)
+            | Self::Phi(variable, _) => Some(variable),
+            Self::Jump(_) | Self::JumpIf(..) | Self::Return(..) => None,
+        }
+    }
+
+    /// Operands the instruction reads, a phi's incoming values are not included since they are
+    /// read on the edge from the predecessor rather than in the block.
+    pub fn operands(&self) -> Vec<&Operand> {
+        match self {
+            Self::Assign(_, operand) | Self::JumpIf(operand, _) | Self::Return(_, operand) => {
+                vec![operand]
+            }
+            Self::Add(_, lhs, rhs)
+            | Self::Sub(_, lhs, rhs)
+            | Self::Mul(_, lhs, rhs)
+            | Self::Cmp(_, _, lhs, rhs) => vec![lhs, rhs],
+            Self::Call(_, _, arguments) => arguments.iter().collect(),
+            Self::Jump(_) | Self::Phi(..) => vec![],
+        }
+    }
+
+    /// The token diagnostics about this instruction point at
+    pub fn token(&self) -> &Token {
+        match self {
+            Self::Assign(variable, _)
+            | Self::Add(variable, ..)
+            | Self::Sub(variable, ..)
+            | Self::Mul(variable, ..)
+            | Self::Cmp(variable, ..)
+            | Self::Call(variable, ..)
+            | Self::Phi(variable, _) => &variable.name,
+            Self::Jump(label) | Self::JumpIf(_, label) => label,
+            Self::Return(_, operand) => operand.token(),
+        }
+    }
+}
+
+/// Predicate of a `@cmp`, whether `lt`, `le`, `gt` and `ge` are signed comes from the compared type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Condition {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl TryFrom<&Token> for Condition {
+    type Error = Token;
+    fn try_from(token: &Token) -> Result<Self, Self::Error> {
+        match token.lexeme.as_str() {
+            "eq" => Ok(Self::Eq),
+            "ne" => Ok(Self::Ne),
+            "lt" => Ok(Self::Lt),
+            "le" => Ok(Self::Le),
+            "gt" => Ok(Self::Gt),
+            "ge" => Ok(Self::Ge),
+            _ => Err(token.clone()),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -74,14 +201,34 @@ pub enum Operand {
     Constant(Token),
 }
 
+impl Operand {
+    pub fn token(&self) -> &Token {
+        match self {
+            Self::Variable(token) | Self::Constant(token) => token,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct BasicBlock {
     pub id: usize,
+    /// The `%name:` that starts the block, only the entry block can be unnamed
+    pub label: Option<Token>,
     pub instructions: Vec<Instruction>,
     pub successors: Vec<usize>,
     pub predecessors: Vec<usize>,
 }
 
+/// Finds the block a `%label` refers to
+pub fn find_block<'a>(blocks: &'a [BasicBlock], label: &Token) -> Option<&'a BasicBlock> {
+    blocks.iter().find(|block| {
+        block
+            .label
+            .as_ref()
+            .is_some_and(|name| name.lexeme == label.lexeme)
+    })
+}
+
 #[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
 pub enum Visibility {
     Public,
@@ -91,6 +238,8 @@ pub enum Visibility {
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Function {
+    /// Text of the `///` comments before the function
+    pub doc: Option<String>,
     pub visibility: Visibility,
     pub name: String,
     pub params: Vec<Variable>,
@@ -114,16 +263,21 @@ pub enum Import {
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Directive {
     Len(Token),
+    SizeOf(Type),
+    AlignOf(Type),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum ConstantValue {
     String(Token),
+    Number(Token),
     Directive(Directive),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Constant {
+    /// Text of the `///` comments before the constant
+    pub doc: Option<String>,
     pub name: Token,
     pub ty: Type,
     pub value: ConstantValue,
@@ -135,3 +289,13 @@ pub struct Program {
     pub constants: Vec<Constant>,
     pub functions: Vec<Function>,
 }
+
+/// Builds the CFG, runs every check and evaluates the constants, which a program needs before it
+/// can be run or compiled
+pub fn analyse(program: &mut Program) -> Result<(), BitBoxError> {
+    cfg::build(program)?;
+    phi::validate(program)?;
+    verify::verify(program)?;
+    typeck::check(program)?;
+    const_eval::evaluate(program)
+}
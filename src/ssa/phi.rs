@@ -0,0 +1,97 @@
+use super::{BasicBlock, Function, Instruction, Program, Type};
+use crate::error::BitBoxError;
+use crate::lexer::token::Token;
+
+/// Checks that phis are only at the top of a block, have exactly one incoming value for each
+/// predecessor and that every incoming value has the phi's type.
+/// Needs the CFG from `ssa::cfg::build`.
+pub fn validate(program: &Program) -> Result<(), BitBoxError> {
+    for function in program.functions.iter() {
+        for block in function.blocks.iter() {
+            validate_block(program, function, block)?;
+        }
+    }
+    Ok(())
+}
+
+fn validate_block(
+    program: &Program,
+    function: &Function,
+    block: &BasicBlock,
+) -> Result<(), BitBoxError> {
+    let mut top = true;
+    for instruction in block.instructions.iter() {
+        let Instruction::Phi(variable, incoming) = instruction else {
+            top = false;
+            continue;
+        };
+        if !top {
+            return Err(BitBoxError::MisplacedPhi(variable.name.clone()));
+        }
+
+        let mut seen = vec![];
+        for (value, _, id) in incoming.iter() {
+            if !block.predecessors.contains(id) || seen.contains(id) {
+                return Err(BitBoxError::UnexpectedPhiIncoming(value.name.clone()));
+            }
+            seen.push(*id);
+
+            let Some((ty, definition)) = type_of(program, function, &value.name) else {
+                return Err(BitBoxError::UndefinedVariable(value.name.clone()));
+            };
+            if ty != &variable.ty {
+                return Err(BitBoxError::InvalidType {
+                    expected: variable.ty.to_string(),
+                    actual_type: ty.to_string(),
+                    actual: value.name.clone(),
+                    definition: Some(Box::new(definition.clone())),
+                });
+            }
+        }
+
+        if let Some(missing) = block.predecessors.iter().find(|id| !seen.contains(id)) {
+            let predecessor = function
+                .blocks
+                .iter()
+                .find(|block| block.id == *missing)
+                .and_then(|block| block.label.as_ref())
+                .map(|label| format!("%{}", label.lexeme))
+                .unwrap_or_else(|| "the entry block".to_string());
+            return Err(BitBoxError::MissingPhiIncoming {
+                phi: variable.name.clone(),
+                predecessor,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// The declared type of the parameter, instruction or global constant that defines `name` and
+/// where it's defined
+fn type_of<'a>(
+    program: &'a Program,
+    function: &'a Function,
+    name: &Token,
+) -> Option<(&'a Type, &'a Token)> {
+    let param = function
+        .params
+        .iter()
+        .find(|param| param.name.lexeme == name.lexeme);
+    if let Some(param) = param {
+        return Some((&param.ty, &param.name));
+    }
+    function
+        .blocks
+        .iter()
+        .flat_map(|block| block.instructions.iter())
+        .filter_map(Instruction::destination)
+        .find(|variable| variable.name.lexeme == name.lexeme)
+        .map(|variable| (&variable.ty, &variable.name))
+        .or_else(|| {
+            program
+                .constants
+                .iter()
+                .find(|constant| constant.name.lexeme == name.lexeme)
+                .map(|constant| (&constant.ty, &constant.name))
+        })
+}
@@ -0,0 +1,367 @@
+use crate::lexer::lex;
+use crate::parser;
+use crate::ssa::{cfg, const_eval, literal, phi, typeck, verify, ConstantValue};
+use crate::test::snapshot_sources;
+use std::fmt::Write;
+
+pub fn snapshot_const_eval(input: &str) -> String {
+    let tokens = lex(input);
+    let mut program = parser::Parser::new(tokens)
+        .parse()
+        .expect("failed to parse program");
+    match const_eval::evaluate(&mut program) {
+        Ok(()) => format!("{:#?}", program.constants),
+        Err(err) => format!("{:#?}", err),
+    }
+}
+
+pub fn snapshot_cfg(input: &str) -> String {
+    let tokens = lex(input);
+    let mut program = parser::Parser::new(tokens)
+        .parse()
+        .expect("failed to parse program");
+    if let Err(err) = cfg::build(&mut program) {
+        return format!("{:#?}", err);
+    }
+    let mut output = String::new();
+    for function in program.functions.iter() {
+        writeln!(&mut output, "{}:", function.name).expect("failed to write function");
+        for block in function.blocks.iter() {
+            let label = block
+                .label
+                .as_ref()
+                .map(|label| format!("%{}", label.lexeme))
+                .unwrap_or_default();
+            writeln!(
+                &mut output,
+                "  {} {}: successors {:?}, predecessors {:?}",
+                block.id, label, block.successors, block.predecessors
+            )
+            .expect("failed to write block");
+        }
+    }
+    output
+}
+
+pub fn snapshot_cfg_dot(input: &str) -> String {
+    let tokens = lex(input);
+    let mut program = parser::Parser::new(tokens)
+        .parse()
+        .expect("failed to parse program");
+    match cfg::build(&mut program) {
+        Ok(()) => cfg::dot(&program),
+        Err(err) => format!("{:#?}", err),
+    }
+}
+
+pub fn snapshot_phi(input: &str) -> String {
+    let tokens = lex(input);
+    let mut program = parser::Parser::new(tokens)
+        .parse()
+        .expect("failed to parse program");
+    cfg::build(&mut program).expect("failed to build cfg");
+    match phi::validate(&program) {
+        Ok(()) => "ok".to_string(),
+        Err(err) => format!("{:#?}", err),
+    }
+}
+
+pub fn snapshot_verify(input: &str) -> String {
+    let tokens = lex(input);
+    let mut program = parser::Parser::new(tokens)
+        .parse()
+        .expect("failed to parse program");
+    cfg::build(&mut program).expect("failed to build cfg");
+    let mut output = String::new();
+    for function in program.functions.iter() {
+        let dominators = verify::Dominators::new(&function.blocks);
+        writeln!(&mut output, "{}:", function.name).expect("failed to write function");
+        for block in function.blocks.iter() {
+            writeln!(
+                &mut output,
+                "  {}: idom {:?}",
+                block.id,
+                dominators.immediate_dominator(block.id)
+            )
+            .expect("failed to write block");
+        }
+    }
+    match verify::verify(&program) {
+        Ok(()) => output + "ok",
+        Err(err) => output + &format!("{:#?}", err),
+    }
+}
+
+pub fn snapshot_typeck(input: &str) -> String {
+    let tokens = lex(input);
+    let program = parser::Parser::new(tokens)
+        .parse()
+        .expect("failed to parse program");
+    match typeck::check(&program) {
+        Ok(()) => "ok".to_string(),
+        Err(err) => format!("{:#?}", err),
+    }
+}
+
+/// Value every number constant takes in its declared type and the bytes of every string
+pub fn snapshot_literals(input: &str) -> String {
+    let tokens = lex(input);
+    let program = parser::Parser::new(tokens)
+        .parse()
+        .expect("failed to parse program");
+    let mut output = String::new();
+    for constant in program.constants.iter() {
+        if let ConstantValue::String(value) = &constant.value {
+            let bytes = literal::string_bytes(value).expect("strings are checked by the parser");
+            writeln!(
+                &mut output,
+                "{}: {} -> {} bytes \"{}\"",
+                constant.name.lexeme,
+                value.lexeme,
+                bytes.len(),
+                bytes.escape_ascii()
+            )
+            .expect("failed to write constant");
+            continue;
+        }
+        let ConstantValue::Number(value) = &constant.value else {
+            continue;
+        };
+        let typed = literal::typed_value(value, &constant.ty);
+        let typed = match typed {
+            Ok(typed) => format!("{:?} bits {:#x}", typed, typed.bits()),
+            Err(err) => format!("{:?}", err.code()),
+        };
+        writeln!(
+            &mut output,
+            "{}: {:?} = {} -> {}",
+            constant.name.lexeme, constant.ty, value.lexeme, typed
+        )
+        .expect("failed to write constant");
+    }
+    output
+}
+
+pub fn snapshot_display(input: &str) -> String {
+    let tokens = lex(input);
+    let program = parser::Parser::new(tokens)
+        .parse()
+        .expect("failed to parse program");
+    program.to_string()
+}
+
+macro_rules! snapshot {
+    ($name:tt, $snapshot:ident, $path:tt) => {
+        #[test]
+        fn $name() {
+            let contents = include_str!($path);
+            let mut settings = insta::Settings::clone_current();
+            settings.set_snapshot_path("testdata/output/");
+            settings.bind(|| {
+                insta::assert_snapshot!($snapshot(contents));
+            });
+        }
+    };
+}
+
+snapshot!(
+    directives,
+    snapshot_const_eval,
+    "../../snapshots/directives.bitbox"
+);
+snapshot!(
+    len_undefined,
+    snapshot_const_eval,
+    "../../snapshots/len_undefined.bitbox"
+);
+snapshot!(
+    directive_out_of_range,
+    snapshot_const_eval,
+    "../../snapshots/directive_out_of_range.bitbox"
+);
+snapshot!(cfg_jump, snapshot_cfg, "../../snapshots/jump.bitbox");
+snapshot!(cfg_jumpif, snapshot_cfg, "../../snapshots/jumpif.bitbox");
+snapshot!(cfg_loop, snapshot_cfg, "../../snapshots/loop.bitbox");
+snapshot!(
+    cfg_dot_loop,
+    snapshot_cfg_dot,
+    "../../snapshots/loop.bitbox"
+);
+snapshot!(
+    cfg_missing_terminator,
+    snapshot_cfg,
+    "../../snapshots/missing_terminator.bitbox"
+);
+snapshot!(
+    cfg_unreachable,
+    snapshot_cfg,
+    "../../snapshots/unreachable.bitbox"
+);
+snapshot!(phi_valid, snapshot_phi, "../../snapshots/phi.bitbox");
+snapshot!(
+    phi_misplaced,
+    snapshot_phi,
+    "../../snapshots/phi_misplaced.bitbox"
+);
+snapshot!(
+    phi_missing_incoming,
+    snapshot_phi,
+    "../../snapshots/phi_missing_incoming.bitbox"
+);
+snapshot!(
+    phi_type_mismatch,
+    snapshot_phi,
+    "../../snapshots/phi_type_mismatch.bitbox"
+);
+snapshot!(
+    phi_constant,
+    snapshot_phi,
+    "../../snapshots/phi_constant.bitbox"
+);
+snapshot!(verify_phi, snapshot_verify, "../../snapshots/phi.bitbox");
+snapshot!(verify_loop, snapshot_verify, "../../snapshots/loop.bitbox");
+snapshot!(
+    verify_constants,
+    snapshot_verify,
+    "../../snapshots/string_constant.bitbox"
+);
+snapshot!(
+    verify_redefinition,
+    snapshot_verify,
+    "../../snapshots/redefinition.bitbox"
+);
+snapshot!(
+    verify_use_before_definition,
+    snapshot_verify,
+    "../../snapshots/use_before_definition.bitbox"
+);
+snapshot!(
+    verify_phi_unavailable,
+    snapshot_verify,
+    "../../snapshots/phi_unavailable.bitbox"
+);
+snapshot!(
+    verify_phi_unreachable_predecessor,
+    snapshot_verify,
+    "../../snapshots/phi_unreachable_predecessor.bitbox"
+);
+snapshot!(
+    typeck_import_function,
+    snapshot_typeck,
+    "../../snapshots/import_function.bitbox"
+);
+snapshot!(
+    typeck_calls,
+    snapshot_typeck,
+    "../../snapshots/calls.bitbox"
+);
+snapshot!(
+    typeck_string_constant,
+    snapshot_typeck,
+    "../../snapshots/string_constant.bitbox"
+);
+snapshot!(
+    typeck_type_mismatch,
+    snapshot_typeck,
+    "../../snapshots/type_mismatch.bitbox"
+);
+snapshot!(
+    typeck_argument_count,
+    snapshot_typeck,
+    "../../snapshots/argument_count.bitbox"
+);
+snapshot!(
+    typeck_return_type,
+    snapshot_typeck,
+    "../../snapshots/return_type.bitbox"
+);
+snapshot!(
+    typeck_constant_out_of_range,
+    snapshot_typeck,
+    "../../snapshots/constant_out_of_range.bitbox"
+);
+snapshot!(
+    typeck_numbers,
+    snapshot_typeck,
+    "../../snapshots/numbers.bitbox"
+);
+snapshot!(
+    literals_numbers,
+    snapshot_literals,
+    "../../snapshots/numbers.bitbox"
+);
+snapshot!(
+    literals_out_of_range,
+    snapshot_literals,
+    "../../snapshots/number_out_of_range.bitbox"
+);
+snapshot!(
+    literals_strings,
+    snapshot_literals,
+    "../../snapshots/strings.bitbox"
+);
+snapshot!(
+    const_eval_strings,
+    snapshot_const_eval,
+    "../../snapshots/strings.bitbox"
+);
+snapshot!(display_phi, snapshot_display, "../../snapshots/phi.bitbox");
+snapshot!(
+    display_calls,
+    snapshot_display,
+    "../../snapshots/calls.bitbox"
+);
+snapshot!(
+    display_comments,
+    snapshot_display,
+    "../../snapshots/comments.bitbox"
+);
+snapshot!(
+    display_directives,
+    snapshot_display,
+    "../../snapshots/directives.bitbox"
+);
+snapshot!(
+    display_strings,
+    snapshot_display,
+    "../../snapshots/strings.bitbox"
+);
+
+/// Printing a program and parsing the output gives back a program that prints the same
+#[test]
+fn display_round_trips() {
+    for (name, source) in snapshot_sources() {
+        let Ok(program) = parser::Parser::new(lex(&source)).parse() else {
+            continue;
+        };
+        let printed = program.to_string();
+        let reparsed = parser::Parser::new(lex(&printed))
+            .parse()
+            .unwrap_or_else(|err| {
+                panic!("failed to parse printed {} {:?}\n{}", name, err, printed)
+            });
+        assert_eq!(reparsed.to_string(), printed, "{}", name);
+        assert_eq!(reparsed.constants.len(), program.constants.len());
+        assert_eq!(reparsed.functions.len(), program.functions.len());
+    }
+}
+
+/// The parser rejects strings with invalid escapes, a program built without it reports the escape
+/// rather than a type error when its length is taken
+#[test]
+fn len_reports_invalid_escape() {
+    let source = "const escaped : *[2; u8] = #\"ok\"#\nconst escaped_len : u32 = .len escaped\n";
+    let mut program = parser::Parser::new(lex(source))
+        .parse()
+        .expect("failed to parse program");
+    let ConstantValue::String(token) = &mut program.constants[0].value else {
+        panic!("expected a string constant");
+    };
+    token.lexeme = "#\"\\q\"#".to_string();
+    let err = const_eval::evaluate(&mut program).expect_err("expected an escape error");
+    assert!(
+        matches!(err, crate::error::BitBoxError::InvalidEscape { .. }),
+        "{:?}",
+        err
+    );
+}
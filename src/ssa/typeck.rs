@@ -0,0 +1,201 @@
+use super::literal;
+use super::{
+    ConstantValue, Function, FunctionSpec, Import, Instruction, Operand, Program, Type, Variable,
+};
+use crate::error::BitBoxError;
+use crate::lexer::token::Token;
+
+/// Checks that every operand has the type its instruction declares, that calls match the
+/// callee's signature, that returns match the function's return type and that integer
+/// constants fit their type.
+pub fn check(program: &Program) -> Result<(), BitBoxError> {
+    for constant in program.constants.iter() {
+        if let ConstantValue::Number(value) = &constant.value {
+            check_number(value, &constant.ty)?;
+        }
+    }
+    for function in program.functions.iter() {
+        Checker { program, function }.check()?;
+    }
+    Ok(())
+}
+
+struct Checker<'a> {
+    program: &'a Program,
+    function: &'a Function,
+}
+
+impl Checker<'_> {
+    fn check(&self) -> Result<(), BitBoxError> {
+        for block in self.function.blocks.iter() {
+            for instruction in block.instructions.iter() {
+                self.check_instruction(instruction)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn check_instruction(&self, instruction: &Instruction) -> Result<(), BitBoxError> {
+        match instruction {
+            Instruction::Assign(variable, operand) => self.check_operand(operand, &variable.ty),
+            Instruction::Add(variable, lhs, rhs)
+            | Instruction::Sub(variable, lhs, rhs)
+            | Instruction::Mul(variable, lhs, rhs)
+            | Instruction::Cmp(variable, _, lhs, rhs) => {
+                self.check_operand(lhs, &variable.ty)?;
+                self.check_operand(rhs, &variable.ty)
+            }
+            Instruction::JumpIf(operand, _) => match self.type_of(operand)? {
+                None | Some((Type::Unsigned(_) | Type::Signed(_), _)) => Ok(()),
+                Some((ty, definition)) => Err(BitBoxError::InvalidType {
+                    expected: "an integer".to_string(),
+                    actual_type: ty.to_string(),
+                    actual: operand.token().clone(),
+                    definition: Some(Box::new(definition.clone())),
+                }),
+            },
+            Instruction::Return(ty, operand) => {
+                if ty != &self.function.return_type {
+                    return Err(BitBoxError::InvalidType {
+                        expected: self.function.return_type.to_string(),
+                        actual_type: ty.to_string(),
+                        actual: operand.token().clone(),
+                        definition: None,
+                    });
+                }
+                self.check_operand(operand, ty)
+            }
+            Instruction::Call(variable, name, arguments) => {
+                self.check_call(variable, name, arguments)
+            }
+            // Incoming types are checked by ssa::phi::validate
+            Instruction::Phi(..) | Instruction::Jump(_) => Ok(()),
+        }
+    }
+
+    fn check_call(
+        &self,
+        variable: &Variable,
+        name: &Token,
+        arguments: &[Operand],
+    ) -> Result<(), BitBoxError> {
+        let (params, return_type) = self.signature_of(name)?;
+        if params.len() != arguments.len() {
+            return Err(BitBoxError::ArgumentCountMismatch {
+                call: name.clone(),
+                expected: params.len(),
+                actual: arguments.len(),
+            });
+        }
+        for (argument, param) in arguments.iter().zip(params.iter()) {
+            self.check_operand(argument, param)?;
+        }
+        if !is_assignable(&variable.ty, return_type) {
+            return Err(BitBoxError::InvalidType {
+                expected: return_type.to_string(),
+                actual_type: variable.ty.to_string(),
+                actual: variable.name.clone(),
+                definition: None,
+            });
+        }
+        Ok(())
+    }
+
+    /// Parameter and return types of a defined or imported function
+    fn signature_of(&self, name: &Token) -> Result<(Vec<Type>, &Type), BitBoxError> {
+        let function = self
+            .program
+            .functions
+            .iter()
+            .find(|function| function.name == name.lexeme);
+        if let Some(function) = function {
+            let params = function.params.iter().map(|param| param.ty.clone());
+            return Ok((params.collect(), &function.return_type));
+        }
+        self.program
+            .imports
+            .iter()
+            .find_map(|import| match import {
+                Import::Function(FunctionSpec {
+                    name: import_name,
+                    params,
+                    return_type,
+                    ..
+                }) if import_name.lexeme == name.lexeme => Some((params.clone(), return_type)),
+                Import::Function(_) => None,
+            })
+            .ok_or_else(|| BitBoxError::UndefinedFunction(name.clone()))
+    }
+
+    fn check_operand(&self, operand: &Operand, expected: &Type) -> Result<(), BitBoxError> {
+        if let Operand::Constant(value) = operand {
+            return check_number(value, expected);
+        }
+        let Some((actual, definition)) = self.type_of(operand)? else {
+            return Ok(());
+        };
+        if !is_assignable(expected, actual) {
+            return Err(BitBoxError::InvalidType {
+                expected: expected.to_string(),
+                actual_type: actual.to_string(),
+                actual: operand.token().clone(),
+                definition: Some(Box::new(definition.clone())),
+            });
+        }
+        Ok(())
+    }
+
+    /// Type of a named operand and where it is defined, `None` for number constants which take
+    /// the type they are used as
+    fn type_of(&self, operand: &Operand) -> Result<Option<(&Type, &Token)>, BitBoxError> {
+        let Operand::Variable(name) = operand else {
+            return Ok(None);
+        };
+        let param = self
+            .function
+            .params
+            .iter()
+            .find(|param| param.name.lexeme == name.lexeme);
+        let definition = || {
+            self.function
+                .blocks
+                .iter()
+                .flat_map(|block| block.instructions.iter())
+                .filter_map(Instruction::destination)
+                .find(|variable| variable.name.lexeme == name.lexeme)
+        };
+        let constant = || {
+            self.program
+                .constants
+                .iter()
+                .find(|constant| constant.name.lexeme == name.lexeme)
+        };
+        if let Some(variable) = param.or_else(definition) {
+            return Ok(Some((&variable.ty, &variable.name)));
+        }
+        if let Some(constant) = constant() {
+            return Ok(Some((&constant.ty, &constant.name)));
+        }
+        Err(BitBoxError::UndefinedVariable(name.clone()))
+    }
+}
+
+/// Whether a value of type `from` can be used where `to` is expected.
+/// A pointer to an array can be used as a pointer to its elements.
+fn is_assignable(to: &Type, from: &Type) -> bool {
+    if to == from {
+        return true;
+    }
+    match (to, from) {
+        (Type::Pointer(to), Type::Pointer(from)) => match from.as_ref() {
+            Type::Array(_, element) => element == to,
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// Checks that a number literal is valid for and fits in `ty`
+fn check_number(value: &Token, ty: &Type) -> Result<(), BitBoxError> {
+    literal::typed_value(value, ty).map(|_| ())
+}
@@ -0,0 +1,240 @@
+use super::{BasicBlock, Function, Instruction, Operand, Program};
+use crate::error::BitBoxError;
+use crate::lexer::token::Token;
+use std::collections::HashMap;
+
+/// Checks that every function is in SSA form: each name is defined once and every use is
+/// dominated by its definition. Needs the CFG from `ssa::cfg::build`.
+pub fn verify(program: &Program) -> Result<(), BitBoxError> {
+    for function in program.functions.iter() {
+        verify_function(program, function)?;
+    }
+    Ok(())
+}
+
+/// Where a name is defined, parameters are defined before the entry block's first instruction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Definition {
+    Param,
+    Instruction { block: usize, index: usize },
+}
+
+fn verify_function(program: &Program, function: &Function) -> Result<(), BitBoxError> {
+    let definitions = collect_definitions(function)?;
+    let dominators = Dominators::new(&function.blocks);
+
+    let lookup = |name: &Token| -> Result<Option<(&Token, Definition)>, BitBoxError> {
+        if let Some(definition) = definitions.get(name.lexeme.as_str()) {
+            return Ok(Some(*definition));
+        }
+        // Constants are global so they are available everywhere
+        if program
+            .constants
+            .iter()
+            .any(|constant| constant.name.lexeme == name.lexeme)
+        {
+            return Ok(None);
+        }
+        Err(BitBoxError::UndefinedVariable(name.clone()))
+    };
+
+    for (block, basic_block) in function.blocks.iter().enumerate() {
+        // Nothing in an unreachable block runs so nothing needs to be available
+        if !dominators.is_reachable(block) {
+            continue;
+        }
+        for (index, instruction) in basic_block.instructions.iter().enumerate() {
+            for operand in instruction.operands() {
+                let Operand::Variable(name) = operand else {
+                    continue;
+                };
+                let Some((defined, definition)) = lookup(name)? else {
+                    continue;
+                };
+                let available = match definition {
+                    Definition::Param => true,
+                    Definition::Instruction {
+                        block: def_block,
+                        index: def_index,
+                    } if def_block == block => def_index < index,
+                    Definition::Instruction {
+                        block: def_block, ..
+                    } => dominators.dominates(def_block, block),
+                };
+                if !available {
+                    return Err(BitBoxError::UseBeforeDefinition {
+                        usage: name.clone(),
+                        definition: defined.clone(),
+                    });
+                }
+            }
+
+            let Instruction::Phi(_, incoming) = instruction else {
+                continue;
+            };
+            for (value, _, predecessor) in incoming.iter() {
+                // An unreachable predecessor never jumps here, so its value is never read
+                if !dominators.is_reachable(*predecessor) {
+                    continue;
+                }
+                let Some((defined, definition)) = lookup(&value.name)? else {
+                    continue;
+                };
+                // The value is read at the end of the predecessor
+                let available = match definition {
+                    Definition::Param => true,
+                    Definition::Instruction {
+                        block: def_block, ..
+                    } => dominators.dominates(def_block, *predecessor),
+                };
+                if !available {
+                    return Err(BitBoxError::PhiValueUnavailable {
+                        value: value.name.clone(),
+                        definition: defined.clone(),
+                    });
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn collect_definitions(
+    function: &Function,
+) -> Result<HashMap<&str, (&Token, Definition)>, BitBoxError> {
+    let mut definitions = HashMap::new();
+    let params = function
+        .params
+        .iter()
+        .map(|param| (&param.name, Definition::Param));
+    let instructions =
+        function
+            .blocks
+            .iter()
+            .enumerate()
+            .flat_map(|(block, basic_block)| {
+                basic_block.instructions.iter().enumerate().filter_map(
+                    move |(index, instruction)| {
+                        let variable = instruction.destination()?;
+                        Some((&variable.name, Definition::Instruction { block, index }))
+                    },
+                )
+            });
+
+    for (name, definition) in params.chain(instructions) {
+        if let Some((previous, _)) = definitions.insert(name.lexeme.as_str(), (name, definition)) {
+            return Err(BitBoxError::Redefinition {
+                name: name.clone(),
+                previous: previous.clone(),
+            });
+        }
+    }
+    Ok(definitions)
+}
+
+/// Immediate dominators of a function's blocks, blocks are indexed by their position which is
+/// also their id. Built with "A Simple, Fast Dominance Algorithm" by Cooper, Harvey and Kennedy.
+#[derive(Debug)]
+pub struct Dominators {
+    idom: Vec<Option<usize>>,
+}
+
+impl Dominators {
+    pub fn new(blocks: &[BasicBlock]) -> Self {
+        let mut idom = vec![None; blocks.len()];
+        if blocks.is_empty() {
+            return Self { idom };
+        }
+
+        let order = reverse_postorder(blocks);
+        let mut rank = vec![usize::MAX; blocks.len()];
+        for (position, block) in order.iter().enumerate() {
+            rank[*block] = position;
+        }
+
+        idom[0] = Some(0);
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &block in order.iter().skip(1) {
+                let mut new_idom = None;
+                for &predecessor in blocks[block].predecessors.iter() {
+                    if idom[predecessor].is_none() {
+                        continue;
+                    }
+                    new_idom = Some(match new_idom {
+                        None => predecessor,
+                        Some(current) => intersect(&idom, &rank, predecessor, current),
+                    });
+                }
+                if new_idom.is_some() && idom[block] != new_idom {
+                    idom[block] = new_idom;
+                    changed = true;
+                }
+            }
+        }
+        Self { idom }
+    }
+
+    pub fn is_reachable(&self, block: usize) -> bool {
+        self.idom[block].is_some()
+    }
+
+    /// The block that immediately dominates `block`, the entry block has none
+    pub fn immediate_dominator(&self, block: usize) -> Option<usize> {
+        self.idom[block].filter(|idom| *idom != block)
+    }
+
+    /// Whether every path from the entry to `block` goes through `dominator`
+    pub fn dominates(&self, dominator: usize, block: usize) -> bool {
+        if !self.is_reachable(block) {
+            return false;
+        }
+        let mut current = block;
+        loop {
+            if current == dominator {
+                return true;
+            }
+            match self.immediate_dominator(current) {
+                Some(idom) => current = idom,
+                None => return false,
+            }
+        }
+    }
+}
+
+fn intersect(idom: &[Option<usize>], rank: &[usize], mut lhs: usize, mut rhs: usize) -> usize {
+    while lhs != rhs {
+        while rank[lhs] > rank[rhs] {
+            lhs = idom[lhs].expect("processed blocks have a dominator");
+        }
+        while rank[rhs] > rank[lhs] {
+            rhs = idom[rhs].expect("processed blocks have a dominator");
+        }
+    }
+    lhs
+}
+
+/// Blocks reachable from the entry block ordered so a block comes before its successors,
+/// ignoring back edges
+fn reverse_postorder(blocks: &[BasicBlock]) -> Vec<usize> {
+    let mut visited = vec![false; blocks.len()];
+    let mut order = vec![];
+    // (block, next successor to visit)
+    let mut stack = vec![(0, 0)];
+    visited[0] = true;
+    while let Some((block, next)) = stack.last_mut() {
+        if let Some(&successor) = blocks[*block].successors.get(*next) {
+            *next += 1;
+            if !visited[successor] {
+                visited[successor] = true;
+                stack.push((successor, 0));
+            }
+            continue;
+        }
+        order.push(*block);
+        stack.pop();
+    }
+    order.reverse();
+    order
+}
@@ -0,0 +1,100 @@
+pub mod vm;
+pub mod wasm;
+pub mod x86_64;
+
+use crate::error::BitBoxError;
+use crate::ssa;
+use std::fmt::Write;
+
+/// A backend a program can be compiled for
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    #[default]
+    Wasm,
+    /// Bytecode for the register machine in `vm`
+    Vm,
+    /// A static executable for x86-64 Linux
+    X86_64,
+}
+
+/// What `Target::compile` produces
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Emit {
+    /// The binary that is written to disk
+    #[default]
+    Bin,
+    /// A readable form of the binary, an assembly listing for the vm and a hex dump for the
+    /// others
+    Text,
+    /// GNU assembler input, only x86-64 has it and `cli::parse` rejects it for the others
+    Asm,
+}
+
+impl Target {
+    pub const NAMES: &'static [&'static str] = &["wasm", "vm", "x86_64"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "wasm" => Some(Self::Wasm),
+            "vm" => Some(Self::Vm),
+            "x86_64" => Some(Self::X86_64),
+            _ => None,
+        }
+    }
+
+    /// Extension of the files `Emit::Bin` output is written to, executables have none. `Emit::Asm`
+    /// output is written to `.s` files.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Wasm => "wasm",
+            Self::Vm => "bbvm",
+            Self::X86_64 => "",
+        }
+    }
+
+    /// Compiles a program that passed every check in `ssa`
+    pub fn compile(&self, program: ssa::Program, emit: Emit) -> Result<Vec<u8>, BitBoxError> {
+        match self {
+            Self::Wasm => {
+                let bytes = wasm::Emitter::new(program)
+                    .emit()?
+                    .to_bytes()
+                    .expect("failed to encode module");
+                match emit {
+                    Emit::Bin => Ok(bytes),
+                    Emit::Text => Ok(hex_dump(&bytes).into_bytes()),
+                    Emit::Asm => unreachable!("there is no assembly for wasm"),
+                }
+            }
+            Self::Vm => {
+                let bytecode = vm::Emitter::new(program).emit()?;
+                match emit {
+                    Emit::Bin => Ok(bytecode.to_bytes()),
+                    Emit::Text => Ok(bytecode.to_string().into_bytes()),
+                    Emit::Asm => unreachable!("the vm's assembly is its text listing"),
+                }
+            }
+            Self::X86_64 => {
+                let executable = x86_64::Emitter::new(program).emit()?;
+                match emit {
+                    Emit::Bin => Ok(executable.to_bytes()),
+                    Emit::Text => Ok(hex_dump(&executable.to_bytes()).into_bytes()),
+                    Emit::Asm => Ok(executable.to_string().into_bytes()),
+                }
+            }
+        }
+    }
+}
+
+/// 16 bytes per line, each line starting with the offset of its first byte
+pub fn hex_dump(bytes: &[u8]) -> String {
+    let mut output = String::new();
+    for (i, chunk) in bytes.chunks(16).enumerate() {
+        write!(&mut output, "{:04x}:", i * 16).expect("failed to write()");
+        for byte in chunk {
+            write!(&mut output, " {:02x}", byte).expect("failed to write()");
+        }
+        output += "\n";
+    }
+    output
+}
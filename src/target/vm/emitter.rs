@@ -0,0 +1,702 @@
+use super::instruction::{Data, Imm, Instruction, Label, Opcode, Register, SymbolTable, Type};
+use super::regalloc::{Allocation, Location, REGISTERS, SCRATCH};
+use super::Header;
+use crate::error::BitBoxError;
+use crate::lexer::token::Token;
+use crate::ssa::{self, literal, BasicBlock, ConstantValue, Function, Import, Operand};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+/// Name of the target in errors
+const TARGET: &str = "vm";
+/// Functions called with `Vm::call` return to the `hult` at this label
+const HALT: &str = ".halt";
+/// The header's entry point, calls `main` if there is one and stops
+const ENTRY: &str = ".entry";
+
+/// Syscall numbers `core` imports are lowered to
+const WRITE: u64 = 0;
+const EXIT: u64 = 1;
+
+/// Lowers a checked program to the bytecode of `old/src/instructions`.
+///
+/// Values are given registers by `regalloc`, and the ones that don't fit are spilled to a
+/// frame of slots the function pushes on entry and reads with `peek` and `poke`. A slot is
+/// usually below the top of the stack, which `pop` can't reach without a free register to pop
+/// the values above it into. Arguments are passed in `R0` onwards and results are returned in
+/// `R0`. Every register may be overwritten by the functions a function calls, so the caller
+/// pushes the ones live across a call before it and pops them after. Phis are lowered to
+/// parallel copies on the edges into their block.
+#[derive(Debug)]
+pub struct Emitter {
+    program: ssa::Program,
+    /// Registers the allocator may use, fewer than `REGISTERS` forces values to be spilled
+    registers: usize,
+}
+
+/// One line of the text section
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Item {
+    Label(String),
+    Instruction(Instruction),
+}
+
+/// An emitted program, encoded with `to_bytes` or printed in the old assembler's syntax
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bytecode {
+    /// Copied to the start of the heap when the program is loaded
+    pub data: Vec<u8>,
+    pub text: Vec<Item>,
+}
+
+impl Bytecode {
+    /// Address of every label, text starts after the header and data section
+    pub fn symbol_table(&self) -> SymbolTable {
+        let mut symbol_table = SymbolTable::new();
+        let mut ip = (Header::SIZE + self.data.len()) as u32;
+        for item in self.text.iter() {
+            match item {
+                Item::Label(name) => {
+                    symbol_table.insert(name.clone(), ip);
+                }
+                Item::Instruction(instruction) => ip += instruction.size(),
+            }
+        }
+        symbol_table
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let symbol_table = self.symbol_table();
+        let mut header = Header::default();
+        header.set_header_text_section(self.data.len() as u32);
+        header.set_header_entry_point(symbol_table[ENTRY]);
+        let mut bytes = header.build().to_vec();
+        bytes.extend_from_slice(&self.data);
+        for item in self.text.iter() {
+            if let Item::Instruction(instruction) = item {
+                bytes.extend(instruction.to_bytes(&symbol_table));
+            }
+        }
+        bytes
+    }
+}
+
+impl Display for Bytecode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, ".data {}", self.data.len())?;
+        for line in crate::target::hex_dump(&self.data).lines() {
+            writeln!(f, "    {}", line)?;
+        }
+        writeln!(f, ".text")?;
+        for item in self.text.iter() {
+            match item {
+                Item::Label(name) => writeln!(f, "{}:", name)?,
+                Item::Instruction(instruction) => writeln!(f, "    {}", instruction)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Emitter {
+    pub fn new(program: ssa::Program) -> Self {
+        Self {
+            program,
+            registers: REGISTERS,
+        }
+    }
+
+    #[cfg(test)]
+    pub fn with_registers(mut self, registers: usize) -> Self {
+        self.registers = registers.min(REGISTERS);
+        self
+    }
+
+    pub fn emit(self) -> Result<Bytecode, BitBoxError> {
+        let mut data = vec![];
+        let mut constants = HashMap::new();
+        for constant in self.program.constants.iter() {
+            let value = match &constant.value {
+                ConstantValue::String(token) => {
+                    let address = data.len() as u64;
+                    data.extend(
+                        literal::string_bytes(token).expect("strings are checked by the parser"),
+                    );
+                    address
+                }
+                ConstantValue::Number(token) => literal::typed_value(token, &constant.ty)
+                    .expect("constants are checked by ssa::typeck")
+                    .bits(),
+                ConstantValue::Directive(_) => {
+                    unreachable!("directives are evaluated by ssa::const_eval before emitting")
+                }
+            };
+            let r#type = vm_type(&constant.ty, &constant.name)?;
+            constants.insert(constant.name.lexeme.as_str(), (value, r#type));
+        }
+
+        let mut text = vec![
+            Item::Label(HALT.to_string()),
+            Item::Instruction(Instruction::new(Opcode::Hult, Type::Void, Data::NoArgs)),
+            Item::Label(ENTRY.to_string()),
+        ];
+        if self
+            .program
+            .functions
+            .iter()
+            .any(|function| function.name == "main")
+        {
+            text.push(Item::Instruction(Instruction::new(
+                Opcode::Call,
+                Type::Void,
+                Data::Label(Label::Name("main".to_string())),
+            )));
+        }
+        text.push(Item::Instruction(Instruction::new(
+            Opcode::Hult,
+            Type::Void,
+            Data::NoArgs,
+        )));
+
+        for function in self.program.functions.iter() {
+            let lowering =
+                FunctionLowering::new(&self.program, &constants, function, self.registers)?;
+            text.extend(lowering.lower()?);
+        }
+        Ok(Bytecode { data, text })
+    }
+}
+
+/// The vm type of values of `ty`, pointers are `u32` addresses into the heap
+fn vm_type(ty: &ssa::Type, token: &Token) -> Result<Type, BitBoxError> {
+    match ty {
+        ssa::Type::Unsigned(bits) if *bits <= 64 => Ok(Type::U(*bits)),
+        ssa::Type::Signed(bits) if *bits <= 64 => Ok(Type::I(*bits)),
+        ssa::Type::Pointer(_) => Ok(Type::U(32)),
+        ssa::Type::Void => Ok(Type::Void),
+        ssa::Type::Unsigned(_)
+        | ssa::Type::Signed(_)
+        | ssa::Type::Float(_)
+        | ssa::Type::Array(..) => Err(unsupported(token, format!("type {}", ty))),
+    }
+}
+
+fn unsupported(token: &Token, feature: String) -> BitBoxError {
+    BitBoxError::UnsupportedByTarget {
+        token: token.clone(),
+        target: TARGET,
+        feature,
+    }
+}
+/// Label of a block, the entry block is labelled with the function's name
+fn block_label(function: &Function, block: &BasicBlock) -> String {
+    match &block.label {
+        Some(label) => format!("{}%{}", function.name, label.lexeme),
+        None => function.name.clone(),
+    }
+}
+
+/// What a copy reads: a value where it was allocated or a constant
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Source {
+    Location(Location),
+    Constant(u64, Type),
+}
+
+struct FunctionLowering<'a> {
+    program: &'a ssa::Program,
+    constants: &'a HashMap<&'a str, (u64, Type)>,
+    function: &'a Function,
+    /// Where every parameter and every value the function defines lives
+    allocation: Allocation<'a>,
+    /// Values pushed on top of the function's frame of spill slots
+    depth: usize,
+    text: Vec<Item>,
+    /// Copies for phis on edges that can't be put in the predecessor, emitted after the
+    /// function's blocks
+    stubs: Vec<Item>,
+}
+
+impl<'a> FunctionLowering<'a> {
+    fn new(
+        program: &'a ssa::Program,
+        constants: &'a HashMap<&'a str, (u64, Type)>,
+        function: &'a Function,
+        registers: usize,
+    ) -> Result<Self, BitBoxError> {
+        let destinations = function
+            .blocks
+            .iter()
+            .flat_map(|block| block.instructions.iter())
+            .filter_map(|instruction| instruction.destination());
+        for variable in function.params.iter().chain(destinations) {
+            vm_type(&variable.ty, &variable.name)?;
+        }
+        if let Some(param) = function.params.get(REGISTERS) {
+            return Err(unsupported(
+                &param.name,
+                format!("a function with more than {} parameters", REGISTERS),
+            ));
+        }
+        Ok(Self {
+            program,
+            constants,
+            function,
+            allocation: Allocation::new(function, registers),
+            depth: 0,
+            text: vec![],
+            stubs: vec![],
+        })
+    }
+
+    fn lower(mut self) -> Result<Vec<Item>, BitBoxError> {
+        self.text.push(Item::Label(self.function.name.clone()));
+        // The frame's slots are pushed before the parameters are moved to where they live
+        for _ in 0..self.allocation.slots() {
+            self.emit(Opcode::Push, Type::Void, Data::Reg1(SCRATCH[0]));
+        }
+        let params = self
+            .function
+            .params
+            .iter()
+            .enumerate()
+            .map(|(index, param)| {
+                let register = Register::try_from(index as u8).expect("checked in new");
+                (
+                    self.location(&param.name),
+                    Source::Location(Location::Register(register)),
+                )
+            })
+            .collect();
+        self.parallel_copy(params);
+
+        for (position, block) in self.function.blocks.iter().enumerate() {
+            if block.label.is_some() {
+                self.text
+                    .push(Item::Label(block_label(self.function, block)));
+            }
+            for (index, instruction) in block.instructions.iter().enumerate() {
+                let at = self.allocation.position(position, index);
+                self.instruction(block, position, at, instruction)?;
+            }
+        }
+        let mut text = self.text;
+        text.extend(self.stubs);
+        Ok(text)
+    }
+
+    fn emit(&mut self, opcode: Opcode, r#type: Type, data: Data) {
+        self.text
+            .push(Item::Instruction(Instruction::new(opcode, r#type, data)));
+    }
+
+    fn instruction(
+        &mut self,
+        block: &BasicBlock,
+        position: usize,
+        at: usize,
+        instruction: &ssa::Instruction,
+    ) -> Result<(), BitBoxError> {
+        match instruction {
+            // Phis are lowered to copies on the edges into their block
+            ssa::Instruction::Phi(..) => {}
+            ssa::Instruction::Assign(variable, value) => {
+                let src = self.read(value, &variable.ty, SCRATCH[0])?;
+                match self.location(&variable.name) {
+                    Location::Register(des) if des == src => {}
+                    Location::Register(des) => {
+                        self.emit(Opcode::Copy, Type::Void, Data::Reg2(des, src))
+                    }
+                    Location::Slot(slot) => self.poke(src, slot),
+                }
+            }
+            ssa::Instruction::Add(variable, lhs, rhs) => {
+                self.binary(Opcode::Add, variable, lhs, rhs)?
+            }
+            ssa::Instruction::Sub(variable, lhs, rhs) => {
+                self.binary(Opcode::Sub, variable, lhs, rhs)?
+            }
+            ssa::Instruction::Mul(variable, lhs, rhs) => {
+                self.binary(Opcode::Mul, variable, lhs, rhs)?
+            }
+            // `gt` and `ge` are `lt` and `le` with the operands swapped
+            ssa::Instruction::Cmp(variable, condition, lhs, rhs) => match condition {
+                ssa::Condition::Eq => self.binary(Opcode::Eq, variable, lhs, rhs)?,
+                ssa::Condition::Ne => self.binary(Opcode::Ne, variable, lhs, rhs)?,
+                ssa::Condition::Lt => self.binary(Opcode::Lt, variable, lhs, rhs)?,
+                ssa::Condition::Le => self.binary(Opcode::Le, variable, lhs, rhs)?,
+                ssa::Condition::Gt => self.binary(Opcode::Lt, variable, rhs, lhs)?,
+                ssa::Condition::Ge => self.binary(Opcode::Le, variable, rhs, lhs)?,
+            },
+            ssa::Instruction::Jump(label) => self.jump(block, label),
+            ssa::Instruction::JumpIf(condition, label) => {
+                match condition {
+                    Operand::Constant(token) => {
+                        let taken = literal::Literal::parse(token)
+                            .map(|literal| literal != literal::Literal::Integer(0))
+                            .expect("constants are checked by the parser");
+                        if taken {
+                            self.jump(block, label);
+                            return Ok(());
+                        }
+                    }
+                    Operand::Variable(name) => {
+                        let r#type = self.type_of(name);
+                        let condition = self.read(condition, &r#type, SCRATCH[0])?;
+                        let target = self.branch_target(block, label);
+                        self.load(SCRATCH[1], 0, Type::U(8));
+                        self.emit(
+                            Opcode::Jne,
+                            Type::Void,
+                            Data::Reg2Label(condition, SCRATCH[1], Label::Name(target)),
+                        );
+                    }
+                }
+                // Falls through to the next block
+                if let Some(next) = self.function.blocks.get(position + 1) {
+                    self.copies(block, next);
+                }
+            }
+            ssa::Instruction::Return(ssa::Type::Void, _) => {
+                // `main` returning void exits with 0
+                self.load(Register::R0, 0, Type::U(8));
+                self.ret();
+            }
+            ssa::Instruction::Return(ty, value) => {
+                let src = self.read(value, ty, Register::R0)?;
+                if src != Register::R0 {
+                    self.emit(Opcode::Copy, Type::Void, Data::Reg2(Register::R0, src));
+                }
+                self.ret();
+            }
+            ssa::Instruction::Call(variable, name, arguments) => {
+                self.call(at, variable, name, arguments)?
+            }
+        }
+        Ok(())
+    }
+
+    /// Pops the frame and returns the value in `R0`
+    fn ret(&mut self) {
+        for _ in 0..self.allocation.slots() {
+            self.emit(Opcode::Pop, Type::Void, Data::Reg1(SCRATCH[1]));
+        }
+        self.emit(Opcode::Return, Type::Void, Data::NoArgs);
+    }
+
+    /// `des = lhs <opcode> rhs` for a value of the operands' type
+    fn binary(
+        &mut self,
+        opcode: Opcode,
+        variable: &ssa::Variable,
+        lhs: &Operand,
+        rhs: &Operand,
+    ) -> Result<(), BitBoxError> {
+        let r#type = vm_type(&variable.ty, &variable.name)?;
+        let lhs = self.read(lhs, &variable.ty, SCRATCH[0])?;
+        let rhs = self.read(rhs, &variable.ty, SCRATCH[1])?;
+        match self.location(&variable.name) {
+            Location::Register(des) => self.emit(opcode, r#type, Data::Reg3(des, lhs, rhs)),
+            Location::Slot(slot) => {
+                self.emit(opcode, r#type, Data::Reg3(SCRATCH[0], lhs, rhs));
+                self.poke(SCRATCH[0], slot);
+            }
+        }
+        Ok(())
+    }
+
+    fn location(&self, name: &Token) -> Location {
+        self.allocation
+            .location(&name.lexeme)
+            .expect("every value of the function is allocated")
+    }
+
+    /// Where the copies of a value are read from, constants are loaded when they are copied
+    fn source(&self, name: &Token) -> Source {
+        match self.allocation.location(&name.lexeme) {
+            Some(location) => Source::Location(location),
+            None => {
+                let (value, r#type) = self.constants[name.lexeme.as_str()];
+                Source::Constant(value, r#type)
+            }
+        }
+    }
+
+    /// The register holding the operand, constants and spilled values are put in `scratch`
+    /// first
+    fn read(
+        &mut self,
+        operand: &Operand,
+        ty: &ssa::Type,
+        scratch: Register,
+    ) -> Result<Register, BitBoxError> {
+        match operand {
+            Operand::Variable(name) => match self.source(name) {
+                Source::Location(Location::Register(register)) => return Ok(register),
+                Source::Location(Location::Slot(slot)) => self.peek(scratch, slot),
+                Source::Constant(value, r#type) => self.load(scratch, value, r#type),
+            },
+            Operand::Constant(token) => {
+                let r#type = vm_type(ty, token)?;
+                let value = literal::typed_value(token, ty)
+                    .expect("constants are checked by ssa::typeck")
+                    .bits();
+                self.load(scratch, value, r#type);
+            }
+        }
+        Ok(scratch)
+    }
+
+    fn load(&mut self, register: Register, value: u64, r#type: Type) {
+        self.emit(
+            Opcode::Load,
+            r#type,
+            Data::Imm(register, Imm::new(value, r#type)),
+        );
+    }
+
+    /// How far below the top of the stack a slot is, the frame's last slot is on top of it
+    fn depth_of(&self, slot: usize) -> u64 {
+        (self.allocation.slots() - 1 - slot + self.depth) as u64
+    }
+
+    fn peek(&mut self, register: Register, slot: usize) {
+        let depth = self.depth_of(slot);
+        self.emit(
+            Opcode::Peek,
+            Type::U(32),
+            Data::Imm(register, Imm::new(depth, Type::U(32))),
+        );
+    }
+
+    fn poke(&mut self, register: Register, slot: usize) {
+        let depth = self.depth_of(slot);
+        self.emit(
+            Opcode::Poke,
+            Type::U(32),
+            Data::Imm(register, Imm::new(depth, Type::U(32))),
+        );
+    }
+
+    fn push(&mut self, register: Register) {
+        self.emit(Opcode::Push, Type::Void, Data::Reg1(register));
+        self.depth += 1;
+    }
+
+    fn pop(&mut self, register: Register) {
+        self.emit(Opcode::Pop, Type::Void, Data::Reg1(register));
+        self.depth -= 1;
+    }
+
+    /// Type of a named value, the type `@jumpif` reads its condition as
+    fn type_of(&self, name: &Token) -> ssa::Type {
+        let variables = self.function.params.iter().chain(
+            self.function
+                .blocks
+                .iter()
+                .flat_map(|block| block.instructions.iter())
+                .filter_map(|instruction| instruction.destination()),
+        );
+        variables
+            .map(|variable| (&variable.name, &variable.ty))
+            .chain(
+                self.program
+                    .constants
+                    .iter()
+                    .map(|constant| (&constant.name, &constant.ty)),
+            )
+            .find(|(other, _)| other.lexeme == name.lexeme)
+            .map(|(_, ty)| ty.clone())
+            .expect("names are checked by ssa::verify")
+    }
+
+    fn successor(&self, label: &Token) -> &'a BasicBlock {
+        ssa::find_block(&self.function.blocks, label).expect("labels are checked by the parser")
+    }
+
+    fn jump(&mut self, block: &BasicBlock, label: &Token) {
+        let successor = self.successor(label);
+        self.copies(block, successor);
+        let target = block_label(self.function, successor);
+        self.emit(Opcode::Jump, Type::Void, Data::Label(Label::Name(target)));
+    }
+
+    /// Where a conditional branch to `label` goes: the block itself, or a stub that does the
+    /// copies for its phis and then jumps to it
+    fn branch_target(&mut self, block: &BasicBlock, label: &Token) -> String {
+        let successor = self.successor(label);
+        let target = block_label(self.function, successor);
+        if !has_phis(successor) {
+            return target;
+        }
+        let stub = format!("{}.{}", target, self.stubs.len());
+        let text = std::mem::replace(&mut self.text, vec![Item::Label(stub.clone())]);
+        self.copies(block, successor);
+        self.emit(Opcode::Jump, Type::Void, Data::Label(Label::Name(target)));
+        let copies = std::mem::replace(&mut self.text, text);
+        self.stubs.extend(copies);
+        stub
+    }
+
+    /// Sets the phis of `successor` to their values for the edge from `block`
+    fn copies(&mut self, block: &BasicBlock, successor: &BasicBlock) {
+        let mut moves = vec![];
+        for instruction in successor.instructions.iter() {
+            let ssa::Instruction::Phi(variable, incoming) = instruction else {
+                break;
+            };
+            let (value, _, _) = incoming
+                .iter()
+                .find(|(_, _, id)| *id == block.id)
+                .expect("phis have a value for every predecessor");
+            moves.push((self.location(&variable.name), self.source(&value.name)));
+        }
+        self.parallel_copy(moves);
+    }
+
+    /// Does every copy as if they all read their source before any is written. A copy is done
+    /// once nothing still to be done reads its destination, and a cycle of copies is broken by
+    /// saving one of its destinations in the second scratch register.
+    fn parallel_copy(&mut self, mut moves: Vec<(Location, Source)>) {
+        moves.retain(|(des, src)| Source::Location(*des) != *src);
+        while !moves.is_empty() {
+            let ready = moves
+                .iter()
+                .position(|(des, _)| !moves.iter().any(|(_, src)| *src == Source::Location(*des)));
+            match ready {
+                Some(index) => {
+                    let (des, src) = moves.remove(index);
+                    self.copy(des, src);
+                }
+                None => {
+                    let (des, _) = moves[0];
+                    let temp = Location::Register(SCRATCH[1]);
+                    self.copy(temp, Source::Location(des));
+                    for (_, src) in moves.iter_mut() {
+                        if *src == Source::Location(des) {
+                            *src = Source::Location(temp);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn copy(&mut self, des: Location, src: Source) {
+        match (des, src) {
+            (Location::Register(des), Source::Location(Location::Register(src))) => {
+                self.emit(Opcode::Copy, Type::Void, Data::Reg2(des, src))
+            }
+            (Location::Register(des), Source::Location(Location::Slot(src))) => self.peek(des, src),
+            (Location::Register(des), Source::Constant(value, r#type)) => {
+                self.load(des, value, r#type)
+            }
+            (Location::Slot(des), Source::Location(Location::Register(src))) => self.poke(src, des),
+            (Location::Slot(des), Source::Location(Location::Slot(src))) => {
+                self.peek(SCRATCH[0], src);
+                self.poke(SCRATCH[0], des);
+            }
+            (Location::Slot(des), Source::Constant(value, r#type)) => {
+                self.load(SCRATCH[0], value, r#type);
+                self.poke(SCRATCH[0], des);
+            }
+        }
+    }
+
+    /// Calls a function or a `core` import. The registers of values live across the call are
+    /// pushed, the arguments are pushed and popped into their registers and the result is
+    /// moved out of `R0` before the saved registers are popped.
+    fn call(
+        &mut self,
+        at: usize,
+        variable: &ssa::Variable,
+        name: &Token,
+        arguments: &[Operand],
+    ) -> Result<(), BitBoxError> {
+        let (params, syscall) = self.callee(name)?;
+        let saved = self.allocation.live_across(at);
+
+        for register in saved.iter() {
+            self.push(*register);
+        }
+        for (argument, ty) in arguments.iter().zip(params.iter()) {
+            let register = self.read(argument, ty, SCRATCH[0])?;
+            self.push(register);
+        }
+        // Syscalls take their number in `R0` and their arguments after it
+        let first = syscall.is_some() as usize;
+        for index in (first..first + arguments.len()).rev() {
+            let register = Register::try_from(index as u8)
+                .ok()
+                .filter(|register| (*register as usize) < REGISTERS)
+                .ok_or_else(|| {
+                    unsupported(
+                        name,
+                        format!("a call with more than {} arguments", REGISTERS - first),
+                    )
+                })?;
+            self.pop(register);
+        }
+        match syscall {
+            Some(number) => {
+                self.load(Register::R0, number, Type::U(8));
+                self.emit(Opcode::Syscall, Type::Void, Data::NoArgs);
+            }
+            None => self.emit(
+                Opcode::Call,
+                Type::Void,
+                Data::Label(Label::Name(name.lexeme.clone())),
+            ),
+        }
+        let result = Location::Register(Register::R0);
+        if let Some(des) = self
+            .allocation
+            .location(&variable.name.lexeme)
+            .filter(|des| *des != result)
+        {
+            self.copy(des, Source::Location(result));
+        }
+        for register in saved.iter().rev() {
+            self.pop(*register);
+        }
+        Ok(())
+    }
+
+    /// Parameter types of the function called `name` and the syscall it is lowered to when it
+    /// is an import
+    fn callee(&self, name: &Token) -> Result<(Vec<ssa::Type>, Option<u64>), BitBoxError> {
+        if let Some(function) = self
+            .program
+            .functions
+            .iter()
+            .find(|function| function.name == name.lexeme)
+        {
+            let params = function.params.iter().map(|param| param.ty.clone());
+            return Ok((params.collect(), None));
+        }
+        let spec = self
+            .program
+            .imports
+            .iter()
+            .find_map(|import| match import {
+                Import::Function(spec) if spec.name.lexeme == name.lexeme => Some(spec),
+                Import::Function(_) => None,
+            })
+            .ok_or_else(|| BitBoxError::UndefinedFunction(name.clone()))?;
+        let syscall = match (spec.module_name.lexeme.as_str(), spec.name.lexeme.as_str()) {
+            ("core", "write") => WRITE,
+            ("core", "exit") => EXIT,
+            (module, function) => {
+                return Err(unsupported(
+                    name,
+                    format!("the import {}::{}", module, function),
+                ))
+            }
+        };
+        Ok((spec.params.clone(), Some(syscall)))
+    }
+}
+
+fn has_phis(block: &BasicBlock) -> bool {
+    matches!(block.instructions.first(), Some(ssa::Instruction::Phi(..)))
+}
@@ -0,0 +1,421 @@
+//! The bytecode of `old/src/instructions`, extended with opcodes 21 and up. An instruction is an
+//! opcode byte, a type byte and then its data: registers are one byte each, immediates are as
+//! wide as the type and labels are a `u32` address in the program.
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+/// Address of every label in the program, including the header and data section
+pub type SymbolTable = HashMap<String, u32>;
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Register {
+    R0 = 0,
+    R1 = 1,
+    R2 = 2,
+    R3 = 3,
+    R4 = 4,
+    R5 = 5,
+    R6 = 6,
+    R7 = 7,
+    R8 = 8,
+    R9 = 9,
+    R10 = 10,
+    R11 = 11,
+    R12 = 12,
+    R13 = 13,
+    R14 = 14,
+    R15 = 15,
+    R16 = 16,
+    R17 = 17,
+    R18 = 18,
+    R19 = 19,
+    R20 = 20,
+    R21 = 21,
+    R22 = 22,
+    R23 = 23,
+    R24 = 24,
+    R25 = 25,
+    R26 = 26,
+    R27 = 27,
+    R28 = 28,
+    R29 = 29,
+    R30 = 30,
+    R31 = 31,
+}
+
+impl Register {
+    pub const COUNT: usize = 32;
+}
+
+impl TryFrom<u8> for Register {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::R0),
+            1 => Ok(Self::R1),
+            2 => Ok(Self::R2),
+            3 => Ok(Self::R3),
+            4 => Ok(Self::R4),
+            5 => Ok(Self::R5),
+            6 => Ok(Self::R6),
+            7 => Ok(Self::R7),
+            8 => Ok(Self::R8),
+            9 => Ok(Self::R9),
+            10 => Ok(Self::R10),
+            11 => Ok(Self::R11),
+            12 => Ok(Self::R12),
+            13 => Ok(Self::R13),
+            14 => Ok(Self::R14),
+            15 => Ok(Self::R15),
+            16 => Ok(Self::R16),
+            17 => Ok(Self::R17),
+            18 => Ok(Self::R18),
+            19 => Ok(Self::R19),
+            20 => Ok(Self::R20),
+            21 => Ok(Self::R21),
+            22 => Ok(Self::R22),
+            23 => Ok(Self::R23),
+            24 => Ok(Self::R24),
+            25 => Ok(Self::R25),
+            26 => Ok(Self::R26),
+            27 => Ok(Self::R27),
+            28 => Ok(Self::R28),
+            29 => Ok(Self::R29),
+            30 => Ok(Self::R30),
+            31 => Ok(Self::R31),
+            _ => Err(value),
+        }
+    }
+}
+
+impl Display for Register {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "%{}", *self as u8)
+    }
+}
+
+/// Opcodes 0 to 20 are the ones of the old assembler, the rest were added for the SSA IR and to
+/// `old/src/instructions`
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Opcode {
+    Load = 0,
+    Store = 1,
+    Copy = 2,
+    Aloc = 3,
+    Push = 4,
+    Pop = 5,
+    Add = 6,
+    Sub = 7,
+    Div = 8,
+    Mul = 9,
+    Inc = 10,
+    Eq = 11,
+    Jne = 12,
+    Hult = 13,
+    PrintReg = 14,
+    Call = 15,
+    And = 16,
+    Or = 17,
+    Shr = 18,
+    Return = 19,
+    Syscall = 20,
+    Jump = 21,
+    Ne = 22,
+    /// Signed for `Type::I` and unsigned for `Type::U`
+    Lt = 23,
+    /// Signed for `Type::I` and unsigned for `Type::U`
+    Le = 24,
+    /// Copies the value the immediate counts down from the top of the stack into the register,
+    /// 0 is the top
+    Peek = 25,
+    /// Overwrites the value the immediate counts down from the top of the stack with the
+    /// register
+    Poke = 26,
+}
+
+impl TryFrom<u8> for Opcode {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Opcode::Load),
+            1 => Ok(Opcode::Store),
+            2 => Ok(Opcode::Copy),
+            3 => Ok(Opcode::Aloc),
+            4 => Ok(Opcode::Push),
+            5 => Ok(Opcode::Pop),
+            6 => Ok(Opcode::Add),
+            7 => Ok(Opcode::Sub),
+            8 => Ok(Opcode::Div),
+            9 => Ok(Opcode::Mul),
+            10 => Ok(Opcode::Inc),
+            11 => Ok(Opcode::Eq),
+            12 => Ok(Opcode::Jne),
+            13 => Ok(Opcode::Hult),
+            14 => Ok(Opcode::PrintReg),
+            15 => Ok(Opcode::Call),
+            16 => Ok(Opcode::And),
+            17 => Ok(Opcode::Or),
+            18 => Ok(Opcode::Shr),
+            19 => Ok(Opcode::Return),
+            20 => Ok(Opcode::Syscall),
+            21 => Ok(Opcode::Jump),
+            22 => Ok(Opcode::Ne),
+            23 => Ok(Opcode::Lt),
+            24 => Ok(Opcode::Le),
+            25 => Ok(Opcode::Peek),
+            26 => Ok(Opcode::Poke),
+            _ => Err(value),
+        }
+    }
+}
+
+impl Opcode {
+    /// Mnemonic of the old assembler
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Load => "load",
+            Self::Store => "store",
+            Self::Copy => "copy",
+            Self::Aloc => "aloc",
+            Self::Push => "push",
+            Self::Pop => "pop",
+            Self::Add => "add",
+            Self::Sub => "sub",
+            Self::Div => "div",
+            Self::Mul => "mul",
+            Self::Inc => "inc",
+            Self::Eq => "eq",
+            Self::Jne => "jne",
+            Self::Hult => "hult",
+            Self::PrintReg => "printreg",
+            Self::Call => "call",
+            Self::And => "and",
+            Self::Or => "or",
+            Self::Shr => "shr",
+            Self::Return => "return",
+            Self::Syscall => "syscall",
+            Self::Jump => "jump",
+            Self::Ne => "ne",
+            Self::Lt => "lt",
+            Self::Le => "le",
+            Self::Peek => "peek",
+            Self::Poke => "poke",
+        }
+    }
+}
+
+/// Width in bits of the value an instruction works on. The high bit of the encoded byte is
+/// set for signed integers and 0 is void.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Type {
+    /// max 64
+    U(u8),
+    /// max 64
+    I(u8),
+    Void,
+}
+
+impl From<u8> for Type {
+    fn from(value: u8) -> Self {
+        if value == 0 {
+            return Type::Void;
+        }
+        let unsigned = value & 0b1000_0000 == 0;
+        if unsigned {
+            Type::U(value & 0b0111_1111)
+        } else {
+            Type::I(value & 0b0111_1111)
+        }
+    }
+}
+
+impl Type {
+    pub fn as_u8(&self) -> u8 {
+        match self {
+            Type::U(bits) => *bits,
+            Type::I(bits) => 0b1000_0000 | *bits,
+            Type::Void => 0,
+        }
+    }
+
+    pub fn bits(&self) -> u8 {
+        match self {
+            Type::U(bits) | Type::I(bits) => *bits,
+            Type::Void => 0,
+        }
+    }
+
+    /// Size of an immediate of this type
+    pub fn bytes(&self) -> usize {
+        (self.bits() as usize).div_ceil(8)
+    }
+
+    /// Truncates `value` to the width of the type, sign extending it for `Type::I`. Registers
+    /// always hold values in this form so comparisons don't need to know where they came from.
+    pub fn wrap(&self, value: u64) -> u64 {
+        let unused = 64 - (self.bits() as u32).clamp(1, 64);
+        match self {
+            Type::I(_) => (((value << unused) as i64) >> unused) as u64,
+            Type::U(_) => (value << unused) >> unused,
+            Type::Void => value,
+        }
+    }
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Type::U(bits) => write!(f, "u{}", bits),
+            Type::I(bits) => write!(f, "i{}", bits),
+            Type::Void => write!(f, "void"),
+        }
+    }
+}
+
+/// Little endian bytes of an immediate, as many as the instruction's type is wide
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Imm(pub Vec<u8>);
+
+impl Imm {
+    pub fn new(value: u64, r#type: Type) -> Self {
+        Self(value.to_le_bytes()[..r#type.bytes()].to_vec())
+    }
+
+    /// The immediate zero extended to 64 bits
+    pub fn value(&self) -> u64 {
+        let mut bytes = [0; 8];
+        bytes[..self.0.len()].copy_from_slice(&self.0);
+        u64::from_le_bytes(bytes)
+    }
+}
+
+/// Where a jump or call goes, emitted instructions name the label and decoded ones have the
+/// address it was resolved to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Label {
+    Name(String),
+    Address(u32),
+}
+
+impl Label {
+    fn address(&self, symbol_table: &SymbolTable) -> u32 {
+        match self {
+            Self::Name(name) => *symbol_table
+                .get(name)
+                .unwrap_or_else(|| panic!("label {} was never defined", name)),
+            Self::Address(address) => *address,
+        }
+    }
+}
+
+impl Display for Label {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Name(name) => write!(f, "{}", name),
+            Self::Address(address) => write!(f, "{:#x}", address),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Data {
+    NoArgs,
+    Reg1(Register),
+    Reg2(Register, Register),
+    Reg3(Register, Register, Register),
+    Imm(Register, Imm),
+    Label(Label),
+    Reg2Label(Register, Register, Label),
+}
+
+impl Data {
+    pub fn to_bytes(&self, symbol_table: &SymbolTable) -> Vec<u8> {
+        match self {
+            Self::NoArgs => vec![],
+            Self::Reg1(reg) => vec![*reg as u8],
+            Self::Reg2(reg1, reg2) => vec![*reg1 as u8, *reg2 as u8],
+            Self::Reg3(reg1, reg2, reg3) => vec![*reg1 as u8, *reg2 as u8, *reg3 as u8],
+            Self::Imm(reg, imm) => std::iter::once(*reg as u8).chain(imm.0.clone()).collect(),
+            Self::Label(label) => label.address(symbol_table).to_le_bytes().to_vec(),
+            Self::Reg2Label(lhs, rhs, label) => [*lhs as u8, *rhs as u8]
+                .into_iter()
+                .chain(label.address(symbol_table).to_le_bytes())
+                .collect(),
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        match self {
+            Self::NoArgs => 0,
+            Self::Reg1(_) => 1,
+            Self::Reg2(_, _) => 2,
+            Self::Reg3(_, _, _) => 3,
+            Self::Imm(_, imm) => 1 + imm.0.len(),
+            Self::Label(..) => 4,
+            Self::Reg2Label(..) => 6,
+        }
+    }
+}
+
+impl Display for Data {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoArgs => Ok(()),
+            Self::Reg1(reg) => write!(f, " {}", reg),
+            Self::Reg2(reg1, reg2) => write!(f, " {} {}", reg1, reg2),
+            Self::Reg3(reg1, reg2, reg3) => write!(f, " {} {} {}", reg1, reg2, reg3),
+            Self::Imm(reg, imm) => write!(f, " {} {}", reg, imm.value()),
+            Self::Label(label) => write!(f, " {}", label),
+            Self::Reg2Label(lhs, rhs, label) => write!(f, " {} {} {}", lhs, rhs, label),
+        }
+    }
+}
+
+/// Represents an instruction
+/// Opcode = u8
+/// type = u8
+/// data = as many bytes as the opcode takes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    pub opcode: Opcode,
+    pub r#type: Type,
+    pub data: Data,
+}
+
+impl Instruction {
+    pub fn new(opcode: Opcode, r#type: Type, data: Data) -> Self {
+        Self {
+            opcode,
+            r#type,
+            data,
+        }
+    }
+
+    pub fn to_bytes(&self, symbol_table: &SymbolTable) -> Vec<u8> {
+        let mut bytes = vec![self.opcode as u8, self.r#type.as_u8()];
+        bytes.extend(self.data.to_bytes(symbol_table));
+        bytes
+    }
+
+    pub fn size(&self) -> u32 {
+        let opcode = 1;
+        let type_ = 1;
+        let data = self.data.size() as u32;
+        opcode + type_ + data
+    }
+}
+
+/// The syntax of the old assembler, `add[i32] %2 %0 %1`
+impl Display for Instruction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.r#type {
+            Type::Void => write!(f, "{}", self.opcode.name())?,
+            r#type => write!(f, "{}[{}]", self.opcode.name(), r#type)?,
+        }
+        write!(f, "{}", self.data)
+    }
+}
@@ -0,0 +1,378 @@
+use super::instruction::{Data, Imm, Instruction, Label, Opcode, Register, Type};
+use super::Header;
+use std::io::Write;
+
+/// Most values the stack holds before `Fault::StackOverflow`
+const STACK_SIZE: usize = 1 << 20;
+
+/// Why the vm stopped before it reached a `hult`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Fault {
+    /// The program is too short for a header or doesn't start with `Header::MAGIC_NUMBER`
+    InvalidHeader,
+    InvalidOpcode {
+        opcode: u8,
+        address: usize,
+    },
+    InvalidRegister(u8),
+    InvalidSyscall(u64),
+    /// Execution ran off the end of the program
+    PcOutOfBounds(usize),
+    StackUnderflow,
+    StackOverflow,
+    OutOfBounds {
+        address: u64,
+        len: u64,
+    },
+    DivisionByZero,
+    /// More instructions were run than `Vm::with_fuel` allowed
+    OutOfFuel,
+    /// The exit syscall ended the program
+    Exit(i32),
+    /// Writing the output of the write syscall failed
+    Io(String),
+}
+
+impl std::fmt::Display for Fault {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidHeader => write!(f, "not a bitbox vm program"),
+            Self::InvalidOpcode { opcode, address } => {
+                write!(f, "invalid opcode {:#04x} at {:#x}", opcode, address)
+            }
+            Self::InvalidRegister(register) => write!(f, "register {} out of bounds", register),
+            Self::InvalidSyscall(number) => write!(f, "invalid syscall {}", number),
+            Self::PcOutOfBounds(pc) => write!(f, "pc {:#x} is outside of the program", pc),
+            Self::StackUnderflow => write!(f, "stack underflow"),
+            Self::StackOverflow => write!(f, "stack overflow"),
+            Self::OutOfBounds { address, len } => write!(
+                f,
+                "{} bytes at {:#x} are out of bounds of the heap",
+                len, address
+            ),
+            Self::DivisionByZero => write!(f, "division by zero"),
+            Self::OutOfFuel => write!(f, "ran out of fuel"),
+            Self::Exit(status) => write!(f, "exited with status {}", status),
+            Self::Io(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for Fault {}
+
+/// The register machine of `old/src/vm`: 32 `u64` registers, a stack of `u64`s and a byte
+/// addressed heap that starts out as a copy of the data section
+pub struct Vm<'a> {
+    program: Vec<u8>,
+    registers: [u64; Register::COUNT],
+    stack: Vec<u64>,
+    heap: Vec<u8>,
+    pc: usize,
+    running: bool,
+    /// Where the write syscall writes to
+    output: Box<dyn Write + 'a>,
+    /// Instructions left to run, unlimited when `None`
+    fuel: Option<u64>,
+}
+
+// Public implementation
+impl<'a> Vm<'a> {
+    pub fn new(program: Vec<u8>) -> Result<Self, Fault> {
+        let word = |offset: usize| {
+            let bytes = program
+                .get(offset..offset + 4)
+                .ok_or(Fault::InvalidHeader)?;
+            Ok(u32::from_le_bytes(bytes.try_into().expect("4 bytes")) as usize)
+        };
+        if program.len() < Header::SIZE || program[..4] != Header::MAGIC_NUMBER {
+            return Err(Fault::InvalidHeader);
+        }
+        let data = Header::SIZE..Header::SIZE + word(Header::TEXT_OFFSET)?;
+        let heap = program.get(data).ok_or(Fault::InvalidHeader)?.to_vec();
+        let pc = word(Header::ENTRY_OFFSET)?;
+
+        Ok(Self {
+            program,
+            registers: [0; Register::COUNT],
+            stack: Vec::new(),
+            heap,
+            pc,
+            running: true,
+            output: Box::new(std::io::sink()),
+            fuel: None,
+        })
+    }
+
+    /// Where the write syscall writes to, it is discarded by default
+    pub fn with_output(mut self, output: impl Write + 'a) -> Self {
+        self.output = Box::new(output);
+        self
+    }
+
+    /// Stops with `Fault::OutOfFuel` after running `fuel` instructions
+    #[cfg(test)]
+    pub fn with_fuel(mut self, fuel: u64) -> Self {
+        self.fuel = Some(fuel);
+        self
+    }
+
+    pub fn register(&self, register: Register) -> u64 {
+        self.registers[register as usize]
+    }
+
+    #[cfg(test)]
+    pub fn heap(&self) -> &[u8] {
+        &self.heap
+    }
+
+    /// Runs from the entry point until a `hult`, returning the status the program exits with:
+    /// the one given to the exit syscall or else the low 32 bits of `R0`
+    pub fn run(&mut self) -> Result<i32, Fault> {
+        match self.resume() {
+            Ok(()) => Ok(self.register(Register::R0) as i32),
+            Err(Fault::Exit(status)) => Ok(status),
+            Err(fault) => Err(fault),
+        }
+    }
+
+    /// Calls the function at `address` with `arguments` in `R0` onwards and returns `R0`.
+    /// The function returns to the `hult` every program starts its text section with.
+    #[cfg(test)]
+    pub fn call(&mut self, address: u32, arguments: &[u64]) -> Result<u64, Fault> {
+        self.registers[..arguments.len()].copy_from_slice(arguments);
+        let text = Header::SIZE as u64 + self.heap.len() as u64;
+        self.push(text)?;
+        self.pc = address as usize;
+        self.running = true;
+        self.resume()?;
+        Ok(self.register(Register::R0))
+    }
+
+    pub fn execute(&mut self) -> Result<(), Fault> {
+        if let Some(fuel) = self.fuel.as_mut() {
+            if *fuel == 0 {
+                return Err(Fault::OutOfFuel);
+            }
+            *fuel -= 1;
+        }
+        let instruction = self.decode()?;
+        let r#type = instruction.r#type;
+        match (instruction.opcode, instruction.data) {
+            (Opcode::Load, Data::Imm(reg, imm)) => self.set(reg, r#type.wrap(imm.value())),
+            (Opcode::Store, Data::Reg2(des, src)) => {
+                let bytes = self.get(src).to_le_bytes();
+                let range = self.range(self.get(des), r#type.bytes() as u64)?;
+                self.heap[range].copy_from_slice(&bytes[..r#type.bytes()]);
+            }
+            (Opcode::Copy, Data::Reg2(des, src)) => self.set(des, self.get(src)),
+            (Opcode::Aloc, Data::Reg2(ret, size)) => {
+                self.set(ret, self.heap.len() as u64);
+                self.heap
+                    .resize(self.heap.len() + self.get(size) as usize, 0);
+            }
+            (Opcode::Peek, Data::Imm(reg, depth)) => {
+                let index = self.stack_index(depth.value())?;
+                self.set(reg, self.stack[index]);
+            }
+            (Opcode::Poke, Data::Imm(reg, depth)) => {
+                let index = self.stack_index(depth.value())?;
+                self.stack[index] = self.get(reg);
+            }
+            (Opcode::Push, Data::Reg1(reg)) => self.push(self.get(reg))?,
+            (Opcode::Pop, Data::Reg1(reg)) => {
+                let value = self.pop()?;
+                self.set(reg, value);
+            }
+            (Opcode::Inc, Data::Reg1(reg)) => {
+                self.set(reg, r#type.wrap(self.get(reg).wrapping_add(1)))
+            }
+            (opcode, Data::Reg3(des, lhs, rhs)) => {
+                let value = binary(opcode, r#type, self.get(lhs), self.get(rhs))?;
+                self.set(des, value);
+            }
+            (Opcode::Jne, Data::Reg2Label(lhs, rhs, Label::Address(address))) => {
+                if self.get(lhs) != self.get(rhs) {
+                    self.pc = address as usize;
+                }
+            }
+            (Opcode::Jump, Data::Label(Label::Address(address))) => self.pc = address as usize,
+            (Opcode::Hult, Data::NoArgs) => self.running = false,
+            (Opcode::PrintReg, Data::Reg1(reg)) => {
+                let value = match r#type {
+                    Type::I(_) => (self.get(reg) as i64).to_string(),
+                    _ => self.get(reg).to_string(),
+                };
+                writeln!(self.output, "{}", value).map_err(|err| Fault::Io(err.to_string()))?;
+            }
+            (Opcode::Call, Data::Label(Label::Address(address))) => {
+                // Prologue
+                self.push(self.pc as u64)?;
+                self.pc = address as usize;
+            }
+            (Opcode::Return, Data::NoArgs) => {
+                // Epilogue
+                self.pc = self.pop()? as usize;
+            }
+            (Opcode::Syscall, Data::NoArgs) => self.syscall()?,
+            (opcode, data) => unreachable!("{:?} was decoded with {:?}", opcode, data),
+        }
+        Ok(())
+    }
+}
+
+// Private implementation
+impl Vm<'_> {
+    fn resume(&mut self) -> Result<(), Fault> {
+        while self.running {
+            self.execute()?;
+        }
+        Ok(())
+    }
+
+    fn get(&self, reg: Register) -> u64 {
+        self.registers[reg as usize]
+    }
+
+    fn set(&mut self, reg: Register, value: u64) {
+        self.registers[reg as usize] = value;
+    }
+
+    fn push(&mut self, value: u64) -> Result<(), Fault> {
+        if self.stack.len() == STACK_SIZE {
+            return Err(Fault::StackOverflow);
+        }
+        self.stack.push(value);
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Result<u64, Fault> {
+        self.stack.pop().ok_or(Fault::StackUnderflow)
+    }
+
+    /// Index of the value `depth` down from the top of the stack
+    fn stack_index(&self, depth: u64) -> Result<usize, Fault> {
+        (self.stack.len() as u64)
+            .checked_sub(depth + 1)
+            .map(|index| index as usize)
+            .ok_or(Fault::StackUnderflow)
+    }
+
+    fn range(&self, address: u64, len: u64) -> Result<std::ops::Range<usize>, Fault> {
+        let end = address.saturating_add(len);
+        if end > self.heap.len() as u64 {
+            return Err(Fault::OutOfBounds { address, len });
+        }
+        Ok(address as usize..end as usize)
+    }
+
+    /// `R0` selects the syscall and `R1` onwards are its arguments, the result is put in `R0`
+    fn syscall(&mut self) -> Result<(), Fault> {
+        match self.register(Register::R0) {
+            // Write
+            0 => {
+                let range = self.range(self.register(Register::R1), self.register(Register::R2))?;
+                self.output
+                    .write_all(&self.heap[range.clone()])
+                    .and_then(|_| self.output.flush())
+                    .map_err(|err| Fault::Io(format!("write failed: {}", err)))?;
+                self.set(Register::R0, range.len() as u64);
+                Ok(())
+            }
+            // Exit
+            1 => Err(Fault::Exit(self.register(Register::R1) as i32)),
+            number => Err(Fault::InvalidSyscall(number)),
+        }
+    }
+
+    fn next_byte(&mut self) -> Result<u8, Fault> {
+        let byte = *self
+            .program
+            .get(self.pc)
+            .ok_or(Fault::PcOutOfBounds(self.pc))?;
+        self.pc += 1;
+        Ok(byte)
+    }
+
+    fn next_register(&mut self) -> Result<Register, Fault> {
+        let byte = self.next_byte()?;
+        Register::try_from(byte).map_err(Fault::InvalidRegister)
+    }
+
+    fn next_label(&mut self) -> Result<Label, Fault> {
+        let mut bytes = [0; 4];
+        for byte in bytes.iter_mut() {
+            *byte = self.next_byte()?;
+        }
+        Ok(Label::Address(u32::from_le_bytes(bytes)))
+    }
+
+    fn decode(&mut self) -> Result<Instruction, Fault> {
+        let address = self.pc;
+        let opcode = self.next_byte()?;
+        let opcode =
+            Opcode::try_from(opcode).map_err(|opcode| Fault::InvalidOpcode { opcode, address })?;
+        let r#type = Type::from(self.next_byte()?);
+        let data = match opcode {
+            Opcode::Load | Opcode::Peek | Opcode::Poke => {
+                let reg = self.next_register()?;
+                let imm = (0..r#type.bytes())
+                    .map(|_| self.next_byte())
+                    .collect::<Result<_, _>>()?;
+                Data::Imm(reg, Imm(imm))
+            }
+            Opcode::Store | Opcode::Copy | Opcode::Aloc => {
+                Data::Reg2(self.next_register()?, self.next_register()?)
+            }
+            Opcode::Push | Opcode::Pop | Opcode::Inc | Opcode::PrintReg => {
+                Data::Reg1(self.next_register()?)
+            }
+            Opcode::Add
+            | Opcode::Sub
+            | Opcode::Div
+            | Opcode::Mul
+            | Opcode::Eq
+            | Opcode::And
+            | Opcode::Or
+            | Opcode::Shr
+            | Opcode::Ne
+            | Opcode::Lt
+            | Opcode::Le => Data::Reg3(
+                self.next_register()?,
+                self.next_register()?,
+                self.next_register()?,
+            ),
+            Opcode::Jne => Data::Reg2Label(
+                self.next_register()?,
+                self.next_register()?,
+                self.next_label()?,
+            ),
+            Opcode::Call | Opcode::Jump => Data::Label(self.next_label()?),
+            Opcode::Hult | Opcode::Return | Opcode::Syscall => Data::NoArgs,
+        };
+        Ok(Instruction::new(opcode, r#type, data))
+    }
+}
+
+/// Operands are already wrapped to `r#type`, so comparing them as `i64` is a signed comparison
+fn binary(opcode: Opcode, r#type: Type, lhs: u64, rhs: u64) -> Result<u64, Fault> {
+    let signed = matches!(r#type, Type::I(_));
+    let value = match opcode {
+        Opcode::Add => lhs.wrapping_add(rhs),
+        Opcode::Sub => lhs.wrapping_sub(rhs),
+        Opcode::Mul => lhs.wrapping_mul(rhs),
+        Opcode::Div if rhs == 0 => return Err(Fault::DivisionByZero),
+        Opcode::Div if signed => (lhs as i64).wrapping_div(rhs as i64) as u64,
+        Opcode::Div => lhs / rhs,
+        Opcode::And => lhs & rhs,
+        Opcode::Or => lhs | rhs,
+        Opcode::Shr => lhs.checked_shr(rhs.min(64) as u32).unwrap_or(0),
+        Opcode::Eq => (lhs == rhs) as u64,
+        Opcode::Ne => (lhs != rhs) as u64,
+        Opcode::Lt if signed => ((lhs as i64) < rhs as i64) as u64,
+        Opcode::Lt => (lhs < rhs) as u64,
+        Opcode::Le if signed => (lhs as i64 <= rhs as i64) as u64,
+        Opcode::Le => (lhs <= rhs) as u64,
+        _ => unreachable!("{:?} does not take three registers", opcode),
+    };
+    Ok(r#type.wrap(value))
+}
@@ -0,0 +1,48 @@
+//! Bytecode for the register machine of `old/src/vm`, and the machine that runs it.
+//!
+//! The instruction set is the one of `old/src/instructions` extended with `jump`, `ne`, `lt`,
+//! `le`, `peek` and `poke`, which were added to `old/src/vm` and its assembler too.
+//!
+//! A program is a `Header`, the data section and then the text section. The data section is
+//! copied to the start of the heap when the program is loaded, so string constants are at the
+//! same addresses as in the interpreter's memory.
+mod emitter;
+mod instruction;
+mod machine;
+mod regalloc;
+#[cfg(test)]
+mod test;
+
+pub use emitter::Emitter;
+pub use machine::Vm;
+pub use regalloc::{Allocation, REGISTERS};
+
+#[derive(Debug)]
+pub struct Header([u8; Header::SIZE]);
+impl Header {
+    pub const MAGIC_NUMBER: [u8; 4] = [0x42, 0x42, 0x56, 0x4d]; //BBVM
+    pub const TEXT_OFFSET: usize = 4;
+    pub const ENTRY_OFFSET: usize = 8;
+    pub const SIZE: usize = 64;
+
+    /// Takes the length of bytes of the data section
+    fn set_header_text_section(&mut self, offset: u32) {
+        self.0[Self::TEXT_OFFSET..Self::TEXT_OFFSET + 4].copy_from_slice(&offset.to_le_bytes());
+    }
+
+    fn set_header_entry_point(&mut self, offset: u32) {
+        self.0[Self::ENTRY_OFFSET..Self::ENTRY_OFFSET + 4].copy_from_slice(&offset.to_le_bytes());
+    }
+
+    fn build(self) -> [u8; Header::SIZE] {
+        self.0
+    }
+}
+
+impl Default for Header {
+    fn default() -> Self {
+        let mut bytes = [0; Header::SIZE];
+        bytes[..4].copy_from_slice(&Header::MAGIC_NUMBER);
+        Self(bytes)
+    }
+}
@@ -0,0 +1,362 @@
+//! Linear scan register allocation (Poletto and Sarkar) of the values of one function.
+//!
+//! Instructions are numbered in block order, two apart, with the parameters defined at 0.
+//! Liveness is solved over the CFG and every value gets one interval from its first to its
+//! last live position, holes included. Phis define their value at the start of their block
+//! and read their incoming values at the end of the predecessor. Values that don't fit in
+//! the registers are spilled to slots in the function's frame on the vm stack.
+use super::instruction::Register;
+use crate::ssa::{BasicBlock, Function, Instruction, Operand, Type};
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+
+/// Kept out of allocation for constants, spilled operands and breaking cycles of copies
+pub const SCRATCH: [Register; 2] = [Register::R30, Register::R31];
+/// Registers values are allocated to, `R0` up to the scratch registers
+pub const REGISTERS: usize = Register::COUNT - SCRATCH.len();
+
+/// Where a value lives while it is live
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Location {
+    Register(Register),
+    /// Index into the function's frame of spill slots
+    Slot(usize),
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Register(register) => write!(f, "{}", register),
+            Self::Slot(slot) => write!(f, "slot {}", slot),
+        }
+    }
+}
+
+/// Positions a value is live at, both ends included
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Interval {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Interval {
+    fn extend(&mut self, position: usize) {
+        self.start = self.start.min(position);
+        self.end = self.end.max(position);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Allocation<'a> {
+    function: &'a Function,
+    /// Position of the first instruction of every block, in `Function::blocks` order
+    block_starts: Vec<usize>,
+    /// Every parameter and value the function defines, ordered by where their interval starts
+    values: Vec<(&'a str, Interval, Location)>,
+    /// Spill slots the function's frame needs
+    slots: usize,
+}
+
+impl<'a> Allocation<'a> {
+    /// Allocates the values of `function` to `R0` up to `registers` and spill slots
+    pub fn new(function: &'a Function, registers: usize) -> Self {
+        let block_starts = block_starts(function);
+        let mut intervals = intervals(function, &block_starts);
+        intervals.sort_by_key(|(_, interval)| (interval.start, interval.end));
+        let (locations, slots) = linear_scan(&intervals, registers);
+        let values = intervals
+            .into_iter()
+            .zip(locations)
+            .map(|((name, interval), location)| (name, interval, location))
+            .collect();
+        Self {
+            function,
+            block_starts,
+            values,
+            slots,
+        }
+    }
+
+    /// Position of an instruction, given by the position of its block in `Function::blocks`
+    /// and its index in the block
+    pub fn position(&self, block: usize, index: usize) -> usize {
+        self.block_starts[block] + 2 * index
+    }
+
+    /// Location of a parameter or a value the function defines
+    pub fn location(&self, name: &str) -> Option<Location> {
+        self.values
+            .iter()
+            .find(|(other, _, _)| *other == name)
+            .map(|(_, _, location)| *location)
+    }
+
+    pub fn slots(&self) -> usize {
+        self.slots
+    }
+
+    /// Registers holding values that are live before and after `position`, the ones a call
+    /// at `position` has to save
+    pub fn live_across(&self, position: usize) -> Vec<Register> {
+        let mut registers = self
+            .values
+            .iter()
+            .filter(|(_, interval, _)| interval.start < position && interval.end > position)
+            .filter_map(|(_, _, location)| match location {
+                Location::Register(register) => Some(*register),
+                Location::Slot(_) => None,
+            })
+            .collect::<Vec<_>>();
+        registers.sort();
+        registers
+    }
+}
+
+/// Every value's interval and location, one per line
+impl Display for Allocation<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let registers = self
+            .values
+            .iter()
+            .filter_map(|(_, _, location)| match location {
+                Location::Register(register) => Some(register),
+                Location::Slot(_) => None,
+            })
+            .collect::<HashSet<_>>();
+        writeln!(
+            f,
+            "{}: {} registers, {} slots",
+            self.function.name,
+            registers.len(),
+            self.slots
+        )?;
+        let width = self
+            .values
+            .iter()
+            .map(|(name, _, _)| name.len())
+            .max()
+            .unwrap_or(0);
+        for (name, interval, location) in self.values.iter() {
+            let range = format!("[{}, {}]", interval.start, interval.end);
+            writeln!(f, "    {:width$}  {:10}  {}", name, range, location)?;
+        }
+        Ok(())
+    }
+}
+
+fn block_starts(function: &Function) -> Vec<usize> {
+    let mut position = 2;
+    let mut starts = vec![];
+    for block in function.blocks.iter() {
+        starts.push(position);
+        position += 2 * block.instructions.len();
+    }
+    starts
+}
+
+/// Values of the function an operand reads, constants are not allocated
+fn local<'a>(operand: &'a Operand, values: &HashSet<&str>) -> Option<&'a str> {
+    match operand {
+        Operand::Variable(name) if values.contains(name.lexeme.as_str()) => {
+            Some(name.lexeme.as_str())
+        }
+        Operand::Variable(_) | Operand::Constant(_) => None,
+    }
+}
+
+/// Values the phis at the top of `successor` read on the edge from `block`
+fn phi_uses<'a>(block: &BasicBlock, successor: &'a BasicBlock) -> impl Iterator<Item = &'a str> {
+    let id = block.id;
+    successor
+        .instructions
+        .iter()
+        .map_while(|instruction| match instruction {
+            Instruction::Phi(_, incoming) => Some(incoming),
+            _ => None,
+        })
+        .filter_map(move |incoming| {
+            incoming
+                .iter()
+                .find(|(_, _, predecessor)| *predecessor == id)
+                .map(|(value, _, _)| value.name.lexeme.as_str())
+        })
+}
+
+/// Values live at the start and at the end of every block, solved backwards until nothing
+/// changes. A phi's value is not live into its block and its incoming value is live out of
+/// the predecessor it comes from.
+fn liveness<'a>(function: &'a Function, values: &HashSet<&'a str>) -> Vec<Liveness<'a>> {
+    let index_of = |id: usize| {
+        function
+            .blocks
+            .iter()
+            .position(|block| block.id == id)
+            .expect("successors are blocks of the function")
+    };
+    let mut liveness = vec![Liveness::default(); function.blocks.len()];
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (index, block) in function.blocks.iter().enumerate().rev() {
+            let mut live = HashSet::new();
+            for successor in block
+                .successors
+                .iter()
+                .map(|id| &function.blocks[index_of(*id)])
+            {
+                live.extend(phi_uses(block, successor).filter(|name| values.contains(name)));
+                live.extend(liveness[index_of(successor.id)].live_in.iter());
+            }
+            liveness[index].live_out.clone_from(&live);
+            for instruction in block.instructions.iter().rev() {
+                if let Some(variable) = instruction.destination() {
+                    live.remove(variable.name.lexeme.as_str());
+                }
+                live.extend(
+                    instruction
+                        .operands()
+                        .into_iter()
+                        .filter_map(|operand| local(operand, values)),
+                );
+            }
+            if live != liveness[index].live_in {
+                liveness[index].live_in = live;
+                changed = true;
+            }
+        }
+    }
+    liveness
+}
+
+#[derive(Debug, Clone, Default)]
+struct Liveness<'a> {
+    live_in: HashSet<&'a str>,
+    live_out: HashSet<&'a str>,
+}
+
+fn intervals<'a>(function: &'a Function, block_starts: &[usize]) -> Vec<(&'a str, Interval)> {
+    let mut order = vec![];
+    let mut intervals: HashMap<&str, Interval> = HashMap::new();
+    let mut extend = |name: &'a str, position: usize| {
+        intervals
+            .entry(name)
+            .or_insert_with(|| {
+                order.push(name);
+                Interval {
+                    start: position,
+                    end: position,
+                }
+            })
+            .extend(position);
+    };
+
+    for param in function.params.iter() {
+        extend(param.name.lexeme.as_str(), 0);
+    }
+    let values = function
+        .params
+        .iter()
+        .map(|param| param.name.lexeme.as_str())
+        .chain(
+            function
+                .blocks
+                .iter()
+                .flat_map(|block| block.instructions.iter())
+                .filter_map(|instruction| instruction.destination())
+                .filter(|variable| variable.ty != Type::Void)
+                .map(|variable| variable.name.lexeme.as_str()),
+        )
+        .collect::<HashSet<_>>();
+
+    let liveness = liveness(function, &values);
+    for (index, block) in function.blocks.iter().enumerate() {
+        let start = block_starts[index];
+        for (offset, instruction) in block.instructions.iter().enumerate() {
+            let position = start + 2 * offset;
+            match instruction {
+                // Phis are defined together at the start of the block
+                Instruction::Phi(variable, _) => extend(variable.name.lexeme.as_str(), start),
+                _ => {
+                    for operand in instruction.operands() {
+                        if let Some(name) = local(operand, &values) {
+                            extend(name, position);
+                        }
+                    }
+                    if let Some(name) = instruction
+                        .destination()
+                        .map(|variable| variable.name.lexeme.as_str())
+                        .filter(|name| values.contains(name))
+                    {
+                        extend(name, position);
+                    }
+                }
+            }
+        }
+        let end = start + 2 * block.instructions.len().saturating_sub(1);
+        for name in liveness[index].live_in.iter() {
+            extend(name, start);
+        }
+        for name in liveness[index].live_out.iter() {
+            extend(name, end);
+        }
+    }
+
+    order
+        .into_iter()
+        .map(|name| (name, intervals[name]))
+        .collect()
+}
+
+/// Gives every interval, sorted by start, a register or a slot. When no register is free
+/// the interval that ends last is spilled, which keeps the most registers free for the
+/// intervals still to come.
+fn linear_scan(intervals: &[(&str, Interval)], registers: usize) -> (Vec<Location>, usize) {
+    let mut locations = vec![Location::Slot(0); intervals.len()];
+    let mut free = (0..registers.min(REGISTERS))
+        .rev()
+        .map(|register| Register::try_from(register as u8).expect("register in range"))
+        .collect::<Vec<_>>();
+    // Indices of the intervals in a register, sorted by end
+    let mut active: Vec<usize> = vec![];
+    let mut slots = 0;
+
+    for (index, (_, interval)) in intervals.iter().enumerate() {
+        // A value whose last use is at `interval.start` can share its register with the value
+        // defined there, instructions read their operands before writing their result
+        active.retain(|other| {
+            if intervals[*other].1.end > interval.start {
+                return true;
+            }
+            if let Location::Register(register) = locations[*other] {
+                free.push(register);
+            }
+            false
+        });
+        free.sort_by(|a, b| b.cmp(a));
+
+        if let Some(register) = free.pop() {
+            locations[index] = Location::Register(register);
+        } else {
+            let spill = active.last().copied();
+            match spill {
+                Some(spill) if intervals[spill].1.end > interval.end => {
+                    locations[index] = locations[spill];
+                    locations[spill] = Location::Slot(slots);
+                    active.pop();
+                }
+                _ => {
+                    locations[index] = Location::Slot(slots);
+                    slots += 1;
+                    continue;
+                }
+            }
+            slots += 1;
+        }
+        let position = active
+            .iter()
+            .position(|other| intervals[*other].1.end > interval.end)
+            .unwrap_or(active.len());
+        active.insert(position, index);
+    }
+    (locations, slots)
+}
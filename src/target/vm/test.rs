@@ -0,0 +1,243 @@
+use super::machine::Fault;
+use super::{Allocation, Emitter, Vm, REGISTERS};
+use crate::interp::Interpreter;
+use crate::lexer::lex;
+use crate::parser;
+use crate::ssa::literal::Value;
+use crate::ssa::{self, Program};
+
+fn checked(input: &str) -> Program {
+    let tokens = lex(input);
+    let mut program = parser::Parser::new(tokens)
+        .parse()
+        .expect("failed to parse program");
+    ssa::cfg::build(&mut program)
+        .and_then(|_| ssa::phi::validate(&program))
+        .and_then(|_| ssa::verify::verify(&program))
+        .and_then(|_| ssa::typeck::check(&program))
+        .and_then(|_| ssa::const_eval::evaluate(&mut program))
+        .expect("failed to check program");
+    program
+}
+
+/// Compiles the program and calls `function` on the vm
+fn call(program: &Program, function: &str, arguments: &[Value]) -> Result<u64, Fault> {
+    call_with_registers(program, REGISTERS, function, arguments)
+}
+
+/// Compiles the program allocating only `registers` registers and calls `function` on the vm
+fn call_with_registers(
+    program: &Program,
+    registers: usize,
+    function: &str,
+    arguments: &[Value],
+) -> Result<u64, Fault> {
+    let bytecode = Emitter::new(program.clone())
+        .with_registers(registers)
+        .emit()
+        .expect("failed to emit program");
+    let address = bytecode.symbol_table()[function];
+    let arguments = arguments.iter().map(Value::bits).collect::<Vec<_>>();
+    Vm::new(bytecode.to_bytes())?
+        .with_fuel(100_000)
+        .call(address, &arguments)
+}
+
+/// The vm must return what the interpreter does, as the same bits, however many registers
+/// the values get
+fn assert_matches_interpreter(program: &Program, function: &str, arguments: &[Value]) {
+    let expected = Interpreter::new(program)
+        .call(function, arguments)
+        .expect("interpreter trapped")
+        .expect("function returns a value");
+    for registers in [REGISTERS, 2, 0] {
+        assert_eq!(
+            call_with_registers(program, registers, function, arguments),
+            Ok(expected.bits()),
+            "{function}({arguments:?}) with {registers} registers"
+        );
+    }
+}
+
+pub fn snapshot_listing(input: &str) -> String {
+    match Emitter::new(checked(input)).emit() {
+        Ok(bytecode) => bytecode.to_string(),
+        Err(err) => format!("{:#?}", err),
+    }
+}
+
+/// The allocation of every function, with all the registers and with only two of them
+pub fn snapshot_regalloc(input: &str) -> String {
+    let program = checked(input);
+    let mut output = String::new();
+    for registers in [REGISTERS, 2] {
+        for function in program.functions.iter() {
+            output += &Allocation::new(function, registers).to_string();
+        }
+    }
+    output
+}
+
+/// What `bitbox run --target vm` exits with
+pub fn snapshot_exit_code(input: &str) -> String {
+    let bytecode = Emitter::new(checked(input))
+        .emit()
+        .expect("failed to emit program");
+    let status = Vm::new(bytecode.to_bytes()).and_then(|vm| vm.with_fuel(100_000).run());
+    format!("{:?}", status)
+}
+
+/// The listing of the program with values spilled to the stack
+pub fn snapshot_spilled_listing(input: &str) -> String {
+    match Emitter::new(checked(input)).with_registers(2).emit() {
+        Ok(bytecode) => bytecode.to_string(),
+        Err(err) => format!("{:#?}", err),
+    }
+}
+
+macro_rules! snapshot {
+    ($name:tt, $path:tt) => {
+        snapshot!($name, $path, snapshot_listing);
+    };
+    ($name:tt, $path:tt, $snapshot:ident) => {
+        #[test]
+        fn $name() {
+            let contents = include_str!($path);
+            let mut settings = insta::Settings::clone_current();
+            settings.set_snapshot_path("testdata/output/");
+            settings.bind(|| {
+                insta::assert_snapshot!($snapshot(contents));
+            });
+        }
+    };
+}
+
+snapshot!(listing_phi, "../../../snapshots/phi.bitbox");
+snapshot!(listing_calls, "../../../snapshots/calls.bitbox");
+snapshot!(
+    listing_hello_world,
+    "../wasm/examples/hello_world/hello_world.bitbox"
+);
+snapshot!(
+    regalloc_phi,
+    "../../../snapshots/phi.bitbox",
+    snapshot_regalloc
+);
+snapshot!(
+    regalloc_pressure,
+    "../../../snapshots/pressure.bitbox",
+    snapshot_regalloc
+);
+snapshot!(
+    listing_spilled_pressure,
+    "../../../snapshots/pressure.bitbox",
+    snapshot_spilled_listing
+);
+snapshot!(
+    regalloc_spill,
+    "../../../snapshots/spill.bitbox",
+    snapshot_regalloc
+);
+snapshot!(
+    exit_code_spill,
+    "../../../snapshots/spill.bitbox",
+    snapshot_exit_code
+);
+
+#[test]
+fn integers_match_the_interpreter() {
+    let mut program = checked(include_str!("../../../snapshots/interp.bitbox"));
+    // The vm has no floats
+    program.functions.retain(|function| function.name != "half");
+    let cases = [
+        ("wrap_u8", vec![Value::Unsigned(100)]),
+        ("wrap_s8", vec![Value::Signed(100)]),
+        ("wrap_u64", vec![Value::Unsigned(0)]),
+        ("signed_less", vec![Value::Signed(-1), Value::Signed(1)]),
+        (
+            "unsigned_less",
+            vec![Value::Unsigned(65535), Value::Unsigned(1)],
+        ),
+        ("copy", vec![Value::Signed(-7)]),
+        ("swap", vec![Value::Signed(1)]),
+        ("swap", vec![Value::Signed(2)]),
+        ("swap", vec![Value::Signed(3)]),
+    ];
+    for (function, arguments) in cases {
+        assert_matches_interpreter(&program, function, &arguments);
+    }
+
+    let program = checked(include_str!("../../../snapshots/phi.bitbox"));
+    assert_matches_interpreter(&program, "abs", &[Value::Signed(-5)]);
+    assert_matches_interpreter(&program, "abs", &[Value::Signed(7)]);
+
+    let program = checked(include_str!("../../../snapshots/cmp.bitbox"));
+    for (x, y) in [(-3, 7), (1, 5), (2, 4)] {
+        let arguments = [Value::Signed(x), Value::Unsigned(y)];
+        assert_matches_interpreter(&program, "compare", &arguments);
+    }
+}
+
+#[test]
+fn calls_and_loops() {
+    let program = checked(include_str!("../../../snapshots/calls.bitbox"));
+    assert_matches_interpreter(&program, "main", &[]);
+
+    let program = checked(include_str!("../../../snapshots/pressure.bitbox"));
+    for x in [-4, 0, 9] {
+        assert_matches_interpreter(&program, "pressure", &[Value::Signed(x)]);
+    }
+
+    let program = checked(include_str!("../../../snapshots/spill.bitbox"));
+    assert_matches_interpreter(&program, "main", &[]);
+
+    let program = checked(include_str!("../../../snapshots/loop.bitbox"));
+    assert_eq!(call(&program, "spin", &[Value::Signed(0)]), Ok(0));
+    assert_eq!(
+        call(&program, "spin", &[Value::Signed(1)]),
+        Err(Fault::OutOfFuel)
+    );
+}
+
+#[test]
+fn faults() {
+    let mut program = checked(include_str!("../../../snapshots/interp.bitbox"));
+    program.functions.retain(|function| function.name != "half");
+    assert_eq!(call(&program, "quit", &[]), Err(Fault::Exit(3)));
+    let bytecode = Emitter::new(program)
+        .emit()
+        .expect("failed to emit program");
+    let address = bytecode.symbol_table()["recurse"];
+    let mut vm = Vm::new(bytecode.to_bytes()).expect("failed to load program");
+    assert_eq!(vm.call(address, &[1]), Err(Fault::StackOverflow));
+
+    assert_eq!(Vm::new(vec![0; 64]).err(), Some(Fault::InvalidHeader));
+}
+
+#[test]
+fn unsupported_types_are_errors() {
+    let program = checked(include_str!("../../../snapshots/interp.bitbox"));
+    let err = Emitter::new(program)
+        .emit()
+        .expect_err("the vm has no floats");
+    assert_eq!(err.code(), "E0028");
+}
+
+#[test]
+fn runs_hello_world() {
+    let program = checked(include_str!(
+        "../wasm/examples/hello_world/hello_world.bitbox"
+    ));
+    let bytes = Emitter::new(program)
+        .emit()
+        .expect("failed to emit program")
+        .to_bytes();
+    assert_eq!(bytes[..4], super::Header::MAGIC_NUMBER);
+    let mut output = vec![];
+    let vm = Vm::new(bytes).expect("failed to load program");
+    // The data section is loaded at the start of the heap
+    assert!(vm.heap().starts_with(b"Hello World\n"));
+    let status = vm.with_output(&mut output).run();
+    assert_eq!(status, Ok(12));
+    assert_eq!(output, b"Hello World\n");
+}
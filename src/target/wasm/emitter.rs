@@ -0,0 +1,618 @@
+use super::{
+    module::Module,
+    opcode::Instruction,
+    section::{
+        _type::{FunctionType, ValueType},
+        code::Block,
+        export::{ExportEntry, ExportType},
+        global::GlobalEntry,
+        memory::Page,
+        DataType,
+    },
+};
+use crate::error::BitBoxError;
+use crate::lexer::token::Token;
+
+use super::ToDataType;
+use crate::ssa;
+use crate::ssa::literal::{self, Value};
+
+/// Size of a wasm page in bytes
+const PAGE_SIZE: u32 = 0x10000;
+
+/// Local holding the index of the block to run next, `%` keeps it apart from the IR's names
+const NEXT_BLOCK: &str = "%next";
+
+#[derive(Debug)]
+pub struct Emitter {
+    module: Module,
+    program: ssa::Program,
+}
+
+impl Emitter {
+    pub fn new(program: ssa::Program) -> Self {
+        Self {
+            module: Module::default(),
+            program,
+        }
+    }
+
+    fn compile_instruction(
+        &mut self,
+        wasm_block: &mut Block,
+        instruction: &ssa::Instruction,
+        function: &ssa::Function,
+        at: usize,
+    ) -> Result<(), BitBoxError> {
+        let params = &function.params;
+        match instruction {
+            ssa::Instruction::Assign(variable, operand) => {
+                self.compile_operand(wasm_block, operand, &variable.ty, params)?;
+                set_local(wasm_block, variable, params)?;
+            }
+            ssa::Instruction::Add(variable, lhs, rhs) => {
+                let mut instructions = match data_type_of(&variable.ty, &variable.name)? {
+                    DataType::I32 => vec![Instruction::I32Add],
+                    DataType::I64 => vec![Instruction::I64Add],
+                    DataType::F32 => vec![Instruction::F32Add],
+                    DataType::F64 => vec![Instruction::F64Add],
+                };
+                instructions.extend(wrap(&variable.ty));
+                self.compile_binary(wasm_block, variable, lhs, rhs, params, &instructions)?;
+            }
+            ssa::Instruction::Sub(variable, lhs, rhs) => {
+                let mut instructions = match data_type_of(&variable.ty, &variable.name)? {
+                    DataType::I32 => vec![Instruction::I32Sub],
+                    DataType::I64 => vec![Instruction::I64Sub],
+                    DataType::F32 => vec![Instruction::F32Sub],
+                    DataType::F64 => vec![Instruction::F64Sub],
+                };
+                instructions.extend(wrap(&variable.ty));
+                self.compile_binary(wasm_block, variable, lhs, rhs, params, &instructions)?;
+            }
+            ssa::Instruction::Mul(variable, lhs, rhs) => {
+                let mut instructions = match data_type_of(&variable.ty, &variable.name)? {
+                    DataType::I32 => vec![Instruction::I32Mul],
+                    DataType::I64 => vec![Instruction::I64Mul],
+                    DataType::F32 => vec![Instruction::F32Mul],
+                    DataType::F64 => vec![Instruction::F64Mul],
+                };
+                instructions.extend(wrap(&variable.ty));
+                self.compile_binary(wasm_block, variable, lhs, rhs, params, &instructions)?;
+            }
+            ssa::Instruction::Cmp(variable, condition, lhs, rhs) => {
+                let data_type = data_type_of(&variable.ty, &variable.name)?;
+                let signed = matches!(variable.ty, ssa::Type::Signed(_));
+                // Comparisons leave an i32 which is converted back to the compared type
+                let instructions = match data_type {
+                    DataType::I32 => vec![comparison(data_type, *condition, signed)],
+                    DataType::I64 => vec![
+                        comparison(data_type, *condition, signed),
+                        Instruction::I64ExtendI32U,
+                    ],
+                    DataType::F32 => vec![
+                        comparison(data_type, *condition, signed),
+                        Instruction::F32ConvertI32U,
+                    ],
+                    DataType::F64 => vec![
+                        comparison(data_type, *condition, signed),
+                        Instruction::F64ConvertI32U,
+                    ],
+                };
+                self.compile_binary(wasm_block, variable, lhs, rhs, params, &instructions)?;
+            }
+            ssa::Instruction::Jump(label) => {
+                self.compile_jump(wasm_block, function, at, label, 0)?;
+            }
+            ssa::Instruction::JumpIf(condition, label) => {
+                // Leaving the block skips the jump and falls through to the next block
+                wasm_block.push(Instruction::Block);
+                match condition {
+                    ssa::Operand::Constant(number) => {
+                        let taken =
+                            literal::Literal::parse(number)? != literal::Literal::Integer(0);
+                        wasm_block.push(Instruction::I32Const(taken as i32));
+                        wasm_block.push(Instruction::I32Eqz);
+                    }
+                    ssa::Operand::Variable(name) => {
+                        let Some(ty) = type_of(function, name) else {
+                            return Err(BitBoxError::UndefinedVariable(name.clone()));
+                        };
+                        self.compile_operand(wasm_block, condition, ty, params)?;
+                        match data_type_of(ty, name)? {
+                            DataType::I64 => wasm_block.push(Instruction::I64Eqz),
+                            _ => wasm_block.push(Instruction::I32Eqz),
+                        }
+                    }
+                }
+                wasm_block.push(Instruction::BrIf(0));
+                self.compile_jump(wasm_block, function, at, label, 1)?;
+                wasm_block.push(Instruction::End);
+                if let Some(next) = function.blocks.get(at + 1) {
+                    self.compile_phi_copies(wasm_block, function, at, next)?;
+                }
+            }
+            ssa::Instruction::Return(ty, operand) => {
+                self.compile_operand(wasm_block, operand, ty, params)?;
+                wasm_block.push(Instruction::Return);
+            }
+            // Set by the jumps into the block, see `compile_phi_copies`
+            ssa::Instruction::Phi(..) => {}
+            ssa::Instruction::Call(variable, name, arguments) => {
+                let (Some(id), Some(types)) = (
+                    self.module.get_function_id(&name.lexeme),
+                    self.param_types(&name.lexeme),
+                ) else {
+                    return Err(BitBoxError::UndefinedFunction(name.clone()));
+                };
+                for (argument, ty) in arguments.iter().zip(types.iter()) {
+                    self.compile_operand(wasm_block, argument, ty, params)?;
+                }
+                wasm_block.push(Instruction::Call(id));
+                // Calls to void functions leave nothing on the stack to store
+                if let ssa::Type::Void = variable.ty {
+                    return Ok(());
+                }
+                set_local(wasm_block, variable, params)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Branches to the dispatch loop with the block `label` as the next one, `nesting` counts the
+    /// structured instructions the jump is inside of within its block
+    fn compile_jump(
+        &mut self,
+        wasm_block: &mut Block,
+        function: &ssa::Function,
+        at: usize,
+        label: &Token,
+        nesting: usize,
+    ) -> Result<(), BitBoxError> {
+        let Some(target) = ssa::find_block(&function.blocks, label) else {
+            return Err(BitBoxError::UndefinedLabel(label.clone()));
+        };
+        let Some(next) = wasm_block.get_local_index(NEXT_BLOCK, function.params.len()) else {
+            unreachable!("functions with jumps have a {} local", NEXT_BLOCK);
+        };
+        self.compile_phi_copies(wasm_block, function, at, target)?;
+        wasm_block.push(Instruction::I32Const(target.id as i32));
+        wasm_block.push(Instruction::LocalSet(next as u32));
+        // Block `at` is placed after the end of the wasm block it is the target of, inside the
+        // wasm blocks of the blocks after it
+        let depth = function.blocks.len() - 1 - at + nesting;
+        wasm_block.push(Instruction::Br(depth as u32));
+        Ok(())
+    }
+
+    /// Sets the phis of `target` to their values coming from block `at`. Every value is pushed
+    /// before any phi is set since a phi may read another phi of the same block.
+    fn compile_phi_copies(
+        &mut self,
+        wasm_block: &mut Block,
+        function: &ssa::Function,
+        at: usize,
+        target: &ssa::BasicBlock,
+    ) -> Result<(), BitBoxError> {
+        let from = function.blocks[at].id;
+        let mut phis = vec![];
+        for instruction in target.instructions.iter() {
+            let ssa::Instruction::Phi(variable, incoming) = instruction else {
+                continue;
+            };
+            let Some((value, ..)) = incoming.iter().find(|(_, _, id)| *id == from) else {
+                let predecessor = function.blocks[at]
+                    .label
+                    .as_ref()
+                    .map(|label| format!("%{}", label.lexeme))
+                    .unwrap_or_else(|| "the entry block".to_string());
+                return Err(BitBoxError::MissingPhiIncoming {
+                    phi: variable.name.clone(),
+                    predecessor,
+                });
+            };
+            let operand = ssa::Operand::Variable(value.name.clone());
+            self.compile_operand(wasm_block, &operand, &variable.ty, &function.params)?;
+            phis.push(variable);
+        }
+        for variable in phis.into_iter().rev() {
+            set_local(wasm_block, variable, &function.params)?;
+        }
+        Ok(())
+    }
+
+    /// Types of the parameters of the function or import called `name`
+    fn param_types(&self, name: &str) -> Option<Vec<ssa::Type>> {
+        let function = self
+            .program
+            .functions
+            .iter()
+            .find(|function| function.name == name)
+            .map(|function| {
+                function
+                    .params
+                    .iter()
+                    .map(|param| param.ty.clone())
+                    .collect()
+            });
+        let import = self.program.imports.iter().find_map(|import| match import {
+            ssa::Import::Function(spec) if spec.name.lexeme == name => Some(spec.params.clone()),
+            ssa::Import::Function(_) => None,
+        });
+        function.or(import)
+    }
+
+    /// Computes `lhs <instructions> rhs` into a new local for `variable`, the operands have the
+    /// variable's type
+    fn compile_binary(
+        &mut self,
+        wasm_block: &mut Block,
+        variable: &ssa::Variable,
+        lhs: &ssa::Operand,
+        rhs: &ssa::Operand,
+        params: &[ssa::Variable],
+        instructions: &[Instruction],
+    ) -> Result<(), BitBoxError> {
+        self.compile_operand(wasm_block, lhs, &variable.ty, params)?;
+        self.compile_operand(wasm_block, rhs, &variable.ty, params)?;
+        for instruction in instructions.iter() {
+            wasm_block.push(instruction.clone());
+        }
+        set_local(wasm_block, variable, params)
+    }
+
+    /// Pushes the operand's value, constants are encoded as `ty`
+    fn compile_operand(
+        &mut self,
+        wasm_block: &mut Block,
+        operand: &ssa::Operand,
+        ty: &ssa::Type,
+        params: &[ssa::Variable],
+    ) -> Result<(), BitBoxError> {
+        match operand {
+            ssa::Operand::Variable(variable) => {
+                if let Some(index) = params
+                    .iter()
+                    .position(|param| param.name.lexeme == *variable.lexeme)
+                    .or(wasm_block.get_local_index(&variable.lexeme, params.len()))
+                {
+                    let instruction = Instruction::LocalGet(index as u32);
+                    wasm_block.push(instruction);
+                    return Ok(());
+                } else if let Some(pointer) = self.module.get_data_pointer(&variable.lexeme) {
+                    let instruction = Instruction::I32Const(pointer.offset as i32);
+                    wasm_block.push(instruction);
+                    return Ok(());
+                } else if let Some(index) = self.module.get_global_index(&variable.lexeme) {
+                    let instruction = Instruction::GlobalGet(index as u32);
+                    wasm_block.push(instruction);
+                    return Ok(());
+                }
+                return Err(BitBoxError::UndefinedVariable(variable.clone()));
+            }
+            ssa::Operand::Constant(number) => {
+                let data_type = data_type_of(ty, number)?;
+                let instruction = match (data_type, literal::typed_value(number, ty)?) {
+                    (DataType::I32, value) => Instruction::I32Const(value.bits() as i32),
+                    (DataType::I64, value) => Instruction::I64Const(value.bits() as i64),
+                    (DataType::F32, Value::F32(value)) => Instruction::F32Const(value),
+                    (DataType::F64, Value::F64(value)) => Instruction::F64Const(value),
+                    (DataType::F32 | DataType::F64, _) => {
+                        return Err(float_mismatch(ty, number));
+                    }
+                };
+                wasm_block.push(instruction);
+            }
+        }
+        Ok(())
+    }
+
+    /// A function without jumps is compiled as it is. Otherwise every block gets a wasm
+    /// block that a `br_table` in the innermost one can leave to run it, all inside a loop that
+    /// jumps branch back to:
+    ///
+    /// ```text
+    /// loop
+    ///   block ... block
+    ///     local.get %next
+    ///     br_table 0 1 ... n-1
+    ///   end
+    ///   <block 0>
+    ///   ...
+    ///   end
+    ///   <block n-1>
+    /// end
+    /// unreachable
+    /// ```
+    ///
+    /// A block without a jump falls through to the next one.
+    fn compile_basic_block(&mut self, function: &ssa::Function) -> Result<Block, BitBoxError> {
+        let mut wasm_block = Block::default();
+        // Locals are declared up front since a block may use values of blocks placed after it
+        for block in function.blocks.iter() {
+            for variable in block.instructions.iter().filter_map(|i| i.destination()) {
+                if let ssa::Type::Void = variable.ty {
+                    continue;
+                }
+                let data_type = data_type_of(&variable.ty, &variable.name)?;
+                wasm_block.push_local(&variable.name.lexeme, data_type);
+            }
+        }
+
+        let dispatch = function
+            .blocks
+            .iter()
+            .flat_map(|block| block.instructions.iter())
+            .any(|instruction| {
+                matches!(
+                    instruction,
+                    ssa::Instruction::Jump(_) | ssa::Instruction::JumpIf(..)
+                )
+            });
+        if dispatch {
+            wasm_block.push_local(NEXT_BLOCK, DataType::I32);
+            let next = wasm_block
+                .get_local_index(NEXT_BLOCK, function.params.len())
+                .expect("the local was just declared");
+            wasm_block.push(Instruction::Loop);
+            for _ in function.blocks.iter() {
+                wasm_block.push(Instruction::Block);
+            }
+            wasm_block.push(Instruction::LocalGet(next as u32));
+            let table = (0..function.blocks.len() as u32).collect();
+            wasm_block.push(Instruction::BrTable(table, 0));
+        }
+        for (at, block) in function.blocks.iter().enumerate() {
+            if dispatch {
+                wasm_block.push(Instruction::End);
+            }
+            for instruction in block.instructions.iter() {
+                self.compile_instruction(&mut wasm_block, instruction, function, at)?;
+            }
+        }
+        if dispatch {
+            wasm_block.push(Instruction::End);
+            wasm_block.push(Instruction::Unreachable);
+        }
+        Ok(wasm_block)
+    }
+
+    /// Every function is declared before any body is compiled so `@call` can reach functions
+    /// defined further down. Imports are declared first and take the lowest indices.
+    fn compile_function_in_module(&mut self) -> Result<(), BitBoxError> {
+        let functions = self.program.functions.clone();
+        for func in functions.iter() {
+            let mut func_type = FunctionType::default();
+            for var in func.params.iter() {
+                let data_type = data_type_of(&var.ty, &var.name)?;
+                let value_type = ValueType::WithName(var.name.lexeme.to_string(), data_type);
+                func_type = func_type.with_param(value_type);
+            }
+            if let Ok(return_type) = func.return_type.to_data_type() {
+                func_type = func_type.with_result(return_type);
+            }
+
+            self.module.declare_function(&func.name, func_type);
+
+            if let ssa::Visibility::Public = func.visibility {
+                let Some(idx) = self.module.get_function_id(&func.name) else {
+                    unreachable!("function {} was just declared", func.name);
+                };
+                self.module
+                    .export(ExportEntry::new(&func.name, ExportType::Func, idx));
+            }
+        }
+
+        for func in functions.iter() {
+            let block = self.compile_basic_block(func)?;
+            self.module.add_code(block);
+        }
+        Ok(())
+    }
+
+    pub fn compile_import_in_module(&mut self) -> Result<(), BitBoxError> {
+        for import in self.program.imports.iter() {
+            match import {
+                ssa::Import::Function(spec) => {
+                    let ssa::FunctionSpec {
+                        module_name,
+                        name,
+                        params,
+                        return_type,
+                    } = spec;
+                    let mut func = FunctionType::default();
+                    for param in params.iter() {
+                        func = func.with_param(ValueType::Data(data_type_of(param, name)?));
+                    }
+                    if let Ok(return_type) = return_type.to_data_type() {
+                        func = func.with_result(return_type);
+                    }
+                    self.module.import(&module_name.lexeme, &name.lexeme, func);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn compile_constant_in_module(&mut self) -> Result<(), BitBoxError> {
+        for constant in self.program.constants.iter() {
+            let ssa::Constant {
+                name, ty, value, ..
+            } = constant;
+            match value {
+                ssa::ConstantValue::String(tok) => {
+                    let bytes = literal::string_bytes(tok)?;
+                    self.module.add_string(&name.lexeme, &bytes);
+                }
+                ssa::ConstantValue::Number(tok) => {
+                    let Ok(data_type) = ty.to_data_type() else {
+                        return Err(BitBoxError::InvalidType {
+                            expected: "a number type".to_string(),
+                            actual_type: ty.to_string(),
+                            actual: name.clone(),
+                            definition: None,
+                        });
+                    };
+                    let value = literal::typed_value(tok, ty)?;
+                    let entry = match (data_type, value) {
+                        (DataType::I32, _) => {
+                            GlobalEntry::new_i32(&name.lexeme, false, value.bits() as i32)
+                        }
+                        (DataType::I64, _) => {
+                            GlobalEntry::new_i64(&name.lexeme, false, value.bits() as i64)
+                        }
+                        (DataType::F32, Value::F32(value)) => {
+                            GlobalEntry::new_f32(&name.lexeme, false, value)
+                        }
+                        (DataType::F64, Value::F64(value)) => {
+                            GlobalEntry::new_f64(&name.lexeme, false, value)
+                        }
+                        (DataType::F32 | DataType::F64, _) => {
+                            return Err(float_mismatch(ty, tok));
+                        }
+                    };
+                    self.module.add_global(entry);
+                }
+                ssa::ConstantValue::Directive(_) => {
+                    unreachable!("directives are evaluated by ssa::const_eval before emitting")
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn emit(mut self) -> Result<Module, BitBoxError> {
+        self.compile_import_in_module()?;
+        self.compile_constant_in_module()?;
+
+        let pages = self.module.data_size().div_ceil(PAGE_SIZE).max(1);
+        self.module.add_memory(Page::WithNoMinimun(pages));
+        self.module
+            .export(ExportEntry::new("memory", ExportType::Memory, 0));
+
+        self.compile_function_in_module()?;
+
+        Ok(self.module)
+    }
+}
+
+/// Stores the value on top of the stack in the local of `variable`
+fn set_local(
+    wasm_block: &mut Block,
+    variable: &ssa::Variable,
+    params: &[ssa::Variable],
+) -> Result<(), BitBoxError> {
+    let Some(index) = wasm_block.get_local_index(&variable.name.lexeme, params.len()) else {
+        return Err(BitBoxError::UndefinedVariable(variable.name.clone()));
+    };
+    wasm_block.push(Instruction::LocalSet(index as u32));
+    Ok(())
+}
+
+/// Instructions that wrap the integer on top of the stack to the width of `ty`. Wasm only
+/// computes in 32 and 64 bits, so narrower values are kept zero extended when they're unsigned
+/// and sign extended when they're signed, as the interpreter keeps them.
+fn wrap(ty: &ssa::Type) -> Vec<Instruction> {
+    let (bits, signed) = match ty {
+        ssa::Type::Unsigned(bits) => (*bits as u32, false),
+        ssa::Type::Signed(bits) => (*bits as u32, true),
+        _ => return vec![],
+    };
+    match (bits, signed) {
+        (0 | 32 | 64.., _) => vec![],
+        (8, true) => vec![Instruction::I32Extend8S],
+        (16, true) => vec![Instruction::I32Extend16S],
+        (..32, true) => vec![
+            Instruction::I32Const(32 - bits as i32),
+            Instruction::I32Shl,
+            Instruction::I32Const(32 - bits as i32),
+            Instruction::I32ShrS,
+        ],
+        (..32, false) => vec![
+            Instruction::I32Const(((1u32 << bits) - 1) as i32),
+            Instruction::I32And,
+        ],
+        (_, true) => vec![
+            Instruction::I64Const(64 - bits as i64),
+            Instruction::I64Shl,
+            Instruction::I64Const(64 - bits as i64),
+            Instruction::I64ShrS,
+        ],
+        (_, false) => vec![
+            Instruction::I64Const(((1u64 << bits) - 1) as i64),
+            Instruction::I64And,
+        ],
+    }
+}
+
+/// The `@cmp` instruction for operands of `data_type`, floats have no unsigned comparisons
+fn comparison(data_type: DataType, condition: ssa::Condition, signed: bool) -> Instruction {
+    match (data_type, condition, signed) {
+        (DataType::I32, ssa::Condition::Eq, _) => Instruction::I32Eq,
+        (DataType::I32, ssa::Condition::Ne, _) => Instruction::I32Ne,
+        (DataType::I32, ssa::Condition::Lt, true) => Instruction::I32LtS,
+        (DataType::I32, ssa::Condition::Lt, false) => Instruction::I32LtU,
+        (DataType::I32, ssa::Condition::Le, true) => Instruction::I32LeS,
+        (DataType::I32, ssa::Condition::Le, false) => Instruction::I32LeU,
+        (DataType::I32, ssa::Condition::Gt, true) => Instruction::I32GtS,
+        (DataType::I32, ssa::Condition::Gt, false) => Instruction::I32GtU,
+        (DataType::I32, ssa::Condition::Ge, true) => Instruction::I32GeS,
+        (DataType::I32, ssa::Condition::Ge, false) => Instruction::I32GeU,
+        (DataType::I64, ssa::Condition::Eq, _) => Instruction::I64Eq,
+        (DataType::I64, ssa::Condition::Ne, _) => Instruction::I64Ne,
+        (DataType::I64, ssa::Condition::Lt, true) => Instruction::I64LtS,
+        (DataType::I64, ssa::Condition::Lt, false) => Instruction::I64LtU,
+        (DataType::I64, ssa::Condition::Le, true) => Instruction::I64LeS,
+        (DataType::I64, ssa::Condition::Le, false) => Instruction::I64LeU,
+        (DataType::I64, ssa::Condition::Gt, true) => Instruction::I64GtS,
+        (DataType::I64, ssa::Condition::Gt, false) => Instruction::I64GtU,
+        (DataType::I64, ssa::Condition::Ge, true) => Instruction::I64GeS,
+        (DataType::I64, ssa::Condition::Ge, false) => Instruction::I64GeU,
+        (DataType::F32, ssa::Condition::Eq, _) => Instruction::F32Eq,
+        (DataType::F32, ssa::Condition::Ne, _) => Instruction::F32Ne,
+        (DataType::F32, ssa::Condition::Lt, _) => Instruction::F32Lt,
+        (DataType::F32, ssa::Condition::Le, _) => Instruction::F32Le,
+        (DataType::F32, ssa::Condition::Gt, _) => Instruction::F32Gt,
+        (DataType::F32, ssa::Condition::Ge, _) => Instruction::F32Ge,
+        (DataType::F64, ssa::Condition::Eq, _) => Instruction::F64Eq,
+        (DataType::F64, ssa::Condition::Ne, _) => Instruction::F64Ne,
+        (DataType::F64, ssa::Condition::Lt, _) => Instruction::F64Lt,
+        (DataType::F64, ssa::Condition::Le, _) => Instruction::F64Le,
+        (DataType::F64, ssa::Condition::Gt, _) => Instruction::F64Gt,
+        (DataType::F64, ssa::Condition::Ge, _) => Instruction::F64Ge,
+    }
+}
+
+/// Type of the parameter or value called `name` in `function`
+fn type_of<'a>(function: &'a ssa::Function, name: &Token) -> Option<&'a ssa::Type> {
+    let mut variables = function.params.iter().chain(
+        function
+            .blocks
+            .iter()
+            .flat_map(|block| block.instructions.iter())
+            .filter_map(|instruction| instruction.destination()),
+    );
+    variables
+        .find(|variable| variable.name.lexeme == name.lexeme)
+        .map(|variable| &variable.ty)
+}
+
+/// Maps a value's type to its wasm type, `token` is what the error points at
+fn data_type_of(ty: &ssa::Type, token: &Token) -> Result<DataType, BitBoxError> {
+    ty.to_data_type().map_err(|_| BitBoxError::InvalidType {
+        expected: "a value type".to_string(),
+        actual_type: ty.to_string(),
+        actual: token.clone(),
+        definition: None,
+    })
+}
+
+/// A number of type `ty` whose value isn't a float of the width wasm gives `ty`
+fn float_mismatch(ty: &ssa::Type, token: &Token) -> BitBoxError {
+    BitBoxError::InvalidType {
+        expected: "f32 or f64".to_string(),
+        actual_type: ty.to_string(),
+        actual: token.clone(),
+        definition: None,
+    }
+}
@@ -0,0 +1,31 @@
+mod emitter;
+mod module;
+mod opcode;
+mod section;
+#[cfg(test)]
+mod test;
+
+use crate::ssa;
+use anyhow::{bail, Result};
+use section::DataType;
+
+pub use emitter::Emitter;
+
+pub trait ToDataType {
+    fn to_data_type(&self) -> Result<DataType>;
+}
+
+impl ToDataType for ssa::Type {
+    fn to_data_type(&self) -> Result<DataType> {
+        match self {
+            ssa::Type::Unsigned(size) | ssa::Type::Signed(size) if *size > 32 => Ok(DataType::I64),
+            ssa::Type::Unsigned(_) | ssa::Type::Signed(_) => Ok(DataType::I32),
+            ssa::Type::Float(32) => Ok(DataType::F32),
+            ssa::Type::Float(64) => Ok(DataType::F64),
+            ssa::Type::Float(_) => bail!("floats are f32 or f64"),
+            // Pointers are offsets into linear memory
+            ssa::Type::Pointer(_) | ssa::Type::Array(..) => Ok(DataType::I32),
+            ssa::Type::Void => bail!("void has no wasm data type"),
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use super::opcode::Instruction;
+use super::section::{
+    _type::{FunctionType, Type},
+    code::{Block, Code},
+    data::{Data, Segment},
+    export::{Export, ExportEntry},
+    function::Function,
+    global::{Global, GlobalEntry},
+    header::Header,
+    import::{Import, ImportEntry, ImportType},
+    memory::{Memory, Page},
+    Section,
+};
+use anyhow::Result;
+use std::collections::HashMap;
+
+const DATA_SEGMENT: &str = ".data";
+/// Where the data segment starts in linear memory
+const DATA_OFFSET: u32 = 0;
+
+/// Location of a constant in linear memory
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DataPointer {
+    pub offset: u32,
+    pub len: u32,
+}
+
+#[derive(Debug, Default)]
+pub struct Module {
+    header: Header,
+    type_section: Type,
+    import_section: Import,
+    function_section: Function,
+    memory_section: Memory,
+    global_section: Global,
+    export_section: Export,
+    code_section: Code,
+    data_section: Data,
+    data_pointers: HashMap<String, DataPointer>,
+}
+
+impl Module {
+    /// Imported functions share the index space with module functions so they must be imported
+    /// before any function is added.
+    pub fn import(&mut self, module_name: &str, name: &str, function_type: FunctionType) {
+        self.type_section.push(function_type);
+        self.import_section
+            .push(ImportEntry::new(module_name, name, ImportType::Func));
+        self.function_section.add_imported_function(name);
+    }
+
+    /// Functions are declared before any body is compiled so calls can reference functions
+    /// defined later in the program.
+    pub fn declare_function(&mut self, name: &str, function_type: FunctionType) {
+        self.type_section.push(function_type);
+        self.function_section.add_function(name);
+    }
+
+    /// Bodies must be added in the same order their functions were declared.
+    pub fn add_code(&mut self, block: Block) {
+        self.code_section.push(block);
+    }
+
+    pub fn get_function_id(&self, name: &str) -> Option<u32> {
+        self.function_section.get_id(name)
+    }
+
+    pub fn export(&mut self, entry: ExportEntry) {
+        self.export_section.push(entry);
+    }
+
+    pub fn add_memory(&mut self, page: Page) {
+        self.memory_section.push(page);
+    }
+
+    pub fn add_global(&mut self, entry: GlobalEntry) {
+        self.global_section.push(entry);
+    }
+
+    pub fn get_global(&self, name: &str) -> Option<(usize, &GlobalEntry)> {
+        self.global_section.get(name)
+    }
+
+    pub fn get_global_index(&self, name: &str) -> Option<usize> {
+        self.get_global(name).map(|(index, _)| index)
+    }
+
+    /// Places `value` at the end of the active data segment returning where it was placed.
+    /// Strings are laid out in the order they are added so offsets are stable between builds.
+    pub fn add_string(&mut self, name: &str, value: &[u8]) -> DataPointer {
+        if self.data_section.data.is_empty() {
+            let segment = Segment::default()
+                .with_name(DATA_SEGMENT)
+                .with_instruction(Instruction::I32Const(DATA_OFFSET as i32));
+            self.data_section.push(segment);
+        }
+        let segment = &mut self.data_section.data[0];
+        let pointer = DataPointer {
+            offset: DATA_OFFSET + segment.push_data(value.to_vec()),
+            len: value.len() as u32,
+        };
+        self.data_pointers.insert(name.to_string(), pointer);
+        pointer
+    }
+
+    pub fn get_data_pointer(&self, name: &str) -> Option<DataPointer> {
+        self.data_pointers.get(name).copied()
+    }
+
+    /// Number of bytes the data section needs in linear memory
+    pub fn data_size(&self) -> u32 {
+        DATA_OFFSET + self.data_section.len() as u32
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let sections: Vec<Section> = vec![
+            self.type_section.clone().into(),
+            self.import_section.clone().into(),
+            self.function_section.clone().into(),
+            self.memory_section.clone().into(),
+            Section::Global(self.global_section.clone()),
+            self.export_section.clone().into(),
+            self.code_section.clone().into(),
+            self.data_section.clone().into(),
+        ];
+
+        let mut bytes = self.header.to_bytes()?;
+        for section in sections.iter() {
+            bytes.extend(section.to_bytes()?);
+        }
+        Ok(bytes)
+    }
+}
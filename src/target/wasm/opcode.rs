@@ -0,0 +1,375 @@
+#![allow(unused)]
+use super::section::DataType;
+use anyhow::Result;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Instruction {
+    /// 0x1a is the opcode to pop the value from the stack
+    Drop,
+    /// 0x10 is the opcode to call a function
+    Call(u32),
+    // Numeric instructions
+    /// 0x41 is the opcode for i32.const
+    I32Const(i32),
+    /// 0x42 is the opcode for i64.const
+    I64Const(i64),
+    F32Const(f32),
+    F64Const(f64),
+
+    // Local
+    LocalGet(u32),
+    LocalSet(u32),
+    // LocalTee(u32),
+    GlobalGet(u32),
+    GlobalSet(u32),
+
+    // Arithmetic instructions
+    /// 0x6a is the opcode for i32.add
+    I32Add,
+    /// 0x6b is the opcode for i32.sub
+    I32Sub,
+    /// 0x6c is the opcode for i32.mul
+    I32Mul,
+    /// 0x6d is the opcode for i32.div_s
+    I32DivS,
+    /// 0x6e is the opcode for i32.div_u
+    I32DivU,
+    /// 0x6f is the opcode for i32.rem_s
+    I32RemS,
+    /// 0x70 is the opcode for i32.rem_u
+    I32RemU,
+    /// 0x7c is the opcode for i64.add
+    I64Add,
+    /// 0x7d is the opcode for i64.sub
+    I64Sub,
+    /// 0x7e is the opcode for i64.mul
+    I64Mul,
+    /// 0x92 is the opcode for f32.add
+    F32Add,
+    /// 0x93 is the opcode for f32.sub
+    F32Sub,
+    /// 0x94 is the opcode for f32.mul
+    F32Mul,
+    /// 0xa0 is the opcode for f64.add
+    F64Add,
+    /// 0xa1 is the opcode for f64.sub
+    F64Sub,
+    /// 0xa2 is the opcode for f64.mul
+    F64Mul,
+
+    // Bitwise instructions
+    /// 0x71 is the opcode for i32.and
+    I32And,
+    /// 0x74 is the opcode for i32.shl
+    I32Shl,
+    /// 0x75 is the opcode for i32.shr_s
+    I32ShrS,
+    /// 0x83 is the opcode for i64.and
+    I64And,
+    /// 0x86 is the opcode for i64.shl
+    I64Shl,
+    /// 0x87 is the opcode for i64.shr_s
+    I64ShrS,
+
+    // Comparison instructions
+    /// 0x45 is the opcode for i32.eqz
+    I32Eqz,
+    /// 0x46 is the opcode for i32.eq
+    I32Eq,
+    /// 0x47 is the opcode for i32.ne
+    I32Ne,
+    /// 0x48 is the opcode for i32.lt_s
+    I32LtS,
+    /// 0x49 is the opcode for i32.lt_u
+    I32LtU,
+    /// 0x4a is the opcode for i32.gt_s
+    I32GtS,
+    /// 0x4b is the opcode for i32.gt_u
+    I32GtU,
+    /// 0x4c is the opcode for i32.le_s
+    I32LeS,
+    /// 0x4d is the opcode for i32.le_u
+    I32LeU,
+    /// 0x4e is the opcode for i32.ge_s
+    I32GeS,
+    /// 0x4f is the opcode for i32.ge_u
+    I32GeU,
+    /// 0x50 is the opcode for i64.eqz
+    I64Eqz,
+    /// 0x51 is the opcode for i64.eq
+    I64Eq,
+    /// 0x52 is the opcode for i64.ne
+    I64Ne,
+    /// 0x53 is the opcode for i64.lt_s
+    I64LtS,
+    /// 0x54 is the opcode for i64.lt_u
+    I64LtU,
+    /// 0x55 is the opcode for i64.gt_s
+    I64GtS,
+    /// 0x56 is the opcode for i64.gt_u
+    I64GtU,
+    /// 0x57 is the opcode for i64.le_s
+    I64LeS,
+    /// 0x58 is the opcode for i64.le_u
+    I64LeU,
+    /// 0x59 is the opcode for i64.ge_s
+    I64GeS,
+    /// 0x5a is the opcode for i64.ge_u
+    I64GeU,
+    /// 0x5b is the opcode for f32.eq
+    F32Eq,
+    /// 0x5c is the opcode for f32.ne
+    F32Ne,
+    /// 0x5d is the opcode for f32.lt
+    F32Lt,
+    /// 0x5e is the opcode for f32.gt
+    F32Gt,
+    /// 0x5f is the opcode for f32.le
+    F32Le,
+    /// 0x60 is the opcode for f32.ge
+    F32Ge,
+    /// 0x61 is the opcode for f64.eq
+    F64Eq,
+    /// 0x62 is the opcode for f64.ne
+    F64Ne,
+    /// 0x63 is the opcode for f64.lt
+    F64Lt,
+    /// 0x64 is the opcode for f64.gt
+    F64Gt,
+    /// 0x65 is the opcode for f64.le
+    F64Le,
+    /// 0x66 is the opcode for f64.ge
+    F64Ge,
+
+    // Conversion instructions
+    /// 0xad is the opcode for i64.extend_i32_u
+    I64ExtendI32U,
+    /// 0xb3 is the opcode for f32.convert_i32_u
+    F32ConvertI32U,
+    /// 0xb8 is the opcode for f64.convert_i32_u
+    F64ConvertI32U,
+    /// 0xc0 is the opcode for i32.extend8_s
+    I32Extend8S,
+    /// 0xc1 is the opcode for i32.extend16_s
+    I32Extend16S,
+
+    // Control instructions
+    /// 0x02 is the opcode for block, always with the empty block type
+    Block,
+    /// 0x03 is the opcode for loop, always with the empty block type
+    Loop,
+    /// 0x04 is the opcode for if followed by the `[DataType]`
+    If(DataType),
+    /// 0x05 is the opcode for else
+    Else,
+    /// 0x0b is the opcode for end
+    End,
+    Br(u32),
+    BrIf(u32),
+    BrTable(Vec<u32>, u32),
+    Return,
+
+    // Memory instructions
+    I32Load,
+    I32Store,
+    // more instructions...
+
+    // Other instructions
+    Nop,
+    Unreachable,
+    // more instructions...
+}
+
+impl Instruction {
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        match self {
+            Self::Drop => Ok(vec![0x1a]), // 0x1a is the opcode for drop
+            Self::Call(index) => {
+                let mut bytes = vec![0x10]; // 0x10 is the opcode for call
+                leb128::write::unsigned(&mut bytes, *index as u64)?;
+                Ok(bytes)
+            }
+            Self::LocalGet(index) => {
+                let mut bytes = vec![0x20]; // 0x20 is the opcode for local.get
+                leb128::write::unsigned(&mut bytes, *index as u64)?;
+                Ok(bytes)
+            }
+            Self::LocalSet(index) => {
+                let mut bytes = vec![0x21]; // 0x21 is the opcode for local.set
+                leb128::write::unsigned(&mut bytes, *index as u64)?;
+                Ok(bytes)
+            }
+            Self::GlobalGet(index) => {
+                let mut bytes = vec![0x23]; // 0x23 is the opcode for global.get
+                leb128::write::unsigned(&mut bytes, *index as u64)?;
+                Ok(bytes)
+            }
+            Self::GlobalSet(index) => {
+                let mut bytes = vec![0x24]; // 0x24 is the opcode for global.set
+                leb128::write::unsigned(&mut bytes, *index as u64)?;
+                Ok(bytes)
+            }
+            // Numeric instructions
+            Self::I32Const(value) => {
+                let mut bytes = vec![0x41]; // 0x41 is the opcode for i32.const
+                leb128::write::signed(&mut bytes, *value as i64)?;
+                Ok(bytes)
+            }
+            Self::I64Const(value) => {
+                let mut bytes = vec![0x42]; // 0x42 is the opcode for i64.const
+                leb128::write::signed(&mut bytes, *value)?;
+                Ok(bytes)
+            }
+            Self::F32Const(value) => {
+                let mut bytes = vec![0x43]; // 0x43 is the opcode for f32.const
+                bytes.extend(value.to_le_bytes());
+                Ok(bytes)
+            }
+            Self::F64Const(value) => {
+                let mut bytes = vec![0x44]; // 0x44 is the opcode for f64.const
+                bytes.extend(value.to_le_bytes());
+                Ok(bytes)
+            }
+
+            // Arithmetic instructions (all are single-byte opcodes)
+            Self::I32Add => Ok(vec![0x6a]),
+            Self::I32Sub => Ok(vec![0x6b]),
+            Self::I32Mul => Ok(vec![0x6c]),
+            Self::I32DivS => Ok(vec![0x6d]),
+            Self::I32DivU => Ok(vec![0x6e]),
+            Self::I32RemS => Ok(vec![0x6f]),
+            Self::I32RemU => Ok(vec![0x70]),
+            Self::I64Add => Ok(vec![0x7c]),
+            Self::I64Sub => Ok(vec![0x7d]),
+            Self::I64Mul => Ok(vec![0x7e]),
+            Self::F32Add => Ok(vec![0x92]),
+            Self::F32Sub => Ok(vec![0x93]),
+            Self::F32Mul => Ok(vec![0x94]),
+            Self::F64Add => Ok(vec![0xa0]),
+            Self::F64Sub => Ok(vec![0xa1]),
+            Self::F64Mul => Ok(vec![0xa2]),
+
+            // Bitwise instructions (all are single-byte opcodes)
+            Self::I32And => Ok(vec![0x71]),
+            Self::I32Shl => Ok(vec![0x74]),
+            Self::I32ShrS => Ok(vec![0x75]),
+            Self::I64And => Ok(vec![0x83]),
+            Self::I64Shl => Ok(vec![0x86]),
+            Self::I64ShrS => Ok(vec![0x87]),
+
+            // Comparison instructions (all are single-byte opcodes)
+            Self::I32Eqz => Ok(vec![0x45]),
+            Self::I32Eq => Ok(vec![0x46]),
+            Self::I32Ne => Ok(vec![0x47]),
+            Self::I32LtS => Ok(vec![0x48]),
+            Self::I32LtU => Ok(vec![0x49]),
+            Self::I32GtS => Ok(vec![0x4a]),
+            Self::I32GtU => Ok(vec![0x4b]),
+            Self::I32LeS => Ok(vec![0x4c]),
+            Self::I32LeU => Ok(vec![0x4d]),
+            Self::I32GeS => Ok(vec![0x4e]),
+            Self::I32GeU => Ok(vec![0x4f]),
+            Self::I64Eqz => Ok(vec![0x50]),
+            Self::I64Eq => Ok(vec![0x51]),
+            Self::I64Ne => Ok(vec![0x52]),
+            Self::I64LtS => Ok(vec![0x53]),
+            Self::I64LtU => Ok(vec![0x54]),
+            Self::I64GtS => Ok(vec![0x55]),
+            Self::I64GtU => Ok(vec![0x56]),
+            Self::I64LeS => Ok(vec![0x57]),
+            Self::I64LeU => Ok(vec![0x58]),
+            Self::I64GeS => Ok(vec![0x59]),
+            Self::I64GeU => Ok(vec![0x5a]),
+            Self::F32Eq => Ok(vec![0x5b]),
+            Self::F32Ne => Ok(vec![0x5c]),
+            Self::F32Lt => Ok(vec![0x5d]),
+            Self::F32Gt => Ok(vec![0x5e]),
+            Self::F32Le => Ok(vec![0x5f]),
+            Self::F32Ge => Ok(vec![0x60]),
+            Self::F64Eq => Ok(vec![0x61]),
+            Self::F64Ne => Ok(vec![0x62]),
+            Self::F64Lt => Ok(vec![0x63]),
+            Self::F64Gt => Ok(vec![0x64]),
+            Self::F64Le => Ok(vec![0x65]),
+            Self::F64Ge => Ok(vec![0x66]),
+
+            // Conversion instructions (all are single-byte opcodes)
+            Self::I64ExtendI32U => Ok(vec![0xad]),
+            Self::F32ConvertI32U => Ok(vec![0xb3]),
+            Self::F64ConvertI32U => Ok(vec![0xb8]),
+            Self::I32Extend8S => Ok(vec![0xc0]),
+            Self::I32Extend16S => Ok(vec![0xc1]),
+
+            // Control instructions
+            Self::Block => Ok(vec![0x02, 0x40]), // 0x40 is the empty block type
+            Self::Loop => Ok(vec![0x03, 0x40]),
+            Self::If(data_type) => Ok(vec![0x04]),
+            Self::Else => Ok(vec![0x05]),
+            Self::End => Ok(vec![0x0b]),
+            Self::Br(label_idx) => {
+                let mut bytes = vec![0x0c]; // 0x0c is the opcode for br
+                leb128::write::unsigned(&mut bytes, *label_idx as u64)?;
+                Ok(bytes)
+            }
+            Self::BrIf(label_idx) => {
+                let mut bytes = vec![0x0d]; // 0x0d is the opcode for br_if
+                leb128::write::unsigned(&mut bytes, *label_idx as u64)?;
+                Ok(bytes)
+            }
+            Self::BrTable(table, default) => {
+                let mut bytes = vec![0x0e]; // 0x0e is the opcode for br_table
+                leb128::write::unsigned(&mut bytes, table.len() as u64)?;
+                for &label in table {
+                    leb128::write::unsigned(&mut bytes, label as u64)?;
+                }
+                leb128::write::unsigned(&mut bytes, *default as u64)?;
+                Ok(bytes)
+            }
+            Self::Return => Ok(vec![0x0f]), // 0x0f is the opcode for return
+
+            // Memory instructions
+            Self::I32Load => Ok(vec![0x28]), // 0x28 is the opcode for i32.load
+            // HACK: 0x02 is the allignment
+            // HACK: 0x00 is the offset
+            Self::I32Store => Ok(vec![0x36, 0x02, 0x00]), // 0x36 is the opcode for i32.store
+
+            // Other instructions
+            Self::Nop => Ok(vec![0x01]), // 0x01 is the opcode for nop
+            Self::Unreachable => Ok(vec![0x00]), // 0x00 is the opcode for unreachable
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.to_bytes().unwrap_or_default().len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_to_bytes() -> Result<()> {
+        assert_eq!(Instruction::I32Add.to_bytes()?, vec![0x6A]);
+        assert_eq!(Instruction::I32Const(42).to_bytes()?, vec![0x41, 0x2A]);
+        assert_eq!(Instruction::I32Const(-1).to_bytes()?, vec![0x41, 0x7F]);
+        assert_eq!(
+            Instruction::I32Const(64).to_bytes()?,
+            vec![0x41, 0xC0, 0x00]
+        );
+        assert_eq!(Instruction::I32GtS.to_bytes()?, vec![0x4A]);
+        assert_eq!(Instruction::I32LtU.to_bytes()?, vec![0x49]);
+        assert_eq!(Instruction::I64Add.to_bytes()?, vec![0x7C]);
+        assert_eq!(Instruction::I64Const(-1).to_bytes()?, vec![0x42, 0x7F]);
+        assert_eq!(Instruction::I32And.to_bytes()?, vec![0x71]);
+        assert_eq!(Instruction::I64ShrS.to_bytes()?, vec![0x87]);
+        assert_eq!(Instruction::I32Extend8S.to_bytes()?, vec![0xC0]);
+        assert_eq!(
+            Instruction::F64Const(1.5).to_bytes()?,
+            vec![0x44, 0, 0, 0, 0, 0, 0, 0xF8, 0x3F]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,169 @@
+use super::DataType;
+use super::Instruction;
+use anyhow::Result;
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Code {
+    blocks: Vec<Block>,
+}
+
+impl Code {
+    const ID: u8 = 0x0A;
+
+    pub fn push(&mut self, block: Block) {
+        self.blocks.push(block);
+    }
+
+    pub fn with(mut self, block: Block) -> Self {
+        self.blocks.push(block);
+        self
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut block_bytes = Vec::new();
+        for block in &self.blocks {
+            block_bytes.extend(block.to_bytes()?);
+        }
+
+        let mut bytes = Vec::new();
+        bytes.push(Code::ID);
+        let mut count = Vec::new();
+        leb128::write::unsigned(&mut count, self.blocks.len() as u64)?;
+        let length = count.len() + block_bytes.len();
+        leb128::write::unsigned(&mut bytes, length as u64)?;
+        bytes.extend(count);
+        bytes.extend(block_bytes);
+        Ok(bytes)
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct LocalVariable {
+    pub name: String,
+    pub ty: DataType,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Block {
+    instructions: Vec<Instruction>,
+    locals: Vec<LocalVariable>,
+}
+
+impl Block {
+    pub fn new(instructions: Vec<Instruction>, locals: Vec<LocalVariable>) -> Self {
+        Self {
+            instructions,
+            locals,
+        }
+    }
+
+    pub fn push(&mut self, instruction: Instruction) {
+        self.instructions.push(instruction);
+    }
+
+    pub fn push_local(&mut self, name: impl Into<String>, ty: DataType) {
+        let name = name.into();
+        self.locals.push(LocalVariable { name, ty });
+    }
+
+    pub fn get_local(&self, name: impl Into<String>) -> Option<&LocalVariable> {
+        let name = name.into();
+        self.locals
+            .iter()
+            .find(|var| var.name.as_str() == name.as_str())
+    }
+
+    pub fn get_local_index(&self, name: &str, offset: usize) -> Option<usize> {
+        self.locals
+            .iter()
+            .position(|var| var.name.as_str() == name)
+            .map(|i| i + offset)
+    }
+
+    pub fn with(mut self, instruction: Instruction) -> Self {
+        self.instructions.push(instruction);
+        self
+    }
+
+    /// Locals are declared as runs of the same type so the declaration order must follow the
+    /// order the locals were pushed in, otherwise `get_local_index` would not line up.
+    fn local_groups(&self) -> Vec<(usize, DataType)> {
+        let mut groups: Vec<(usize, DataType)> = Vec::new();
+        for var in self.locals.iter() {
+            match groups.last_mut() {
+                Some((count, ty)) if *ty == var.ty => *count += 1,
+                _ => groups.push((1, var.ty)),
+            }
+        }
+        groups
+    }
+
+    fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut body = Vec::new();
+        let groups = self.local_groups();
+        leb128::write::unsigned(&mut body, groups.len() as u64)?;
+        for (count, ty) in groups.iter() {
+            leb128::write::unsigned(&mut body, *count as u64)?;
+            body.push(*ty as u8);
+        }
+        for instruction in &self.instructions {
+            body.extend(instruction.to_bytes()?);
+        }
+        body.push(0x0B);
+
+        let mut bytes = Vec::new();
+        leb128::write::unsigned(&mut bytes, body.len() as u64)?;
+        bytes.extend(body);
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_code_block() {
+        let block = Block::default()
+            .with(Instruction::I32Const(1))
+            .with(Instruction::I32Const(2))
+            .with(Instruction::I32Add);
+        eprintln!("{:?}", block);
+        let bytes = match block.to_bytes() {
+            Ok(bytes) => bytes,
+            Err(err) => panic!("ERROR: {}", err),
+        };
+        assert_eq!(bytes, vec![0x07, 0x00, 0x41, 0x01, 0x41, 0x02, 0x6A, 0x0B]);
+    }
+
+    #[test]
+    fn test_code_section() {
+        let code_section = Code::default().with(
+            Block::default()
+                .with(Instruction::I32Const(1))
+                .with(Instruction::I32Const(2))
+                .with(Instruction::I32Add),
+        );
+
+        let bytes = code_section.to_bytes().unwrap();
+        assert_eq!(
+            bytes,
+            vec![0x0A, 0x09, 0x01, 0x07, 0x00, 0x41, 0x01, 0x41, 0x02, 0x6A, 0x0B]
+        );
+    }
+
+    #[test]
+    fn test_code_block_locals_keep_order() {
+        let mut block = Block::default();
+        block.push_local("a", DataType::I32);
+        block.push_local("b", DataType::I64);
+        block.push_local("c", DataType::I32);
+        assert_eq!(block.get_local_index("c", 0), Some(2));
+        let bytes = block.to_bytes().unwrap();
+        assert_eq!(
+            bytes,
+            vec![0x08, 0x03, 0x01, 0x7F, 0x01, 0x7E, 0x01, 0x7F, 0x0B]
+        );
+    }
+}
@@ -0,0 +1,87 @@
+use anyhow::Result;
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Function {
+    imported_functions: Vec<String>,
+    functions: Vec<String>,
+}
+
+impl Function {
+    const ID: u8 = 0x03;
+
+    pub fn add_imported_function(&mut self, name: impl Into<String>) {
+        self.imported_functions.push(name.into());
+    }
+
+    pub fn with_function(mut self, name: impl Into<String>) -> Self {
+        self.functions.push(name.into());
+        self
+    }
+
+    pub fn add_function(&mut self, name: impl Into<String>) {
+        self.functions.push(name.into());
+    }
+
+    pub fn get_id(&self, name: impl Into<String>) -> Option<u32> {
+        let name = name.into();
+        let import_id = self
+            .imported_functions
+            .iter()
+            .position(|function| function == &name)
+            .map(|id| id as u32);
+
+        if import_id.is_some() {
+            return import_id;
+        }
+
+        let imports = self.imported_functions.len();
+        self.functions
+            .iter()
+            .position(|function| function == &name)
+            .map(|id| (id + imports) as u32)
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut body = Vec::new();
+        leb128::write::unsigned(&mut body, self.functions.len() as u64)?;
+
+        // Function `n` uses type `n` since a type is pushed for every import and function
+        let start = self.imported_functions.len() as u64;
+        let end = self.functions.len() as u64 + start;
+        for function_id in start..end {
+            leb128::write::unsigned(&mut body, function_id)?;
+        }
+
+        let mut bytes = vec![Function::ID];
+        leb128::write::unsigned(&mut bytes, body.len() as u64)?;
+        bytes.extend(body);
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_function() -> Result<()> {
+        let mut section = Function::default();
+        section.add_function("foo");
+        section.add_function("bar");
+        let bytes = section.to_bytes()?;
+        assert_eq!(bytes, vec![0x03, 0x03, 0x02, 0x00, 0x01]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_function_after_imports() -> Result<()> {
+        let mut section = Function::default();
+        section.add_imported_function("write");
+        section.add_function("main");
+        assert_eq!(section.get_id("write"), Some(0));
+        assert_eq!(section.get_id("main"), Some(1));
+        let bytes = section.to_bytes()?;
+        assert_eq!(bytes, vec![0x03, 0x02, 0x01, 0x01]);
+        Ok(())
+    }
+}
@@ -0,0 +1,173 @@
+use crate::interp::Interpreter;
+use crate::lexer::lex;
+use crate::parser;
+use crate::ssa::literal::Value;
+use crate::ssa::{self, Program};
+use crate::target::hex_dump;
+use crate::target::wasm::Emitter;
+use std::process::Command;
+
+/// Instantiates the module in argv[1] with stubs for the `core` imports, calls the export in
+/// argv[2] with the rest of the arguments and prints what it returns. Arguments ending in `n`
+/// are BigInts for i64 parameters.
+const RUNNER: &str = r#"
+const [path, name, ...args] = process.argv.slice(1);
+const module = new WebAssembly.Module(require("fs").readFileSync(path));
+const core = { write: () => 0, exit: () => { throw new Error("exit"); } };
+const { exports } = new WebAssembly.Instance(module, { core });
+const values = args.map((arg) => (arg.endsWith("n") ? BigInt(arg.slice(0, -1)) : Number(arg)));
+console.log(String(exports[name](...values)));
+"#;
+
+fn checked(input: &str) -> Program {
+    let tokens = lex(input);
+    let mut program = parser::Parser::new(tokens)
+        .parse()
+        .expect("failed to parse program");
+    ssa::analyse(&mut program).expect("failed to check program");
+    program
+}
+
+/// Runs `function` of the compiled program in node and returns what it printed, `None` when
+/// node isn't installed
+fn call(program: &Program, function: &str, arguments: &[Value]) -> Option<String> {
+    let module = Emitter::new(program.clone())
+        .emit()
+        .expect("failed to emit program");
+    let bytes = module.to_bytes().expect("failed to encode module");
+    let path = std::env::temp_dir().join(format!("bitbox-test-{}.wasm", std::process::id()));
+    std::fs::write(&path, bytes).expect("failed to write module");
+    let params = &program
+        .functions
+        .iter()
+        .find(|candidate| candidate.name == function)
+        .expect("the function is defined")
+        .params;
+    let arguments = arguments.iter().zip(params.iter()).map(|(value, param)| {
+        let wide =
+            matches!(param.ty, ssa::Type::Unsigned(bits) | ssa::Type::Signed(bits) if bits > 32);
+        let suffix = if wide { "n" } else { "" };
+        match value {
+            Value::Unsigned(value) => format!("{value}{suffix}"),
+            Value::Signed(value) => format!("{value}{suffix}"),
+            Value::F32(value) => value.to_string(),
+            Value::F64(value) => value.to_string(),
+        }
+    });
+    let output = Command::new("node")
+        .arg("-e")
+        .arg(RUNNER)
+        .arg(&path)
+        .arg(function)
+        .args(arguments)
+        .output();
+    std::fs::remove_file(&path).expect("failed to remove module");
+    let output = match output {
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return None,
+        output => output.expect("failed to run node"),
+    };
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let printed = String::from_utf8(output.stdout).expect("node prints text");
+    Some(printed.trim().to_string())
+}
+
+/// The module must return what the interpreter does. Wasm has no unsigned types, so a `u32`
+/// or `u64` comes back negative when its top bit is set, narrower values must come back as
+/// they are.
+fn assert_matches_interpreter(program: &Program, function: &str, arguments: &[Value]) {
+    let expected = Interpreter::new(program)
+        .call(function, arguments)
+        .expect("interpreter trapped")
+        .expect("function returns a value");
+    let Some(actual) = call(program, function, arguments) else {
+        eprintln!("node isn't installed, not running {function}");
+        return;
+    };
+    let expected = match expected {
+        Value::Unsigned(value) if value > i32::MAX as u128 && value <= u32::MAX as u128 => {
+            (value as u32 as i32).to_string()
+        }
+        Value::Unsigned(value) if value > i64::MAX as u128 => (value as u64 as i64).to_string(),
+        Value::Unsigned(value) => value.to_string(),
+        Value::Signed(value) => value.to_string(),
+        Value::F32(value) => value.to_string(),
+        Value::F64(value) => value.to_string(),
+    };
+    assert_eq!(actual, expected, "{function}({arguments:?})");
+}
+
+pub fn snapshot_emitting(input: &str) -> String {
+    let tokens = lex(input);
+    let mut program = parser::Parser::new(tokens)
+        .parse()
+        .expect("failed to parse program");
+    if let Err(err) = ssa::const_eval::evaluate(&mut program) {
+        return format!("{:#?}", err);
+    }
+    let module = match Emitter::new(program).emit() {
+        Ok(module) => module,
+        Err(err) => return format!("{:#?}", err),
+    };
+    let bytes = module.to_bytes().expect("failed to encode module");
+    hex_dump(&bytes)
+}
+
+macro_rules! snapshot {
+    ($name:tt, $path:tt) => {
+        #[test]
+        fn $name() {
+            let contents = include_str!($path);
+            let mut settings = insta::Settings::clone_current();
+            settings.set_snapshot_path("testdata/output/");
+            settings.bind(|| {
+                insta::assert_snapshot!(snapshot_emitting(contents));
+            });
+        }
+    };
+}
+
+snapshot!(binary, "../../../snapshots/basic.bitbox");
+snapshot!(import_function, "../../../snapshots/import_function.bitbox");
+snapshot!(string_constant, "../../../snapshots/string_constant.bitbox");
+snapshot!(calls, "../../../snapshots/calls.bitbox");
+snapshot!(mul, "../../../snapshots/mul.bitbox");
+snapshot!(cmp, "../../../snapshots/cmp.bitbox");
+snapshot!(wide_types, "../../../snapshots/wide_types.bitbox");
+snapshot!(jump, "../../../snapshots/jump.bitbox");
+snapshot!(jumpif, "../../../snapshots/jumpif.bitbox");
+snapshot!(loops, "../../../snapshots/loop.bitbox");
+snapshot!(phi, "../../../snapshots/phi.bitbox");
+snapshot!(interp, "../../../snapshots/interp.bitbox");
+snapshot!(narrow, "../../../snapshots/narrow.bitbox");
+
+#[test]
+fn narrow_integers_match_the_interpreter() {
+    let program = checked(include_str!("../../../snapshots/narrow.bitbox"));
+    let cases = [
+        ("wraps_below", vec![Value::Unsigned(100)]),
+        ("wraps_below", vec![Value::Unsigned(10)]),
+        ("wrap_s16", vec![Value::Signed(200)]),
+        ("wrap_u24", vec![Value::Unsigned(0)]),
+        ("wrap_s40", vec![Value::Signed(1)]),
+    ];
+    for (function, arguments) in cases {
+        assert_matches_interpreter(&program, function, &arguments);
+    }
+
+    let program = checked(include_str!("../../../snapshots/interp.bitbox"));
+    let cases = [
+        ("wrap_u8", vec![Value::Unsigned(100)]),
+        ("wrap_s8", vec![Value::Signed(100)]),
+        ("wrap_u64", vec![Value::Unsigned(0)]),
+        ("signed_less", vec![Value::Signed(-1), Value::Signed(1)]),
+        ("half", vec![Value::F64(5.0)]),
+        ("swap", vec![Value::Signed(3)]),
+    ];
+    for (function, arguments) in cases {
+        assert_matches_interpreter(&program, function, &arguments);
+    }
+}
@@ -0,0 +1,53 @@
+//! A static ELF64 executable for x86-64 Linux with one loadable segment and no sections.
+//! The segment maps the whole file, headers included, read and execute only, so the text
+//! and the data after it are at `BASE` plus their offset in the file.
+
+/// Virtual address the file is mapped at
+pub const BASE: u64 = 0x40_0000;
+const ELF_HEADER_SIZE: u16 = 64;
+const PROGRAM_HEADER_SIZE: u16 = 56;
+/// Offset of the first byte after the headers, where the text starts
+pub const HEADERS_SIZE: u64 = (ELF_HEADER_SIZE + PROGRAM_HEADER_SIZE) as u64;
+
+const ET_EXEC: u16 = 2;
+const EM_X86_64: u16 = 62;
+const PT_LOAD: u32 = 1;
+const PF_X: u32 = 1;
+const PF_R: u32 = 4;
+const PAGE_SIZE: u64 = 0x1000;
+
+/// The executable whose `contents` are loaded right after its headers and that starts at
+/// the virtual address `entry`
+pub fn executable(contents: &[u8], entry: u64) -> Vec<u8> {
+    let size = HEADERS_SIZE + contents.len() as u64;
+    let mut bytes = vec![];
+    // e_ident: magic, 64-bit, little endian, version 1, System V ABI, padding
+    bytes.extend(b"\x7fELF");
+    bytes.extend([2, 1, 1, 0]);
+    bytes.extend([0; 8]);
+    bytes.extend(ET_EXEC.to_le_bytes());
+    bytes.extend(EM_X86_64.to_le_bytes());
+    bytes.extend(1u32.to_le_bytes());
+    bytes.extend(entry.to_le_bytes());
+    // Program headers right after this one, no section headers
+    bytes.extend((ELF_HEADER_SIZE as u64).to_le_bytes());
+    bytes.extend(0u64.to_le_bytes());
+    bytes.extend(0u32.to_le_bytes());
+    bytes.extend(ELF_HEADER_SIZE.to_le_bytes());
+    bytes.extend(PROGRAM_HEADER_SIZE.to_le_bytes());
+    bytes.extend(1u16.to_le_bytes());
+    bytes.extend([0; 6]);
+
+    bytes.extend(PT_LOAD.to_le_bytes());
+    bytes.extend((PF_R | PF_X).to_le_bytes());
+    bytes.extend(0u64.to_le_bytes());
+    bytes.extend(BASE.to_le_bytes());
+    bytes.extend(BASE.to_le_bytes());
+    bytes.extend(size.to_le_bytes());
+    bytes.extend(size.to_le_bytes());
+    bytes.extend(PAGE_SIZE.to_le_bytes());
+
+    debug_assert_eq!(bytes.len() as u64, HEADERS_SIZE);
+    bytes.extend(contents);
+    bytes
+}
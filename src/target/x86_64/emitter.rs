@@ -0,0 +1,670 @@
+use super::elf;
+use super::instruction::{Condition, Instruction, Memory, Register, SymbolTable};
+use crate::error::BitBoxError;
+use crate::lexer::token::Token;
+use crate::ssa::{self, literal, BasicBlock, ConstantValue, Function, Import, Operand};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+/// Name of the target in errors
+const TARGET: &str = "x86_64";
+/// The executable's entry point, calls `main` if there is one and exits with its result
+const START: &str = "_start";
+/// Linux syscall numbers
+const SYS_WRITE: u64 = 1;
+const SYS_EXIT: u64 = 60;
+const STDOUT: u64 = 1;
+
+/// Lowers a checked program to x86-64 machine code in a static Linux executable.
+///
+/// Every parameter and value of a function has a stack slot below `rbp`, and instructions
+/// load their operands into `rax` and `rcx`, compute in `rax` and store it in the result's
+/// slot. Functions are called with the System V calling convention, so arguments are passed
+/// in `rdi`, `rsi`, `rdx`, `rcx`, `r8` and `r9` and then on the stack, and results are
+/// returned in `rax`. Values are kept sign or zero extended from their width to 64 bits.
+/// `core` imports are lowered to stubs that make the Linux syscall.
+#[derive(Debug)]
+pub struct Emitter {
+    program: ssa::Program,
+    /// Function `_start` calls instead of `main` and its arguments
+    entry: Option<(String, Vec<u64>)>,
+}
+
+/// One line of the text section
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Item {
+    Label(String),
+    Instruction(Instruction),
+    /// The IR instruction the instructions after it were selected for, only in the listing
+    Comment(String),
+}
+
+/// An emitted program, encoded as an ELF executable with `to_bytes` or printed as GNU
+/// assembler input
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Executable {
+    pub text: Vec<Item>,
+    /// Labelled string constants, placed after the text
+    pub data: Vec<(String, Vec<u8>)>,
+}
+
+impl Executable {
+    /// Address of every label once the executable is loaded
+    pub fn symbol_table(&self) -> SymbolTable {
+        let mut symbol_table = SymbolTable::new();
+        let mut address = elf::BASE + elf::HEADERS_SIZE;
+        for item in self.text.iter() {
+            match item {
+                Item::Label(name) => {
+                    symbol_table.insert(name.clone(), address);
+                }
+                Item::Instruction(instruction) => address += instruction.size() as u64,
+                Item::Comment(_) => {}
+            }
+        }
+        for (name, bytes) in self.data.iter() {
+            symbol_table.insert(name.clone(), address);
+            address += bytes.len() as u64;
+        }
+        symbol_table
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let symbol_table = self.symbol_table();
+        let mut contents = vec![];
+        for item in self.text.iter() {
+            if let Item::Instruction(instruction) = item {
+                let address = elf::BASE + elf::HEADERS_SIZE + contents.len() as u64;
+                contents.extend(instruction.to_bytes(address, &symbol_table));
+            }
+        }
+        for (_, bytes) in self.data.iter() {
+            contents.extend(bytes);
+        }
+        elf::executable(&contents, symbol_table[START])
+    }
+}
+
+/// A `.s` file in Intel syntax that assembles and links to the same program
+impl Display for Executable {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "    .intel_syntax noprefix")?;
+        writeln!(f, "    .text")?;
+        writeln!(f, "    .globl {}", START)?;
+        for item in self.text.iter() {
+            match item {
+                Item::Label(name) => writeln!(f, "{}:", name)?,
+                Item::Instruction(instruction) => writeln!(f, "    {}", instruction)?,
+                Item::Comment(comment) => writeln!(f, "    # {}", comment)?,
+            }
+        }
+        if !self.data.is_empty() {
+            writeln!(f, "    .section .rodata")?;
+        }
+        for (name, bytes) in self.data.iter() {
+            writeln!(f, "{}:", name)?;
+            for chunk in bytes.chunks(16) {
+                let bytes = chunk
+                    .iter()
+                    .map(|byte| format!("0x{:02x}", byte))
+                    .collect::<Vec<_>>();
+                writeln!(f, "    .byte {}", bytes.join(", "))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// How a constant is read: numbers are immediates and strings are addresses of their label
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Constant {
+    Number(u64),
+    String(String),
+}
+
+impl Emitter {
+    pub fn new(program: ssa::Program) -> Self {
+        Self {
+            program,
+            entry: None,
+        }
+    }
+
+    /// Makes `_start` call `function` with `arguments` and write its result to stdout as 8
+    /// little endian bytes before exiting with 0
+    #[cfg(test)]
+    pub fn with_entry(mut self, function: &str, arguments: Vec<u64>) -> Self {
+        self.entry = Some((function.to_string(), arguments));
+        self
+    }
+
+    pub fn emit(self) -> Result<Executable, BitBoxError> {
+        let mut data = vec![];
+        let mut constants = HashMap::new();
+        for constant in self.program.constants.iter() {
+            x86_type(&constant.ty, &constant.name)?;
+            let value = match &constant.value {
+                ConstantValue::String(token) => {
+                    let label = format!(".Lstr_{}", constant.name.lexeme);
+                    let bytes =
+                        literal::string_bytes(token).expect("strings are checked by the parser");
+                    data.push((label.clone(), bytes));
+                    Constant::String(label)
+                }
+                ConstantValue::Number(token) => Constant::Number(
+                    literal::typed_value(token, &constant.ty)
+                        .expect("constants are checked by ssa::typeck")
+                        .bits(),
+                ),
+                ConstantValue::Directive(_) => {
+                    unreachable!("directives are evaluated by ssa::const_eval before emitting")
+                }
+            };
+            constants.insert(constant.name.lexeme.as_str(), value);
+        }
+
+        let mut text = vec![Item::Label(START.to_string())];
+        text.extend(self.start().into_iter().map(Item::Instruction));
+        for function in self.program.functions.iter() {
+            let lowering = FunctionLowering::new(&self.program, &constants, function)?;
+            text.extend(lowering.lower()?);
+        }
+        for import in self.program.imports.iter() {
+            let Import::Function(spec) = import;
+            if let Some(stub) = stub(spec) {
+                text.push(Item::Label(stub_label(spec)));
+                text.extend(stub.into_iter().map(Item::Instruction));
+            }
+        }
+        Ok(Executable { text, data })
+    }
+
+    /// Calls `main`, or the entry given to `with_entry`, and exits
+    fn start(&self) -> Vec<Instruction> {
+        let exit = |status| {
+            [
+                status,
+                Instruction::MovImm(Register::Rax, SYS_EXIT),
+                Instruction::Syscall,
+            ]
+        };
+        let mut text = vec![];
+        match &self.entry {
+            Some((function, arguments)) => {
+                assert!(
+                    arguments.len() <= Register::ARGUMENTS.len(),
+                    "entries take their arguments in registers"
+                );
+                for (register, argument) in Register::ARGUMENTS.iter().zip(arguments) {
+                    text.push(Instruction::MovImm(*register, *argument));
+                }
+                text.extend([
+                    Instruction::Call(function.clone()),
+                    Instruction::Push(Register::Rax),
+                    Instruction::Mov(Register::Rsi, Register::Rsp),
+                    Instruction::MovImm(Register::Rdx, 8),
+                    Instruction::MovImm(Register::Rdi, STDOUT),
+                    Instruction::MovImm(Register::Rax, SYS_WRITE),
+                    Instruction::Syscall,
+                ]);
+                text.extend(exit(Instruction::MovImm(Register::Rdi, 0)));
+            }
+            None if self
+                .program
+                .functions
+                .iter()
+                .any(|function| function.name == "main") =>
+            {
+                text.push(Instruction::Call("main".to_string()));
+                text.extend(exit(Instruction::Mov(Register::Rdi, Register::Rax)));
+            }
+            None => text.extend(exit(Instruction::MovImm(Register::Rdi, 0))),
+        }
+        text
+    }
+}
+
+/// Checks that values of `ty` fit in a register, pointers are 64-bit addresses
+fn x86_type(ty: &ssa::Type, token: &Token) -> Result<(), BitBoxError> {
+    match ty {
+        ssa::Type::Unsigned(bits) | ssa::Type::Signed(bits) if *bits <= 64 => Ok(()),
+        ssa::Type::Pointer(_) | ssa::Type::Void => Ok(()),
+        ssa::Type::Unsigned(_)
+        | ssa::Type::Signed(_)
+        | ssa::Type::Float(_)
+        | ssa::Type::Array(..) => Err(unsupported(token, format!("type {}", ty))),
+    }
+}
+
+fn unsupported(token: &Token, feature: String) -> BitBoxError {
+    BitBoxError::UnsupportedByTarget {
+        token: token.clone(),
+        target: TARGET,
+        feature,
+    }
+}
+
+/// Label of the stub an import is called through
+fn stub_label(spec: &ssa::FunctionSpec) -> String {
+    format!("{}.{}", spec.module_name.lexeme, spec.name.lexeme)
+}
+
+/// Code of the stub for an import, `None` for the ones with no stub
+fn stub(spec: &ssa::FunctionSpec) -> Option<Vec<Instruction>> {
+    match (spec.module_name.lexeme.as_str(), spec.name.lexeme.as_str()) {
+        // `write(STDOUT, pointer, len)`, the arguments move one register along
+        ("core", "write") => Some(vec![
+            Instruction::Mov(Register::Rdx, Register::Rsi),
+            Instruction::Mov(Register::Rsi, Register::Rdi),
+            Instruction::MovImm(Register::Rdi, STDOUT),
+            Instruction::MovImm(Register::Rax, SYS_WRITE),
+            Instruction::Syscall,
+            Instruction::Ret,
+        ]),
+        // `exit(status)`, the status is already in `rdi`
+        ("core", "exit") => Some(vec![
+            Instruction::MovImm(Register::Rax, SYS_EXIT),
+            Instruction::Syscall,
+        ]),
+        _ => None,
+    }
+}
+
+/// Label of a block, local to the object file. The entry block is labelled with the
+/// function's name. Names can't contain a `.`, so separating the function and the label with
+/// one keeps the labels of different blocks and the `.Lstr_` labels of strings apart.
+fn block_label(function: &Function, block: &BasicBlock) -> String {
+    match &block.label {
+        Some(label) => format!(".L{}.{}", function.name, label.lexeme),
+        None => function.name.clone(),
+    }
+}
+
+struct FunctionLowering<'a> {
+    program: &'a ssa::Program,
+    constants: &'a HashMap<&'a str, Constant>,
+    function: &'a Function,
+    /// Stack slot of every parameter and every value the function defines
+    slots: HashMap<&'a str, Memory>,
+    /// Bytes below `rbp` the slots take, rounded up to keep `rsp` 16 byte aligned
+    frame: i32,
+    text: Vec<Item>,
+    /// Copies for phis on edges that can't be put in the predecessor, emitted after the
+    /// function's blocks
+    stubs: Vec<Item>,
+}
+
+impl<'a> FunctionLowering<'a> {
+    fn new(
+        program: &'a ssa::Program,
+        constants: &'a HashMap<&'a str, Constant>,
+        function: &'a Function,
+    ) -> Result<Self, BitBoxError> {
+        let mut slots = HashMap::new();
+        let destinations = function
+            .blocks
+            .iter()
+            .flat_map(|block| block.instructions.iter())
+            .filter_map(|instruction| instruction.destination())
+            .filter(|variable| variable.ty != ssa::Type::Void);
+        for variable in function.params.iter().chain(destinations) {
+            x86_type(&variable.ty, &variable.name)?;
+            let memory = Memory {
+                base: Register::Rbp,
+                displacement: -8 * (slots.len() as i32 + 1),
+            };
+            slots.insert(variable.name.lexeme.as_str(), memory);
+        }
+        let frame = (8 * slots.len() as i32 + 15) / 16 * 16;
+        Ok(Self {
+            program,
+            constants,
+            function,
+            slots,
+            frame,
+            text: vec![],
+            stubs: vec![],
+        })
+    }
+
+    fn lower(mut self) -> Result<Vec<Item>, BitBoxError> {
+        self.text.push(Item::Label(self.function.name.clone()));
+        self.emit(Instruction::Push(Register::Rbp));
+        self.emit(Instruction::Mov(Register::Rbp, Register::Rsp));
+        if self.frame > 0 {
+            self.emit(Instruction::SubImm(Register::Rsp, self.frame));
+        }
+        // Arguments after the sixth are above the return address and the saved `rbp`
+        for (index, param) in self.function.params.iter().enumerate() {
+            let slot = self.slot(&param.name);
+            match Register::ARGUMENTS.get(index) {
+                Some(register) => self.emit(Instruction::Store(slot, *register)),
+                None => {
+                    let displacement = 16 + 8 * (index - Register::ARGUMENTS.len()) as i32;
+                    let memory = Memory {
+                        base: Register::Rbp,
+                        displacement,
+                    };
+                    self.emit(Instruction::Load(Register::Rax, memory));
+                    self.emit(Instruction::Store(slot, Register::Rax));
+                }
+            }
+        }
+
+        for (position, block) in self.function.blocks.iter().enumerate() {
+            if block.label.is_some() {
+                self.text
+                    .push(Item::Label(block_label(self.function, block)));
+            }
+            for instruction in block.instructions.iter() {
+                self.text.push(Item::Comment(instruction.to_string()));
+                self.instruction(block, position, instruction)?;
+            }
+        }
+        let mut text = self.text;
+        text.extend(self.stubs);
+        Ok(text)
+    }
+
+    fn emit(&mut self, instruction: Instruction) {
+        self.text.push(Item::Instruction(instruction));
+    }
+
+    fn slot(&self, name: &Token) -> Memory {
+        self.slots[name.lexeme.as_str()]
+    }
+
+    fn instruction(
+        &mut self,
+        block: &BasicBlock,
+        position: usize,
+        instruction: &ssa::Instruction,
+    ) -> Result<(), BitBoxError> {
+        match instruction {
+            // Phis are lowered to copies on the edges into their block
+            ssa::Instruction::Phi(..) => {}
+            ssa::Instruction::Assign(variable, value) => {
+                self.load(value, &variable.ty, Register::Rax);
+                self.emit(Instruction::Store(self.slot(&variable.name), Register::Rax));
+            }
+            ssa::Instruction::Add(variable, lhs, rhs) => self.binary(
+                Instruction::Add(Register::Rax, Register::Rcx),
+                variable,
+                lhs,
+                rhs,
+            ),
+            ssa::Instruction::Sub(variable, lhs, rhs) => self.binary(
+                Instruction::Sub(Register::Rax, Register::Rcx),
+                variable,
+                lhs,
+                rhs,
+            ),
+            ssa::Instruction::Mul(variable, lhs, rhs) => self.binary(
+                Instruction::Imul(Register::Rax, Register::Rcx),
+                variable,
+                lhs,
+                rhs,
+            ),
+            ssa::Instruction::Cmp(variable, condition, lhs, rhs) => {
+                self.load(lhs, &variable.ty, Register::Rax);
+                self.load(rhs, &variable.ty, Register::Rcx);
+                self.emit(Instruction::Cmp(Register::Rax, Register::Rcx));
+                let signed = matches!(variable.ty, ssa::Type::Signed(_));
+                let condition = match (condition, signed) {
+                    (ssa::Condition::Eq, _) => Condition::Equal,
+                    (ssa::Condition::Ne, _) => Condition::NotEqual,
+                    (ssa::Condition::Lt, true) => Condition::Less,
+                    (ssa::Condition::Le, true) => Condition::LessOrEqual,
+                    (ssa::Condition::Gt, true) => Condition::Greater,
+                    (ssa::Condition::Ge, true) => Condition::GreaterOrEqual,
+                    (ssa::Condition::Lt, false) => Condition::Below,
+                    (ssa::Condition::Le, false) => Condition::BelowOrEqual,
+                    (ssa::Condition::Gt, false) => Condition::Above,
+                    (ssa::Condition::Ge, false) => Condition::AboveOrEqual,
+                };
+                self.emit(Instruction::Set(condition, Register::Rax));
+                self.emit(Instruction::Movzx8(Register::Rax));
+                self.emit(Instruction::Store(self.slot(&variable.name), Register::Rax));
+            }
+            ssa::Instruction::Jump(label) => self.jump(block, label),
+            ssa::Instruction::JumpIf(condition, label) => {
+                match condition {
+                    Operand::Constant(token) => {
+                        let taken = literal::Literal::parse(token)
+                            .map(|literal| literal != literal::Literal::Integer(0))
+                            .expect("constants are checked by the parser");
+                        if taken {
+                            self.jump(block, label);
+                            return Ok(());
+                        }
+                    }
+                    Operand::Variable(name) => {
+                        let r#type = self.type_of(name);
+                        self.load(condition, &r#type, Register::Rax);
+                        let target = self.branch_target(block, label);
+                        self.emit(Instruction::Test(Register::Rax, Register::Rax));
+                        self.emit(Instruction::Jcc(Condition::NotEqual, target));
+                    }
+                }
+                // Falls through to the next block
+                if let Some(next) = self.function.blocks.get(position + 1) {
+                    self.copies(block, next);
+                }
+            }
+            ssa::Instruction::Return(ssa::Type::Void, _) => {
+                // `main` returning void exits with 0
+                self.emit(Instruction::MovImm(Register::Rax, 0));
+                self.ret();
+            }
+            ssa::Instruction::Return(ty, value) => {
+                self.load(value, ty, Register::Rax);
+                self.ret();
+            }
+            ssa::Instruction::Call(variable, name, arguments) => {
+                self.call(variable, name, arguments)?
+            }
+        }
+        Ok(())
+    }
+
+    fn ret(&mut self) {
+        self.emit(Instruction::Mov(Register::Rsp, Register::Rbp));
+        self.emit(Instruction::Pop(Register::Rbp));
+        self.emit(Instruction::Ret);
+    }
+
+    /// `rax = rax <op> rcx` with the operands loaded first and the result wrapped to the
+    /// width of its type
+    fn binary(
+        &mut self,
+        operation: Instruction,
+        variable: &ssa::Variable,
+        lhs: &Operand,
+        rhs: &Operand,
+    ) {
+        self.load(lhs, &variable.ty, Register::Rax);
+        self.load(rhs, &variable.ty, Register::Rcx);
+        self.emit(operation);
+        let unused = 64
+            - match variable.ty {
+                ssa::Type::Unsigned(bits) | ssa::Type::Signed(bits) => bits,
+                _ => 64,
+            };
+        if unused > 0 {
+            self.emit(Instruction::Shl(Register::Rax, unused));
+            match variable.ty {
+                ssa::Type::Signed(_) => self.emit(Instruction::Sar(Register::Rax, unused)),
+                _ => self.emit(Instruction::Shr(Register::Rax, unused)),
+            }
+        }
+        self.emit(Instruction::Store(self.slot(&variable.name), Register::Rax));
+    }
+
+    /// Puts the operand in `register`, string constants are their address
+    fn load(&mut self, operand: &Operand, ty: &ssa::Type, register: Register) {
+        match operand {
+            Operand::Variable(name) => {
+                if let Some(slot) = self.slots.get(name.lexeme.as_str()) {
+                    self.emit(Instruction::Load(register, *slot));
+                    return;
+                }
+                match &self.constants[name.lexeme.as_str()] {
+                    Constant::Number(value) => self.emit(Instruction::MovImm(register, *value)),
+                    Constant::String(label) => self.emit(Instruction::Lea(register, label.clone())),
+                }
+            }
+            Operand::Constant(token) => {
+                let value = literal::typed_value(token, ty)
+                    .expect("constants are checked by ssa::typeck")
+                    .bits();
+                self.emit(Instruction::MovImm(register, value));
+            }
+        }
+    }
+
+    /// Type of a named value, the type `@jumpif` reads its condition as
+    fn type_of(&self, name: &Token) -> ssa::Type {
+        let variables = self.function.params.iter().chain(
+            self.function
+                .blocks
+                .iter()
+                .flat_map(|block| block.instructions.iter())
+                .filter_map(|instruction| instruction.destination()),
+        );
+        variables
+            .map(|variable| (&variable.name, &variable.ty))
+            .chain(
+                self.program
+                    .constants
+                    .iter()
+                    .map(|constant| (&constant.name, &constant.ty)),
+            )
+            .find(|(other, _)| other.lexeme == name.lexeme)
+            .map(|(_, ty)| ty.clone())
+            .expect("names are checked by ssa::verify")
+    }
+
+    fn successor(&self, label: &Token) -> &'a BasicBlock {
+        ssa::find_block(&self.function.blocks, label).expect("labels are checked by the parser")
+    }
+
+    fn jump(&mut self, block: &BasicBlock, label: &Token) {
+        let successor = self.successor(label);
+        self.copies(block, successor);
+        self.emit(Instruction::Jmp(block_label(self.function, successor)));
+    }
+
+    /// Where a conditional branch to `label` goes: the block itself, or a stub that does the
+    /// copies for its phis and then jumps to it
+    fn branch_target(&mut self, block: &BasicBlock, label: &Token) -> String {
+        let successor = self.successor(label);
+        let target = block_label(self.function, successor);
+        if !has_phis(successor) {
+            return target;
+        }
+        let stub = format!("{}.{}", target, self.stubs.len());
+        let text = std::mem::replace(&mut self.text, vec![Item::Label(stub.clone())]);
+        self.copies(block, successor);
+        self.emit(Instruction::Jmp(target));
+        let copies = std::mem::replace(&mut self.text, text);
+        self.stubs.extend(copies);
+        stub
+    }
+
+    /// Sets the phis of `successor` to their values for the edge from `block`. The values are
+    /// all pushed before any phi is popped, so phis can read each other.
+    fn copies(&mut self, block: &BasicBlock, successor: &BasicBlock) {
+        let mut destinations = vec![];
+        for instruction in successor.instructions.iter() {
+            let ssa::Instruction::Phi(variable, incoming) = instruction else {
+                break;
+            };
+            let (value, _, _) = incoming
+                .iter()
+                .find(|(_, _, id)| *id == block.id)
+                .expect("phis have a value for every predecessor");
+            let value = Operand::Variable(value.name.clone());
+            self.load(&value, &variable.ty, Register::Rax);
+            self.emit(Instruction::Push(Register::Rax));
+            destinations.push(self.slot(&variable.name));
+        }
+        for slot in destinations.into_iter().rev() {
+            self.emit(Instruction::Pop(Register::Rax));
+            self.emit(Instruction::Store(slot, Register::Rax));
+        }
+    }
+
+    /// Calls a function or the stub of a `core` import with the System V calling convention.
+    /// Arguments after the sixth are pushed last to first, with padding below them when
+    /// there's an odd number of them so `rsp` stays 16 byte aligned at the call.
+    fn call(
+        &mut self,
+        variable: &ssa::Variable,
+        name: &Token,
+        arguments: &[Operand],
+    ) -> Result<(), BitBoxError> {
+        let (params, label) = self.callee(name)?;
+        let registers = Register::ARGUMENTS.len().min(arguments.len());
+        let pushed = arguments.len() - registers;
+        let padding = 8 * (pushed % 2) as i32;
+        if padding > 0 {
+            self.emit(Instruction::SubImm(Register::Rsp, padding));
+        }
+        for (argument, ty) in arguments.iter().zip(params.iter()).skip(registers).rev() {
+            self.load(argument, ty, Register::Rax);
+            self.emit(Instruction::Push(Register::Rax));
+        }
+        for ((argument, ty), register) in arguments
+            .iter()
+            .zip(params.iter())
+            .zip(Register::ARGUMENTS.iter())
+        {
+            self.load(argument, ty, *register);
+        }
+        self.emit(Instruction::Call(label));
+        let popped = 8 * pushed as i32 + padding;
+        if popped > 0 {
+            self.emit(Instruction::AddImm(Register::Rsp, popped));
+        }
+        if let Some(slot) = self.slots.get(variable.name.lexeme.as_str()).copied() {
+            self.emit(Instruction::Store(slot, Register::Rax));
+        }
+        Ok(())
+    }
+
+    /// Parameter types of the function called `name` and the label it is called at
+    fn callee(&self, name: &Token) -> Result<(Vec<ssa::Type>, String), BitBoxError> {
+        if let Some(function) = self
+            .program
+            .functions
+            .iter()
+            .find(|function| function.name == name.lexeme)
+        {
+            let params = function.params.iter().map(|param| param.ty.clone());
+            return Ok((params.collect(), function.name.clone()));
+        }
+        let spec = self
+            .program
+            .imports
+            .iter()
+            .find_map(|import| match import {
+                Import::Function(spec) if spec.name.lexeme == name.lexeme => Some(spec),
+                Import::Function(_) => None,
+            })
+            .ok_or_else(|| BitBoxError::UndefinedFunction(name.clone()))?;
+        if stub(spec).is_none() {
+            return Err(unsupported(
+                name,
+                format!(
+                    "the import {}::{}",
+                    spec.module_name.lexeme, spec.name.lexeme
+                ),
+            ));
+        }
+        Ok((spec.params.clone(), stub_label(spec)))
+    }
+}
+
+fn has_phis(block: &BasicBlock) -> bool {
+    matches!(block.instructions.first(), Some(ssa::Instruction::Phi(..)))
+}
@@ -0,0 +1,368 @@
+//! The subset of x86-64 the backend selects and its machine code. Every instruction has one
+//! encoding, so its size doesn't depend on where its labels are and the text can be laid out
+//! before it is encoded. Branches, calls and `lea` always take a 32-bit displacement.
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+/// Virtual address of every label in the executable
+pub type SymbolTable = HashMap<String, u64>;
+
+/// The general purpose registers, numbered as in their encoding
+// Values live on the stack, so only a few registers are selected
+#[allow(dead_code)]
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Register {
+    Rax = 0,
+    Rcx = 1,
+    Rdx = 2,
+    Rbx = 3,
+    Rsp = 4,
+    Rbp = 5,
+    Rsi = 6,
+    Rdi = 7,
+    R8 = 8,
+    R9 = 9,
+    R10 = 10,
+    R11 = 11,
+    R12 = 12,
+    R13 = 13,
+    R14 = 14,
+    R15 = 15,
+}
+
+impl Register {
+    /// Where the System V calling convention passes the first integer arguments
+    pub const ARGUMENTS: [Register; 6] = [
+        Register::Rdi,
+        Register::Rsi,
+        Register::Rdx,
+        Register::Rcx,
+        Register::R8,
+        Register::R9,
+    ];
+
+    /// The low three bits that go in a ModRM byte or an opcode
+    fn low(self) -> u8 {
+        self as u8 & 0b111
+    }
+
+    /// The high bit that goes in a REX prefix
+    fn high(self) -> u8 {
+        self as u8 >> 3
+    }
+
+    /// Name of the register's low byte
+    fn byte_name(self) -> &'static str {
+        match self {
+            Self::Rax => "al",
+            Self::Rcx => "cl",
+            Self::Rdx => "dl",
+            Self::Rbx => "bl",
+            Self::Rsp => "spl",
+            Self::Rbp => "bpl",
+            Self::Rsi => "sil",
+            Self::Rdi => "dil",
+            Self::R8 => "r8b",
+            Self::R9 => "r9b",
+            Self::R10 => "r10b",
+            Self::R11 => "r11b",
+            Self::R12 => "r12b",
+            Self::R13 => "r13b",
+            Self::R14 => "r14b",
+            Self::R15 => "r15b",
+        }
+    }
+}
+
+impl Display for Register {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Rax => "rax",
+            Self::Rcx => "rcx",
+            Self::Rdx => "rdx",
+            Self::Rbx => "rbx",
+            Self::Rsp => "rsp",
+            Self::Rbp => "rbp",
+            Self::Rsi => "rsi",
+            Self::Rdi => "rdi",
+            Self::R8 => "r8",
+            Self::R9 => "r9",
+            Self::R10 => "r10",
+            Self::R11 => "r11",
+            Self::R12 => "r12",
+            Self::R13 => "r13",
+            Self::R14 => "r14",
+            Self::R15 => "r15",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Condition codes, numbered as in the low nibble of `jcc` and `setcc`
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Condition {
+    /// Unsigned `<`
+    Below = 0x2,
+    /// Unsigned `>=`
+    AboveOrEqual = 0x3,
+    Equal = 0x4,
+    NotEqual = 0x5,
+    /// Unsigned `<=`
+    BelowOrEqual = 0x6,
+    /// Unsigned `>`
+    Above = 0x7,
+    Less = 0xc,
+    GreaterOrEqual = 0xd,
+    LessOrEqual = 0xe,
+    Greater = 0xf,
+}
+
+impl Condition {
+    /// What follows `j` and `set` in the mnemonic
+    fn suffix(self) -> &'static str {
+        match self {
+            Self::Below => "b",
+            Self::AboveOrEqual => "ae",
+            Self::Equal => "e",
+            Self::NotEqual => "ne",
+            Self::BelowOrEqual => "be",
+            Self::Above => "a",
+            Self::Less => "l",
+            Self::GreaterOrEqual => "ge",
+            Self::LessOrEqual => "le",
+            Self::Greater => "g",
+        }
+    }
+}
+
+/// `[base + displacement]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Memory {
+    pub base: Register,
+    pub displacement: i32,
+}
+
+/// A quadword at the address, `qword ptr [rbp - 8]`
+impl Display for Memory {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.displacement {
+            0 => write!(f, "qword ptr [{}]", self.base),
+            displacement if displacement < 0 => {
+                write!(
+                    f,
+                    "qword ptr [{} - {}]",
+                    self.base,
+                    displacement.unsigned_abs()
+                )
+            }
+            displacement => write!(f, "qword ptr [{} + {}]", self.base, displacement),
+        }
+    }
+}
+
+/// Operands are in Intel order, the destination first. Everything but `Set` works on the
+/// whole 64-bit register.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Instruction {
+    Mov(Register, Register),
+    /// `movabs`, the immediate is always 64 bits
+    MovImm(Register, u64),
+    Load(Register, Memory),
+    Store(Memory, Register),
+    /// Address of a label, relative to the instruction pointer
+    Lea(Register, String),
+    Add(Register, Register),
+    Sub(Register, Register),
+    Imul(Register, Register),
+    AddImm(Register, i32),
+    SubImm(Register, i32),
+    Shl(Register, u8),
+    /// Logical shift right
+    Shr(Register, u8),
+    /// Arithmetic shift right
+    Sar(Register, u8),
+    Cmp(Register, Register),
+    Test(Register, Register),
+    /// Sets the low byte of the register to 1 if the condition holds and 0 otherwise
+    Set(Condition, Register),
+    /// Zero extends the low byte of the register to the whole register
+    Movzx8(Register),
+    Jmp(String),
+    Jcc(Condition, String),
+    Call(String),
+    Ret,
+    Push(Register),
+    Pop(Register),
+    Syscall,
+}
+
+impl Instruction {
+    /// Machine code of the instruction when it is at `address`
+    pub fn to_bytes(&self, address: u64, symbol_table: &SymbolTable) -> Vec<u8> {
+        // Displacement from the end of the instruction to `label`, which is its last 4 bytes
+        let relative = |label: &str, size: usize| {
+            let target = symbol_table[label] as i64;
+            let next = (address + size as u64) as i64;
+            i32::try_from(target - next).expect("the text is smaller than 2GiB")
+        };
+        match self {
+            Self::Mov(des, src) => rex_rm(0x89, *src, *des),
+            Self::MovImm(des, value) => {
+                let mut bytes = vec![0x48 | des.high(), 0xb8 + des.low()];
+                bytes.extend(value.to_le_bytes());
+                bytes
+            }
+            Self::Load(des, memory) => rex_memory(&[0x8b], *des, *memory),
+            Self::Store(memory, src) => rex_memory(&[0x89], *src, *memory),
+            Self::Lea(des, label) => {
+                // ModRM with mod 00 and r/m 101 is `[rip + disp32]`
+                let mut bytes = vec![0x48 | des.high() << 2, 0x8d, des.low() << 3 | 0b101];
+                bytes.extend(relative(label, 7).to_le_bytes());
+                bytes
+            }
+            Self::Add(des, src) => rex_rm(0x01, *src, *des),
+            Self::Sub(des, src) => rex_rm(0x29, *src, *des),
+            Self::Imul(des, src) => {
+                let mut bytes = rex_rm(0xaf, *des, *src);
+                bytes.insert(1, 0x0f);
+                bytes
+            }
+            Self::AddImm(des, value) => extension_imm32(0x81, 0, *des, *value),
+            Self::SubImm(des, value) => extension_imm32(0x81, 5, *des, *value),
+            Self::Shl(des, count) => extension_imm8(0xc1, 4, *des, *count),
+            Self::Shr(des, count) => extension_imm8(0xc1, 5, *des, *count),
+            Self::Sar(des, count) => extension_imm8(0xc1, 7, *des, *count),
+            Self::Cmp(lhs, rhs) => rex_rm(0x39, *rhs, *lhs),
+            Self::Test(lhs, rhs) => rex_rm(0x85, *rhs, *lhs),
+            // The REX prefix makes the low byte of `rsp` to `rdi` `spl` to `dil` instead of
+            // `ah` to `bh`
+            Self::Set(condition, des) => vec![
+                0x40 | des.high(),
+                0x0f,
+                0x90 + *condition as u8,
+                0b11_000_000 | des.low(),
+            ],
+            Self::Movzx8(des) => {
+                let mut bytes = rex_rm(0xb6, *des, *des);
+                bytes.insert(1, 0x0f);
+                bytes
+            }
+            Self::Jmp(label) => {
+                let mut bytes = vec![0xe9];
+                bytes.extend(relative(label, 5).to_le_bytes());
+                bytes
+            }
+            Self::Jcc(condition, label) => {
+                let mut bytes = vec![0x0f, 0x80 + *condition as u8];
+                bytes.extend(relative(label, 6).to_le_bytes());
+                bytes
+            }
+            Self::Call(label) => {
+                let mut bytes = vec![0xe8];
+                bytes.extend(relative(label, 5).to_le_bytes());
+                bytes
+            }
+            Self::Ret => vec![0xc3],
+            Self::Push(src) => optional_rex(src.high(), 0x50 + src.low()),
+            Self::Pop(des) => optional_rex(des.high(), 0x58 + des.low()),
+            Self::Syscall => vec![0x0f, 0x05],
+        }
+    }
+
+    /// Size of the machine code, which is the same wherever the instruction is
+    pub fn size(&self) -> usize {
+        match self {
+            Self::Lea(..) => 7,
+            Self::Jmp(..) | Self::Call(..) => 5,
+            Self::Jcc(..) => 6,
+            _ => self.to_bytes(0, &SymbolTable::new()).len(),
+        }
+    }
+}
+
+/// GNU assembler syntax with `.intel_syntax noprefix`
+impl Display for Instruction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Mov(des, src) => write!(f, "mov {}, {}", des, src),
+            Self::MovImm(des, value) => write!(f, "movabs {}, {}", des, value),
+            Self::Load(des, memory) => write!(f, "mov {}, {}", des, memory),
+            Self::Store(memory, src) => write!(f, "mov {}, {}", memory, src),
+            Self::Lea(des, label) => write!(f, "lea {}, [rip + {}]", des, label),
+            Self::Add(des, src) => write!(f, "add {}, {}", des, src),
+            Self::Sub(des, src) => write!(f, "sub {}, {}", des, src),
+            Self::Imul(des, src) => write!(f, "imul {}, {}", des, src),
+            Self::AddImm(des, value) => write!(f, "add {}, {}", des, value),
+            Self::SubImm(des, value) => write!(f, "sub {}, {}", des, value),
+            Self::Shl(des, count) => write!(f, "shl {}, {}", des, count),
+            Self::Shr(des, count) => write!(f, "shr {}, {}", des, count),
+            Self::Sar(des, count) => write!(f, "sar {}, {}", des, count),
+            Self::Cmp(lhs, rhs) => write!(f, "cmp {}, {}", lhs, rhs),
+            Self::Test(lhs, rhs) => write!(f, "test {}, {}", lhs, rhs),
+            Self::Set(condition, des) => {
+                write!(f, "set{} {}", condition.suffix(), des.byte_name())
+            }
+            Self::Movzx8(des) => write!(f, "movzx {}, {}", des, des.byte_name()),
+            Self::Jmp(label) => write!(f, "jmp {}", label),
+            Self::Jcc(condition, label) => write!(f, "j{} {}", condition.suffix(), label),
+            Self::Call(label) => write!(f, "call {}", label),
+            Self::Ret => write!(f, "ret"),
+            Self::Push(src) => write!(f, "push {}", src),
+            Self::Pop(des) => write!(f, "pop {}", des),
+            Self::Syscall => write!(f, "syscall"),
+        }
+    }
+}
+
+/// `REX.W opcode ModRM` with both operands registers, `reg` in the reg field
+fn rex_rm(opcode: u8, reg: Register, rm: Register) -> Vec<u8> {
+    vec![
+        0x48 | reg.high() << 2 | rm.high(),
+        opcode,
+        0b11_000_000 | reg.low() << 3 | rm.low(),
+    ]
+}
+
+/// `REX.W opcode ModRM [SIB] disp32` with `[base + disp32]` as the r/m operand
+fn rex_memory(opcode: &[u8], reg: Register, memory: Memory) -> Vec<u8> {
+    let mut bytes = vec![0x48 | reg.high() << 2 | memory.base.high()];
+    bytes.extend(opcode);
+    bytes.push(0b10_000_000 | reg.low() << 3 | memory.base.low());
+    // An r/m of 100 means a SIB byte follows, so `rsp` and `r12` need one that names them
+    if memory.base.low() == Register::Rsp.low() {
+        bytes.push(0x24);
+    }
+    bytes.extend(memory.displacement.to_le_bytes());
+    bytes
+}
+
+/// `REX.W opcode ModRM imm32` where the reg field extends the opcode
+fn extension_imm32(opcode: u8, extension: u8, rm: Register, value: i32) -> Vec<u8> {
+    let mut bytes = vec![
+        0x48 | rm.high(),
+        opcode,
+        0b11_000_000 | extension << 3 | rm.low(),
+    ];
+    bytes.extend(value.to_le_bytes());
+    bytes
+}
+
+/// `REX.W opcode ModRM imm8` where the reg field extends the opcode
+fn extension_imm8(opcode: u8, extension: u8, rm: Register, value: u8) -> Vec<u8> {
+    vec![
+        0x48 | rm.high(),
+        opcode,
+        0b11_000_000 | extension << 3 | rm.low(),
+        value,
+    ]
+}
+
+/// A one byte opcode with the register in it, `r8` to `r15` need a REX prefix
+fn optional_rex(high: u8, opcode: u8) -> Vec<u8> {
+    match high {
+        0 => vec![opcode],
+        _ => vec![0x41, opcode],
+    }
+}
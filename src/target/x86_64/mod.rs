@@ -0,0 +1,12 @@
+//! Native code for x86-64 Linux, written straight to a static ELF executable without an
+//! assembler or a linker.
+//!
+//! The executable starts at `_start`, which calls `main` and exits with what it returns.
+//! String constants are placed after the text and are addressed relative to `rip`.
+mod elf;
+mod emitter;
+mod instruction;
+#[cfg(test)]
+mod test;
+
+pub use emitter::Emitter;
@@ -0,0 +1,372 @@
+use super::instruction::{Condition, Instruction, Memory, Register, SymbolTable};
+use super::Emitter;
+use crate::interp::Interpreter;
+use crate::lexer::lex;
+use crate::parser;
+use crate::ssa::literal::Value;
+use crate::ssa::{self, Program};
+use std::process::{Command, Output};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+fn checked(input: &str) -> Program {
+    let tokens = lex(input);
+    let mut program = parser::Parser::new(tokens)
+        .parse()
+        .expect("failed to parse program");
+    ssa::cfg::build(&mut program)
+        .and_then(|_| ssa::phi::validate(&program))
+        .and_then(|_| ssa::verify::verify(&program))
+        .and_then(|_| ssa::typeck::check(&program))
+        .and_then(|_| ssa::const_eval::evaluate(&mut program))
+        .expect("failed to check program");
+    program
+}
+
+/// Writes the executable to a file of its own and runs it
+fn execute(bytes: &[u8]) -> Output {
+    use std::os::unix::fs::PermissionsExt;
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+    let path = std::env::temp_dir().join(format!(
+        "bitbox-test-{}-{}",
+        std::process::id(),
+        COUNT.fetch_add(1, Ordering::Relaxed)
+    ));
+    std::fs::write(&path, bytes).expect("failed to write executable");
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))
+        .expect("failed to make the file executable");
+    // Another test forking while the file is open for writing makes it busy for a moment
+    let output = loop {
+        match Command::new(&path).output() {
+            Err(err) if err.kind() == std::io::ErrorKind::ExecutableFileBusy => continue,
+            output => break output.expect("failed to run executable"),
+        }
+    };
+    std::fs::remove_file(&path).expect("failed to remove executable");
+    output
+}
+
+/// Compiles the program with an entry that calls `function` and returns what it returned
+fn call(program: &Program, function: &str, arguments: &[Value]) -> u64 {
+    let arguments = arguments.iter().map(Value::bits).collect();
+    let bytes = Emitter::new(program.clone())
+        .with_entry(function, arguments)
+        .emit()
+        .expect("failed to emit program")
+        .to_bytes();
+    let output = execute(&bytes);
+    assert_eq!(output.status.code(), Some(0));
+    u64::from_le_bytes(output.stdout.try_into().expect("the entry writes 8 bytes"))
+}
+
+/// The executable must return what the interpreter does, as the same bits
+fn assert_matches_interpreter(program: &Program, function: &str, arguments: &[Value]) {
+    let expected = Interpreter::new(program)
+        .call(function, arguments)
+        .expect("interpreter trapped")
+        .expect("function returns a value");
+    assert_eq!(
+        call(program, function, arguments),
+        expected.bits(),
+        "{function}({arguments:?})"
+    );
+}
+
+pub fn snapshot_asm(input: &str) -> String {
+    match Emitter::new(checked(input)).emit() {
+        Ok(executable) => executable.to_string(),
+        Err(err) => format!("{:#?}", err),
+    }
+}
+
+macro_rules! snapshot {
+    ($name:tt, $path:tt) => {
+        #[test]
+        fn $name() {
+            let contents = include_str!($path);
+            let mut settings = insta::Settings::clone_current();
+            settings.set_snapshot_path("testdata/output/");
+            settings.bind(|| {
+                insta::assert_snapshot!(snapshot_asm(contents));
+            });
+        }
+    };
+}
+
+snapshot!(asm_phi, "../../../snapshots/phi.bitbox");
+snapshot!(asm_calls, "../../../snapshots/calls.bitbox");
+snapshot!(asm_label_names, "../../../snapshots/label_names.bitbox");
+snapshot!(
+    asm_hello_world,
+    "../wasm/examples/hello_world/hello_world.bitbox"
+);
+
+#[test]
+fn encodings() {
+    let slot = |displacement| Memory {
+        base: Register::Rbp,
+        displacement,
+    };
+    let cases = [
+        (
+            Instruction::Mov(Register::Rax, Register::Rcx),
+            vec![0x48, 0x89, 0xc8],
+        ),
+        (
+            Instruction::Mov(Register::R8, Register::Rdi),
+            vec![0x49, 0x89, 0xf8],
+        ),
+        (
+            Instruction::MovImm(Register::R9, 1),
+            vec![0x49, 0xb9, 1, 0, 0, 0, 0, 0, 0, 0],
+        ),
+        (
+            Instruction::Load(Register::Rax, slot(-8)),
+            vec![0x48, 0x8b, 0x85, 0xf8, 0xff, 0xff, 0xff],
+        ),
+        (
+            Instruction::Store(slot(-16), Register::R9),
+            vec![0x4c, 0x89, 0x8d, 0xf0, 0xff, 0xff, 0xff],
+        ),
+        (
+            Instruction::Load(
+                Register::Rcx,
+                Memory {
+                    base: Register::Rsp,
+                    displacement: 8,
+                },
+            ),
+            vec![0x48, 0x8b, 0x8c, 0x24, 8, 0, 0, 0],
+        ),
+        (
+            Instruction::Imul(Register::Rax, Register::Rcx),
+            vec![0x48, 0x0f, 0xaf, 0xc1],
+        ),
+        (
+            Instruction::SubImm(Register::Rsp, 16),
+            vec![0x48, 0x81, 0xec, 16, 0, 0, 0],
+        ),
+        (
+            Instruction::Shl(Register::Rax, 32),
+            vec![0x48, 0xc1, 0xe0, 32],
+        ),
+        (
+            Instruction::Sar(Register::Rax, 32),
+            vec![0x48, 0xc1, 0xf8, 32],
+        ),
+        (
+            Instruction::Cmp(Register::Rax, Register::Rcx),
+            vec![0x48, 0x39, 0xc8],
+        ),
+        (
+            Instruction::Test(Register::Rax, Register::Rax),
+            vec![0x48, 0x85, 0xc0],
+        ),
+        (
+            Instruction::Set(Condition::Less, Register::Rax),
+            vec![0x40, 0x0f, 0x9c, 0xc0],
+        ),
+        (
+            Instruction::Movzx8(Register::Rax),
+            vec![0x48, 0x0f, 0xb6, 0xc0],
+        ),
+        (Instruction::Push(Register::R12), vec![0x41, 0x54]),
+        (Instruction::Pop(Register::Rbp), vec![0x5d]),
+        (Instruction::Syscall, vec![0x0f, 0x05]),
+    ];
+    for (instruction, bytes) in cases {
+        assert_eq!(
+            instruction.to_bytes(0, &SymbolTable::new()),
+            bytes,
+            "{instruction:?}"
+        );
+        assert_eq!(instruction.size(), bytes.len(), "{instruction:?}");
+    }
+
+    // Displacements are from the end of the instruction
+    let symbol_table = SymbolTable::from([("target".to_string(), 0x100)]);
+    let jump = Instruction::Jcc(Condition::NotEqual, "target".to_string());
+    assert_eq!(
+        jump.to_bytes(0x200, &symbol_table),
+        [0x0f, 0x85, 0xfa, 0xfe, 0xff, 0xff]
+    );
+    let lea = Instruction::Lea(Register::Rdi, "target".to_string());
+    assert_eq!(
+        lea.to_bytes(0xf9, &symbol_table),
+        [0x48, 0x8d, 0x3d, 0, 0, 0, 0]
+    );
+}
+
+#[test]
+#[cfg_attr(
+    not(all(target_arch = "x86_64", target_os = "linux")),
+    ignore = "runs x86-64 linux executables"
+)]
+fn integers_match_the_interpreter() {
+    let mut program = checked(include_str!("../../../snapshots/interp.bitbox"));
+    // Floats have no instructions selected for them yet
+    program.functions.retain(|function| function.name != "half");
+    let cases = [
+        ("wrap_u8", vec![Value::Unsigned(100)]),
+        ("wrap_s8", vec![Value::Signed(100)]),
+        ("wrap_u64", vec![Value::Unsigned(0)]),
+        ("signed_less", vec![Value::Signed(-1), Value::Signed(1)]),
+        (
+            "unsigned_less",
+            vec![Value::Unsigned(65535), Value::Unsigned(1)],
+        ),
+        ("copy", vec![Value::Signed(-7)]),
+        ("swap", vec![Value::Signed(1)]),
+        ("swap", vec![Value::Signed(2)]),
+        ("swap", vec![Value::Signed(3)]),
+    ];
+    for (function, arguments) in cases {
+        assert_matches_interpreter(&program, function, &arguments);
+    }
+
+    let program = checked(include_str!("../../../snapshots/phi.bitbox"));
+    assert_matches_interpreter(&program, "abs", &[Value::Signed(-5)]);
+    assert_matches_interpreter(&program, "abs", &[Value::Signed(7)]);
+
+    let program = checked(include_str!("../../../snapshots/cmp.bitbox"));
+    for (x, y) in [(-3, 7), (1, 5), (2, 4)] {
+        let arguments = [Value::Signed(x), Value::Unsigned(y)];
+        assert_matches_interpreter(&program, "compare", &arguments);
+    }
+}
+
+#[test]
+#[cfg_attr(
+    not(all(target_arch = "x86_64", target_os = "linux")),
+    ignore = "runs x86-64 linux executables"
+)]
+fn calls() {
+    let program = checked(include_str!("../../../snapshots/calls.bitbox"));
+    assert_matches_interpreter(&program, "main", &[]);
+
+    let program = checked(include_str!("../../../snapshots/pressure.bitbox"));
+    for x in [-4, 0, 9] {
+        assert_matches_interpreter(&program, "pressure", &[Value::Signed(x)]);
+    }
+
+    // Arguments after the sixth are passed on the stack
+    let program = checked(
+        "function sum(a: u64, b: u64, c: u64, d: u64, e: u64, f: u64, g: u64, h: u64) u64 {
+    @sub u64 : ab, a, b
+    @sub u64 : abc, ab, c
+    @sub u64 : abcd, abc, d
+    @sub u64 : abcde, abcd, e
+    @sub u64 : abcdef, abcde, f
+    @sub u64 : abcdefg, abcdef, g
+    @sub u64 : abcdefgh, abcdefg, h
+    @ret u64 : abcdefgh
+}
+
+public function seven(x: u64) u64 {
+    @call u64 : y sum(x, 1, 2, 3, 4, 5, 6, 0)
+    @call u64 : z sum(y, 1, 2, 3, 4, 5, 6, 7)
+    @ret u64 : z
+}
+",
+    );
+    assert_matches_interpreter(&program, "seven", &[Value::Unsigned(100)]);
+}
+
+#[test]
+#[cfg_attr(
+    not(all(target_arch = "x86_64", target_os = "linux")),
+    ignore = "runs x86-64 linux executables"
+)]
+fn runs_hello_world() {
+    let program = checked(include_str!(
+        "../wasm/examples/hello_world/hello_world.bitbox"
+    ));
+    let bytes = Emitter::new(program)
+        .emit()
+        .expect("failed to emit program")
+        .to_bytes();
+    assert_eq!(bytes[..4], *b"\x7fELF");
+    let output = execute(&bytes);
+    assert_eq!(output.status.code(), Some(12));
+    assert_eq!(output.stdout, b"Hello World\n");
+
+    let mut program = checked(include_str!("../../../snapshots/interp.bitbox"));
+    program.functions.retain(|function| function.name != "half");
+    let bytes = Emitter::new(program)
+        .with_entry("quit", vec![])
+        .emit()
+        .expect("failed to emit program")
+        .to_bytes();
+    assert_eq!(execute(&bytes).status.code(), Some(3));
+}
+
+#[test]
+#[cfg_attr(
+    not(all(target_arch = "x86_64", target_os = "linux")),
+    ignore = "runs x86-64 linux executables"
+)]
+fn labels_of_different_blocks_differ() {
+    let program = checked(include_str!("../../../snapshots/label_names.bitbox"));
+    let bytes = Emitter::new(program)
+        .emit()
+        .expect("failed to emit program")
+        .to_bytes();
+    let output = execute(&bytes);
+    assert_eq!(output.status.code(), Some(7));
+    assert_eq!(output.stdout, b"hi\n");
+}
+
+#[test]
+#[cfg_attr(
+    not(all(target_arch = "x86_64", target_os = "linux")),
+    ignore = "assembles and links x86-64 linux executables"
+)]
+fn assembles_labels_of_different_blocks() {
+    let program = checked(include_str!("../../../snapshots/label_names.bitbox"));
+    let asm = Emitter::new(program)
+        .emit()
+        .expect("failed to emit program")
+        .to_string();
+    let path = std::env::temp_dir().join(format!("bitbox-test-asm-{}", std::process::id()));
+    let source = path.with_extension("s");
+    let object = path.with_extension("o");
+    std::fs::write(&source, asm).expect("failed to write assembly");
+    let assembled = Command::new("as")
+        .arg(&source)
+        .arg("-o")
+        .arg(&object)
+        .output()
+        .expect("failed to run as");
+    assert!(
+        assembled.status.success(),
+        "{}",
+        String::from_utf8_lossy(&assembled.stderr)
+    );
+    let linked = Command::new("ld")
+        .arg(&object)
+        .arg("-o")
+        .arg(&path)
+        .output()
+        .expect("failed to run ld");
+    assert!(
+        linked.status.success(),
+        "{}",
+        String::from_utf8_lossy(&linked.stderr)
+    );
+    let output = Command::new(&path)
+        .output()
+        .expect("failed to run executable");
+    for file in [&source, &object, &path] {
+        std::fs::remove_file(file).expect("failed to remove file");
+    }
+    assert_eq!(output.status.code(), Some(7));
+    assert_eq!(output.stdout, b"hi\n");
+}
+
+#[test]
+fn unsupported_types_are_errors() {
+    let program = checked(include_str!("../../../snapshots/interp.bitbox"));
+    let err = Emitter::new(program)
+        .emit()
+        .expect_err("floats are not supported");
+    assert_eq!(err.code(), "E0028");
+}
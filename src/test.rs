@@ -0,0 +1,22 @@
+use std::path::Path;
+
+/// Every program in `snapshots/` with its file name, for tests that check a property of all of
+/// them rather than snapshotting one
+pub fn snapshot_sources() -> Vec<(String, String)> {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("snapshots");
+    let mut sources = std::fs::read_dir(&directory)
+        .expect("failed to read the snapshots directory")
+        .map(|entry| entry.expect("failed to read a snapshots entry").path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "bitbox")
+        })
+        .map(|path| {
+            let name = path.file_name().expect("entries have a name");
+            let source = std::fs::read_to_string(&path).expect("failed to read snapshot");
+            (name.to_string_lossy().into_owned(), source)
+        })
+        .collect::<Vec<_>>();
+    sources.sort();
+    sources
+}